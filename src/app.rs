@@ -1,31 +1,130 @@
+use crate::connection_string::format_key_values;
+use crate::connection_string::host_port_pairs;
+use crate::connection_string::ImportedConnection;
+use crate::data_diff::compare_data;
+use crate::data_diff::DataCompare;
+use crate::data_diff::DataDiff;
+use crate::data_editor::DataEditor;
+use crate::data_editor::PAGE_SIZE;
+use crate::er_diagram::write_png;
+use crate::er_diagram::ErDiagram;
+use crate::find_replace::FindReplace;
+use crate::libpq::lookup_pgpass;
+use crate::libpq::PgService;
+use crate::library::default_library_dir;
+use crate::library::LibraryDraft;
+use crate::library::LibraryEntry;
+use crate::object_search::qualified_object_name;
+use crate::object_search::ObjectSearch;
+use crate::object_search::TreeReveal;
 use crate::postgres::convert_type;
 use crate::postgres::CellValue;
+use crate::query_functions::pg_compare::get_schema_snapshot;
+use crate::query_functions::pg_compare::SchemaSnapshot;
+use crate::query_functions::pg_connection::connect;
+use crate::query_functions::pg_connection::test_connection;
+use crate::query_functions::pg_data::apply_row_changes;
 use crate::query_functions::pg_data::cancel_query;
+use crate::query_functions::pg_data::fetch_text_rows;
 use crate::query_functions::pg_data::get_database_names;
+use crate::query_functions::pg_data::get_diagram_columns;
+use crate::query_functions::pg_data::get_diagram_foreign_keys;
+use crate::query_functions::pg_data::get_index_stats;
 use crate::query_functions::pg_data::get_public_tables;
 use crate::query_functions::pg_data::get_running_queries_data;
+use crate::query_functions::pg_data::get_ssl_status;
+use crate::query_functions::pg_data::get_table_estimates;
+use crate::query_functions::pg_data::get_table_size;
+use crate::query_functions::pg_data::get_table_stats;
 use crate::query_functions::pg_data::DatabaseNames;
+use crate::query_functions::pg_data::DiagramColumn;
+use crate::query_functions::pg_data::DiagramForeignKey;
+use crate::query_functions::pg_data::IndexStats;
 use crate::query_functions::pg_data::PublicTable;
 use crate::query_functions::pg_data::RunningQueriesData;
+use crate::query_functions::pg_data::SslStatus;
+use crate::query_functions::pg_data::TableSize;
+use crate::query_functions::pg_data::TableStats;
+use crate::query_functions::pg_ddl::apply_ddl;
+use crate::query_functions::pg_ddl::get_object_ddl;
+use crate::query_functions::pg_params::QueryParam;
 use crate::query_functions::pg_query_handlers::execute_query;
+use crate::query_functions::pg_schema::get_data_types;
+use crate::query_functions::pg_schema::get_extensions;
+use crate::query_functions::pg_schema::get_relation_oid;
+use crate::query_functions::pg_schema::get_schema_objects;
+use crate::query_functions::pg_schema::get_schemas;
+use crate::query_functions::pg_schema::get_search_objects;
+use crate::query_functions::pg_schema::get_table_details;
+use crate::query_functions::pg_schema::SchemaObject;
+use crate::query_functions::pg_schema::SearchObject;
+use crate::query_functions::pg_schema::TableDetails;
+use crate::query_functions::pg_session::end_transaction;
+use crate::query_functions::pg_session::EditorSession;
+use crate::query_functions::pg_session::SessionSettings;
+use crate::query_functions::pg_session::TransactionStatus;
+use crate::schema_diff::SchemaCompare;
+use crate::sql::classify_statement;
+use crate::sql::prepare_placeholders;
+use crate::sql::qualified_name;
+use crate::sql::split_statements;
+use crate::sql::unfiltered_modification;
+use crate::sql::StatementKind;
+use crate::ssh_tunnel::SshTunnel;
+use crate::ssh_tunnel::SshTunnelConfig;
+use crate::ssh_tunnel::VERIFY_FULL_TUNNEL_WARNING;
+use crate::table_designer::TableDesign;
+use crate::table_designer::TableDesigner;
+use crate::table_sql::table_template;
+use crate::table_sql::TableTemplate;
+use crate::table_stats::TableEstimates;
+use crate::table_stats::TableInfo;
 use crate::themes::set_theme;
 use crate::themes::ROSEMARY_DARK;
+use crate::ui::connections_panel::show_connect_progress;
+use crate::ui::connections_panel::show_connection_status;
 use crate::ui::connections_panel::show_connections_panel;
+use crate::ui::credentials_panel::show_credentials_panel;
+use crate::ui::data_compare_panel::show_data_compare_panel;
+use crate::ui::data_compare_panel::DataCompareAction;
+use crate::ui::data_editor_panel::show_data_editor_panel;
+use crate::ui::data_editor_panel::DataEditorAction;
 use crate::ui::databases_panel::show_databases_panel;
+use crate::ui::editor_panel::editor_id;
+use crate::ui::editor_panel::insert_into_editor;
 use crate::ui::editor_panel::show_editor_panel;
+use crate::ui::er_diagram_panel::show_er_diagram_panel;
+use crate::ui::er_diagram_panel::DiagramAction;
+use crate::ui::library_panel::show_library_panel;
+use crate::ui::object_search_panel::show_object_search_panel;
+use crate::ui::object_search_panel::ObjectSearchAction;
 use crate::ui::pagination_panel::show_pagination_panel;
 use crate::ui::query_metrics_panel::show_query_metrics_panel;
+use crate::ui::query_params_panel::show_query_params_panel;
 use crate::ui::results_table_panel::show_results_table_panel;
 use crate::ui::running_queries_panel::show_running_queries_panel;
+use crate::ui::schema_compare_panel::show_schema_compare_panel;
+use crate::ui::schema_compare_panel::SchemaCompareAction;
+use crate::ui::table_designer_panel::show_table_designer_panel;
+use crate::ui::table_designer_panel::DesignerAction;
+use crate::ui::table_info_panel::show_table_info_panel;
+use crate::ui::table_info_panel::TableInfoAction;
 use crate::ui::tables_panel::show_tables_panel;
+use crate::vault::CredentialVault;
 use rayon::prelude::*;
+use sqlx::postgres::types::Oid;
 use sqlx::postgres::PgRow;
 use sqlx::Column;
 use sqlx::Row;
 use sqlx::{Pool, Postgres};
-use urlencoding::encode;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 
 pub const ROSEMARY_SORT_COL_STR: &str = "__rosemary_default_sort_by_col";
 
@@ -41,14 +140,74 @@ pub struct QueryResultsPanel {
     pub query_execution_time_sec: f64,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct SavedConnection {
+    #[serde(default = "new_connection_id")]
+    pub id: String,
     pub connection_name: String,
     pub db_host: String,
     pub db_port: String,
     pub db_user: String,
+    /// Only set for connections saved before passwords moved into the vault.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub db_password: String,
     pub db_name: String,
+    #[serde(default)]
+    pub prompt_for_password: bool,
+    /// Other libpq parameters such as `sslmode` or `connect_timeout`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub group: String,
+    /// Tints the window while connected, e.g. red for production.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colour: Option<[u8; 3]>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Opens sessions with `default_transaction_read_only = on`.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Asks before running statements that change data or the schema.
+    #[serde(default)]
+    pub production: bool,
+    #[serde(default, skip_serializing_if = "SessionSettings::is_empty")]
+    pub session: SessionSettings,
+}
+
+impl Default for SavedConnection {
+    fn default() -> Self {
+        Self {
+            id: new_connection_id(),
+            connection_name: String::new(),
+            db_host: String::from("localhost"),
+            db_port: String::from("5432"),
+            db_user: String::new(),
+            db_password: String::new(),
+            db_name: String::new(),
+            prompt_for_password: false,
+            params: BTreeMap::new(),
+            ssh_tunnel: None,
+            group: String::new(),
+            colour: None,
+            notes: String::new(),
+            read_only: false,
+            production: false,
+            session: SessionSettings::default(),
+        }
+    }
+}
+
+pub fn new_connection_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialPrompt {
+    CreateVault,
+    UnlockVault,
+    ConnectionPassword,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -60,16 +219,105 @@ pub struct Rosemary {
     pub db_host: String,
     pub db_port: String,
     pub db_user: String,
+    #[serde(skip)]
     pub db_password: String,
+    pub prompt_for_password: bool,
     pub db_name: String,
+    pub db_params: String,
+    pub db_ssl_mode: String,
+    pub db_ssl_root_cert: String,
+    pub db_ssl_cert: String,
+    pub db_ssl_key: String,
+    pub db_use_ssh_tunnel: bool,
+    pub db_ssh_tunnel: SshTunnelConfig,
+    pub connection_group: String,
+    pub connection_colour: Option<[u8; 3]>,
+    pub connection_notes: String,
+    pub db_read_only: bool,
+    pub db_production: bool,
+    pub db_session: SessionSettings,
     pub connection_list: Vec<SavedConnection>,
-    pub connect_to_idx: usize,
+    /// Id of the saved connection being opened.
+    pub connect_to_id: String,
     #[serde(skip)]
     pub selected_db: String,
 
     // Don't persist on reload
     #[serde(skip)]
-    pub db_pool: Option<Pool<Postgres>>,
+    pub live_connections: Vec<LiveConnection>,
+    /// Key of the live connection the editor runs its queries on.
+    #[serde(skip)]
+    pub active_connection_key: String,
+    /// Key of the live connection the last query was sent to, for cancelling it.
+    #[serde(skip)]
+    pub query_connection_key: String,
+    #[serde(skip)]
+    pub editing_connection: Option<String>,
+    #[serde(skip)]
+    pub pending_execution: Option<PendingExecution>,
+    #[serde(skip)]
+    pub definition_viewer: Option<DefinitionViewer>,
+    #[serde(skip)]
+    pub data_editor: Option<DataEditor>,
+    #[serde(skip)]
+    pub table_designer: Option<TableDesigner>,
+    #[serde(skip)]
+    pub er_diagram: Option<ErDiagram>,
+    #[serde(skip)]
+    pub schema_compare: Option<SchemaCompare>,
+    #[serde(skip)]
+    pub data_compare: Option<DataCompare>,
+    #[serde(skip)]
+    pub object_search: Option<ObjectSearch>,
+    /// An object the table tree should expand to, picked in the object search.
+    #[serde(skip)]
+    pub tree_reveal: Option<TreeReveal>,
+    /// The table in the details panel, the last one clicked in the table list.
+    #[serde(skip)]
+    pub table_info: Option<TableInfo>,
+    #[serde(skip)]
+    pub compare_event_tx: Sender<CompareEvent>,
+    #[serde(skip)]
+    pub compare_event_rx: Receiver<CompareEvent>,
+    #[serde(skip)]
+    pub table_event_tx: Sender<TableEvent>,
+    #[serde(skip)]
+    pub table_event_rx: Receiver<TableEvent>,
+    #[serde(skip)]
+    pub catalog_event_tx: Sender<CatalogEvent>,
+    #[serde(skip)]
+    pub catalog_event_rx: Receiver<CatalogEvent>,
+    /// What the table list opens once a table's details are loaded, with the
+    /// connection key and table it is for.
+    #[serde(skip)]
+    pub after_details: Option<(String, (String, String), AfterDetails)>,
+    /// Runs the selected query on the next frame, for statements added to the
+    /// editor by background work.
+    #[serde(skip)]
+    pub execute_requested: bool,
+    #[serde(skip)]
+    /// Id of the saved connection waiting for the delete to be confirmed.
+    pub pending_connection_delete: Option<String>,
+    #[serde(skip)]
+    pub connecting_to: String,
+    #[serde(skip)]
+    pub connect_task: Option<JoinHandle<()>>,
+    #[serde(skip)]
+    pub connect_started: Option<Instant>,
+    #[serde(skip)]
+    pub connect_tx: Sender<Result<OpenedConnection, String>>,
+    #[serde(skip)]
+    pub connect_rx: Receiver<Result<OpenedConnection, String>>,
+    #[serde(skip)]
+    pub connect_error: String,
+    #[serde(skip)]
+    pub connection_test_task: Option<JoinHandle<()>>,
+    #[serde(skip)]
+    pub connection_test_tx: Sender<Result<String, String>>,
+    #[serde(skip)]
+    pub connection_test_rx: Receiver<Result<String, String>>,
+    #[serde(skip)]
+    pub connection_test_result: Option<Result<String, String>>,
     #[serde(skip)]
     pub connection_modal_open: bool,
     #[serde(skip)]
@@ -90,13 +338,12 @@ pub struct Rosemary {
 
     // Table list
     #[serde(skip)]
-    pub tables: Vec<PublicTable>,
-    #[serde(skip)]
-    pub should_fetch_table_list: bool,
-    #[serde(skip)]
     pub table_filter: String,
     pub show_table_list: bool,
     pub table_queries_are_additive: bool,
+    /// Shows estimated row counts and sizes next to the tables in the list.
+    pub show_table_estimates: bool,
+    pub show_table_info: bool,
 
     #[serde(skip)]
     pub query_result_tx: Sender<(Vec<PgRow>, String, u128, f64)>,
@@ -112,21 +359,464 @@ pub struct Rosemary {
     pub running_queries: Vec<RunningQueriesData>,
     #[serde(skip)]
     pub running_queries_modal_open: bool,
+
+    // Transactions
+    pub auto_commit: bool,
+    #[serde(skip)]
+    pub editor_session: EditorSession,
+    #[serde(skip)]
+    pub transaction_status: TransactionStatus,
+    #[serde(skip)]
+    pub transaction_status_tx: Sender<TransactionStatus>,
+    #[serde(skip)]
+    pub transaction_status_rx: Receiver<TransactionStatus>,
+    #[serde(skip)]
+    pub pending_transaction_action: Option<PendingTransactionAction>,
+    #[serde(skip)]
+    pub transaction_end_tx: Sender<Result<(), String>>,
+    #[serde(skip)]
+    pub transaction_end_rx: Receiver<Result<(), String>>,
+    /// Set while a COMMIT or ROLLBACK is running.
+    #[serde(skip)]
+    pub ending_transaction: bool,
+    /// Carried out once the COMMIT or ROLLBACK picked in the "Open transaction"
+    /// window has finished.
+    #[serde(skip)]
+    pub after_transaction: Option<PendingTransactionAction>,
+    #[serde(skip)]
+    pub transaction_error: Option<String>,
+    #[serde(skip)]
+    pub allow_close: bool,
+
+    // Bind parameters, by query text, the most recently run first
+    pub recent_query_params: Vec<(String, Vec<QueryParam>)>,
+    #[serde(skip)]
+    pub parameter_prompt: Option<ParameterPrompt>,
+
+    // Query library
+    pub library_dir: String,
+    pub show_library: bool,
+    #[serde(skip)]
+    pub library_entries: Vec<LibraryEntry>,
+    #[serde(skip)]
+    pub should_reload_library: bool,
+    #[serde(skip)]
+    pub library_filter: String,
+    #[serde(skip)]
+    pub library_draft: LibraryDraft,
+    #[serde(skip)]
+    pub library_error: String,
+    /// Set when saving the draft would replace another entry's file.
+    #[serde(skip)]
+    pub library_confirm_overwrite: bool,
+
+    // SQL files
+    pub editor_file: Option<PathBuf>,
+    pub editor_file_modified: Option<SystemTime>,
+    pub saved_code: String,
+    pub recent_files: Vec<PathBuf>,
+    #[serde(skip)]
+    pub pending_file_action: Option<PendingFileAction>,
+    #[serde(skip)]
+    pub file_reload_prompt: bool,
+    #[serde(skip)]
+    pub last_file_check: f64,
+    #[serde(skip)]
+    pub file_error: String,
+    #[serde(skip)]
+    pub allow_close_unsaved: bool,
+    #[serde(skip)]
+    pub window_title: String,
+
+    #[serde(skip)]
+    pub find_replace: FindReplace,
+
+    // Credentials
+    #[serde(skip)]
+    pub vault: CredentialVault,
+    #[serde(skip)]
+    pub credential_prompt: Option<CredentialPrompt>,
+    #[serde(skip)]
+    pub credential_reason: String,
+    #[serde(skip)]
+    pub credential_input: String,
+    #[serde(skip)]
+    pub credential_confirm: String,
+    #[serde(skip)]
+    pub credential_error: String,
+    #[serde(skip)]
+    pub connect_after_credentials: bool,
+    #[serde(skip)]
+    pub pending_secret: Option<(String, String)>,
+    /// Ids of connections removed while the vault was locked, whose passwords
+    /// are removed from it when it is next unlocked.
+    pub pending_secret_removals: Vec<String>,
+    #[serde(skip)]
+    pub prompted_password: Option<String>,
+    #[serde(skip)]
+    pub vault_migration_checked: bool,
+
+    // libpq configuration
+    #[serde(skip)]
+    pub pg_services: Vec<PgService>,
+    #[serde(skip)]
+    pub should_load_pg_services: bool,
+    #[serde(skip)]
+    pub connection_string: String,
+    #[serde(skip)]
+    pub connection_form_error: String,
+    /// Parts of the last imported connection or file that won't be used.
+    #[serde(skip)]
+    pub connection_import_warnings: Vec<String>,
+
+    // ER diagrams
+    /// Table positions, keyed by saved connection and database, then by table.
+    pub diagram_layouts: HashMap<String, HashMap<String, [f32; 2]>>,
+}
+
+/// Something the user asked for that would discard unsaved editor changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingFileAction {
+    New,
+    Open(PathBuf),
+    Close,
+}
+
+const MAX_RECENT_FILES: usize = 10;
+const MAX_RECENT_QUERY_PARAMS: usize = 50;
+
+fn sql_file_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .add_filter("SQL", &["sql"])
+        .add_filter("All files", &["*"])
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn pgpass_password(conn: &SavedConnection, database: &str) -> Option<String> {
+    // The password file is matched against the first host only.
+    let host = conn.db_host.split(',').next().unwrap_or_default().trim();
+    let port = conn.db_port.split(',').next().unwrap_or_default().trim();
+    lookup_pgpass(host, port, database, &conn.db_user)
+}
+
+/// How an object in the schema browser is identified.
+#[derive(Debug, Clone)]
+pub enum ObjectRef {
+    Oid(Oid, String),
+    Relation(String, String),
+}
+
+/// A connection that finished opening in the background.
+pub struct OpenedConnection {
+    pub connection_id: String,
+    pub name: String,
+    pub database: String,
+    pub pool: Pool<Postgres>,
+    pub ssl_status: Option<SslStatus>,
+    pub tunnel: Option<SshTunnel>,
+}
+
+/// An open connection. A saved connection can be open on several databases at once.
+pub struct LiveConnection {
+    pub key: String,
+    pub connection_id: String,
+    pub name: String,
+    pub database: String,
+    pub pool: Pool<Postgres>,
+    pub ssl_status: Option<SslStatus>,
+    pub tunnel: Option<SshTunnel>,
+    pub tables: Vec<PublicTable>,
+    pub schemas: Vec<String>,
+    pub should_fetch_table_list: bool,
+    /// Loaded when a table is first expanded, keyed by schema and table name.
+    pub table_details: HashMap<(String, String), Result<TableDetails, String>>,
+    /// Functions, sequences, types and materialized views, loaded when a schema is expanded.
+    pub schema_objects: HashMap<String, Result<Vec<SchemaObject>, String>>,
+    pub extensions: Option<Result<Vec<SchemaObject>, String>>,
+    /// Everything the object search can find, loaded when it is first opened.
+    pub search_objects: Option<Result<Vec<SearchObject>, String>>,
+    /// Row counts and sizes asked for from the table context menu.
+    pub table_sizes: HashMap<(String, String), Result<TableSize, String>>,
+    /// Row estimates and sizes of every table, loaded when they are shown in the list.
+    pub table_estimates: Option<Result<TableEstimates, String>>,
+    /// Types offered by the table designer, loaded when it is first opened.
+    pub data_types: Option<Result<Vec<String>, String>>,
+    /// Catalog loads still running.
+    pub loading: HashSet<CatalogLoad>,
+}
+
+impl LiveConnection {
+    pub fn label(&self) -> String {
+        if self.database.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} / {}", self.name, self.database)
+        }
+    }
+}
+
+impl From<OpenedConnection> for LiveConnection {
+    fn from(opened: OpenedConnection) -> Self {
+        Self {
+            key: new_connection_id(),
+            connection_id: opened.connection_id,
+            name: opened.name,
+            database: opened.database,
+            pool: opened.pool,
+            ssl_status: opened.ssl_status,
+            tunnel: opened.tunnel,
+            tables: Vec::new(),
+            schemas: Vec::new(),
+            should_fetch_table_list: true,
+            table_details: HashMap::new(),
+            schema_objects: HashMap::new(),
+            extensions: None,
+            search_objects: None,
+            table_sizes: HashMap::new(),
+            table_estimates: None,
+            data_types: None,
+            loading: HashSet::new(),
+        }
+    }
+}
+
+/// Opens the connection's SSH tunnel, if it has one, and returns a copy of the
+/// connection pointed at it.
+async fn with_tunnel(
+    conn: SavedConnection,
+) -> Result<(SavedConnection, Option<SshTunnel>), String> {
+    let Some(config) = conn.ssh_tunnel.clone() else {
+        return Ok((conn, None));
+    };
+    if conn.params.get("sslmode").map(String::as_str) == Some("verify-full") {
+        return Err(String::from(VERIFY_FULL_TUNNEL_WARNING));
+    }
+    let targets = host_port_pairs(&conn.db_host, &conn.db_port)?;
+    let tunnel = tokio::task::spawn_blocking(move || SshTunnel::open(&config, &targets))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok((tunnel.tunnelled(&conn), Some(tunnel)))
+}
+
+async fn open_connection(
+    conn: SavedConnection,
+    database: String,
+    password: String,
+) -> Result<OpenedConnection, String> {
+    let connection_id = conn.id.clone();
+    let name = conn.connection_name.clone();
+    let (conn, tunnel) = with_tunnel(conn).await?;
+    let pool = connect(&conn, &database, &password).await?;
+    let ssl_status = match get_ssl_status(&pool).await {
+        Ok(status) => Some(status),
+        Err(e) => {
+            eprintln!("Failed to read the encryption status: {}", e);
+            None
+        }
+    };
+    Ok(OpenedConnection {
+        connection_id,
+        name,
+        database,
+        pool,
+        ssl_status,
+        tunnel,
+    })
+}
+
+/// Results of table work done in the background.
+pub enum TableEvent {
+    Size {
+        key: String,
+        table: (String, String),
+        size: Result<TableSize, String>,
+    },
+    Stats {
+        key: String,
+        table: (String, String),
+        stats: Result<Box<(TableStats, Vec<IndexStats>)>, String>,
+    },
+    EditorRows {
+        id: String,
+        rows: Result<Vec<Vec<Option<String>>>, String>,
+    },
+    EditorSaved {
+        id: String,
+        result: Result<usize, String>,
+    },
+    DesignApplied {
+        id: String,
+        result: Result<(), String>,
+    },
+}
+
+/// Results of a compare between two connections.
+pub enum CompareEvent {
+    Schemas {
+        id: String,
+        result: Result<(SchemaSnapshot, SchemaSnapshot), String>,
+    },
+    /// Source rows compared so far.
+    DataProgress { id: String, rows: usize },
+    Data {
+        id: String,
+        result: Result<DataDiff, String>,
+    },
+}
+
+/// Something opened from the table list that needs the table's details.
+#[derive(Debug)]
+pub enum AfterDetails {
+    /// The designer, with the status to show in it.
+    Designer(String),
+    DataEditor,
+    Template(String, TableTemplate),
+}
+
+/// A catalog load running in the background, tracked so it is only started once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CatalogLoad {
+    Details(String, String),
+    Objects(String),
+    Extensions,
+    Estimates,
+    SearchObjects,
+    DataTypes,
+}
+
+/// Catalog metadata loaded in the background.
+pub enum CatalogEvent {
+    Details {
+        key: String,
+        table: (String, String),
+        details: Result<Box<TableDetails>, String>,
+    },
+    Objects {
+        key: String,
+        schema: String,
+        objects: Result<Vec<SchemaObject>, String>,
+    },
+    Extensions {
+        key: String,
+        extensions: Result<Vec<SchemaObject>, String>,
+    },
+    Estimates {
+        key: String,
+        estimates: Result<TableEstimates, String>,
+    },
+    SearchObjects {
+        key: String,
+        objects: Result<Vec<SearchObject>, String>,
+    },
+    DataTypes {
+        key: String,
+        types: Result<Vec<String>, String>,
+    },
+    /// An object's `CREATE` statement, for the definition viewer or the clipboard.
+    Definition {
+        title: String,
+        copy: bool,
+        definition: Result<String, String>,
+    },
+    ErDiagram {
+        key: String,
+        layout_key: String,
+        schema: String,
+        table: Option<String>,
+        result: Result<(Vec<DiagramColumn>, Vec<DiagramForeignKey>), String>,
+    },
+}
+
+/// A query waiting for the user to fill in its placeholders.
+#[derive(Debug)]
+pub struct ParameterPrompt {
+    pub original_query: String,
+    pub prepared_query: String,
+    pub query_idx: usize,
+    pub params: Vec<QueryParam>,
+}
+
+/// The `CREATE` statement of an object, shown in its own window.
+#[derive(Debug)]
+pub struct DefinitionViewer {
+    pub title: String,
+    /// `None` while it is loading.
+    pub definition: Option<Result<String, String>>,
+}
+
+/// A query waiting for the user to confirm it despite safety warnings.
+#[derive(Debug)]
+pub struct PendingExecution {
+    pub query: String,
+    pub query_idx: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Something the user asked for that would abandon the open transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingTransactionAction {
+    Connect,
+    Close,
+    /// Point the editor at another live connection.
+    Switch(String),
+    Disconnect(String),
 }
 
 impl Default for Rosemary {
     fn default() -> Self {
         let (tx, rx) = std::sync::mpsc::channel();
         let (pid_tx, pid_rx) = std::sync::mpsc::channel();
+        let (status_tx, status_rx) = std::sync::mpsc::channel();
+        let (transaction_end_tx, transaction_end_rx) = std::sync::mpsc::channel();
+        let (connect_tx, connect_rx) = std::sync::mpsc::channel();
+        let (connection_test_tx, connection_test_rx) = std::sync::mpsc::channel();
+        let (table_event_tx, table_event_rx) = std::sync::mpsc::channel();
+        let (compare_event_tx, compare_event_rx) = std::sync::mpsc::channel();
+        let (catalog_event_tx, catalog_event_rx) = std::sync::mpsc::channel();
         Self {
             code: "".to_owned(),
             query_to_execute: 0,
-            db_pool: None,
-            tables: Vec::new(),
+            live_connections: Vec::new(),
+            active_connection_key: String::new(),
+            query_connection_key: String::new(),
+            pending_execution: None,
+            definition_viewer: None,
+            data_editor: None,
+            table_designer: None,
+            er_diagram: None,
+            schema_compare: None,
+            data_compare: None,
+            object_search: None,
+            tree_reveal: None,
+            table_info: None,
+            compare_event_tx,
+            compare_event_rx,
+            table_event_tx,
+            table_event_rx,
+            catalog_event_tx,
+            catalog_event_rx,
+            after_details: None,
+            execute_requested: false,
+            editing_connection: None,
+            pending_connection_delete: None,
+            connecting_to: String::new(),
+            connect_task: None,
+            connect_started: None,
+            connect_tx,
+            connect_rx,
+            connect_error: String::new(),
+            connection_test_task: None,
+            connection_test_tx,
+            connection_test_rx,
+            connection_test_result: None,
             databases: Vec::new(),
-            should_fetch_table_list: false,
             table_filter: String::new(),
             show_table_list: true,
+            show_table_estimates: false,
+            show_table_info: false,
             connection_modal_open: false,
             db_select_modal_open: false,
             connection_name: "".to_string(),
@@ -134,10 +824,24 @@ impl Default for Rosemary {
             db_port: "5432".to_string(),
             db_user: "".to_string(),
             db_password: "".to_string(),
+            prompt_for_password: false,
             db_name: "".to_string(),
+            db_params: String::new(),
+            db_ssl_mode: String::new(),
+            db_ssl_root_cert: String::new(),
+            db_ssl_cert: String::new(),
+            db_ssl_key: String::new(),
+            db_use_ssh_tunnel: false,
+            db_ssh_tunnel: SshTunnelConfig::default(),
+            connection_group: String::new(),
+            connection_colour: None,
+            connection_notes: String::new(),
+            db_read_only: false,
+            db_production: false,
+            db_session: SessionSettings::default(),
             selected_db: "".to_string(),
             connection_list: Vec::new(),
-            connect_to_idx: 0,
+            connect_to_id: String::new(),
             table_queries_are_additive: true,
             split_results_table: false,
             query_result_tx: tx,
@@ -168,6 +872,56 @@ impl Default for Rosemary {
             ],
             running_queries: Vec::new(),
             running_queries_modal_open: false,
+            auto_commit: true,
+            editor_session: EditorSession::default(),
+            transaction_status: TransactionStatus::Idle,
+            transaction_status_tx: status_tx,
+            transaction_status_rx: status_rx,
+            pending_transaction_action: None,
+            transaction_end_tx,
+            transaction_end_rx,
+            ending_transaction: false,
+            after_transaction: None,
+            transaction_error: None,
+            allow_close: false,
+            recent_query_params: Vec::new(),
+            parameter_prompt: None,
+            library_dir: default_library_dir().to_string_lossy().into_owned(),
+            show_library: false,
+            library_entries: Vec::new(),
+            should_reload_library: true,
+            library_filter: String::new(),
+            library_draft: LibraryDraft::default(),
+            library_error: String::new(),
+            library_confirm_overwrite: false,
+            editor_file: None,
+            editor_file_modified: None,
+            saved_code: String::new(),
+            recent_files: Vec::new(),
+            pending_file_action: None,
+            file_reload_prompt: false,
+            last_file_check: 0.0,
+            file_error: String::new(),
+            allow_close_unsaved: false,
+            window_title: String::new(),
+            find_replace: FindReplace::default(),
+            vault: CredentialVault::default(),
+            credential_prompt: None,
+            credential_reason: String::new(),
+            credential_input: String::new(),
+            credential_confirm: String::new(),
+            credential_error: String::new(),
+            connect_after_credentials: false,
+            pending_secret: None,
+            pending_secret_removals: Vec::new(),
+            prompted_password: None,
+            vault_migration_checked: false,
+            pg_services: Vec::new(),
+            should_load_pg_services: true,
+            connection_string: String::new(),
+            connection_form_error: String::new(),
+            connection_import_warnings: Vec::new(),
+            diagram_layouts: HashMap::new(),
         }
     }
 }
@@ -179,13 +933,7 @@ impl Rosemary {
             return app;
         }
 
-        let app = Rosemary::default();
-        app
-    }
-
-    fn reset_table_data(&mut self) {
-        self.tables = Vec::new();
-        self.should_fetch_table_list = true;
+        Rosemary::default()
     }
 
     fn reset_query_result_data(&mut self, idx: usize) {
@@ -201,159 +949,2124 @@ impl Rosemary {
         }
     }
 
-    fn connect_to_db(&mut self) {
-        let runtime = Runtime::new().expect("Failed to create runtime");
-        let conn = &self.connection_list[self.connect_to_idx];
-        let database = if !&self.selected_db.trim().is_empty() {
-            self.selected_db.clone()
+    /// Opens the vault prompt, creating a vault first if there isn't one yet.
+    pub fn prompt_for_vault(&mut self, reason: &str) {
+        self.credential_prompt = Some(if self.vault.exists() {
+            CredentialPrompt::UnlockVault
         } else {
-            conn.db_name.clone()
-        };
-        let database_url = format!(
-            "postgresql://{}:{}@{}:{}/{}?application_name=Rosemary",
-            encode(&conn.db_user), encode(&conn.db_password), conn.db_host, conn.db_port, database
-        );
+            CredentialPrompt::CreateVault
+        });
+        self.credential_reason = reason.to_string();
+    }
 
-        let connection_result =
-            runtime.block_on(async { sqlx::PgPool::connect(&database_url).await });
+    /// Stores a connection password in the vault, asking to unlock it first if needed.
+    pub fn store_connection_password(&mut self, connection_id: &str, password: &str) {
+        if self.vault.is_unlocked() {
+            if let Err(e) = self.vault.set(connection_id, password) {
+                self.credential_error = e;
+            }
+        } else {
+            self.pending_secret = Some((connection_id.to_string(), password.to_string()));
+            self.prompt_for_vault("Saved passwords are kept in an encrypted vault.");
+        }
+    }
 
-        match connection_result {
-            Ok(pool) => {
-                self.db_pool = Some(pool);
-                self.connection_modal_open = false;
-                self.reset_table_data();
-                self.reset_query_result_data(0);
-                if self.query_results.len() > 1 {
-                    self.reset_query_result_data(1);
+    pub fn edit_connection(&mut self, idx: usize) {
+        let mut connection = self.connection_list[idx].clone();
+        let id = connection.id.clone();
+        // Passwords stay in the vault unless a new one is typed in.
+        connection.db_password.clear();
+        self.fill_connection_form(connection);
+        self.editing_connection = Some(id);
+    }
+
+    /// Saves the connection from the form, replacing the one being edited if there is one.
+    pub fn save_connection(&mut self, mut connection: SavedConnection) {
+        let existing = self
+            .editing_connection
+            .take()
+            .and_then(|id| self.connection_list.iter().position(|conn| conn.id == id));
+        if let Some(idx) = existing {
+            connection.id = self.connection_list[idx].id.clone();
+            if self.db_password.is_empty() && !connection.prompt_for_password {
+                // Keep a password saved by an older version until it is migrated.
+                connection.db_password = self.connection_list[idx].db_password.clone();
+            }
+            if connection.prompt_for_password && self.vault.is_unlocked() {
+                if let Err(e) = self.vault.remove(&connection.id) {
+                    eprintln!("{e}");
                 }
             }
-            Err(e) => {
-                eprintln!("Failed to connect: {}", e);
+        }
+
+        if !connection.prompt_for_password && !self.db_password.is_empty() {
+            let password = std::mem::take(&mut self.db_password);
+            self.store_connection_password(&connection.id, &password);
+        }
+        match existing {
+            Some(idx) => self.connection_list[idx] = connection,
+            None => self.connection_list.push(connection),
+        }
+    }
+
+    pub fn duplicate_connection(&mut self, idx: usize) {
+        let original = &self.connection_list[idx];
+        let password = self.saved_password(original);
+        let duplicate = SavedConnection {
+            id: new_connection_id(),
+            connection_name: format!("{} (copy)", original.connection_name),
+            ..original.clone()
+        };
+        if duplicate.db_password.is_empty() {
+            if let Some(password) = password {
+                self.store_connection_password(&duplicate.id, &password);
             }
         }
+        self.connection_list.insert(idx + 1, duplicate);
     }
 
-    fn get_tables(&mut self) {
-        self.should_fetch_table_list = false;
-        let db_pool = &mut self.db_pool;
-        let table_rows_ref = &mut self.tables;
+    /// Moves a connection in front of another one, into that connection's group.
+    pub fn move_connection(&mut self, from: usize, before: usize) {
+        if from == before {
+            return;
+        }
+        let mut connection = self.connection_list.remove(from);
+        let before = if from < before { before - 1 } else { before };
+        if let Some(target) = self.connection_list.get(before) {
+            connection.group = target.group.clone();
+        }
+        self.connection_list.insert(before, connection);
+    }
 
-        let runtime = Runtime::new().expect("Failed to create runtime");
-        runtime.block_on(async move {
-            if let Some(pool) = db_pool {
-                match get_public_tables(&pool).await {
-                    Ok(rows) => {
-                        if !rows.is_empty() {
-                            *table_rows_ref = rows;
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{:?}", e);
-                    }
-                }
-            }
-        });
+    /// The panel frame, tinted with the connected connection's colour.
+    fn connection_chrome(&self, ctx: &egui::Context) -> egui::Frame {
+        let mut frame = egui::Frame::side_top_panel(&ctx.style());
+        if let Some([r, g, b]) = self.connected_connection().and_then(|conn| conn.colour) {
+            frame.fill = frame
+                .fill
+                .lerp_to_gamma(egui::Color32::from_rgb(r, g, b), 0.35);
+        }
+        frame
     }
 
-    fn get_databases(&mut self) {
-        self.databases = Vec::new();
-        let db_pool = &mut self.db_pool;
-        let databases_rows_ref = &mut self.databases;
+    /// The live connection the editor is bound to.
+    pub fn active_connection(&self) -> Option<&LiveConnection> {
+        self.live_connections
+            .iter()
+            .find(|live| live.key == self.active_connection_key)
+    }
 
-        let runtime = Runtime::new().expect("Failed to create runtime");
-        runtime.block_on(async move {
-            if let Some(pool) = db_pool {
-                match get_database_names(&pool).await {
-                    Ok(rows) => {
-                        if !rows.is_empty() {
-                            *databases_rows_ref = rows;
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{:?}", e);
-                    }
-                }
-            }
-        });
+    pub fn db_pool(&self) -> Option<Pool<Postgres>> {
+        self.active_connection().map(|live| live.pool.clone())
     }
 
-    fn get_running_queries(&mut self) {
-        self.running_queries = Vec::new();
-        let db_pool = &mut self.db_pool;
-        let running_queries_rows_ref = &mut self.running_queries;
+    /// The saved connection behind the editor's live connection.
+    pub fn connected_connection(&self) -> Option<&SavedConnection> {
+        let live = self.active_connection()?;
+        self.connection_list
+            .iter()
+            .find(|conn| conn.id == live.connection_id)
+    }
 
-        let runtime = Runtime::new().expect("Failed to create runtime");
-        runtime.block_on(async move {
-            if let Some(pool) = db_pool {
-                match get_running_queries_data(&pool).await {
-                    Ok(rows) => {
-                        if !rows.is_empty() {
-                            *running_queries_rows_ref = rows;
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{:?}", e);
-                    }
-                }
-            }
-        });
+    /// Binds the editor to another live connection, asking what to do with
+    /// an open transaction first.
+    pub fn set_active_connection(&mut self, key: &str) {
+        if key == self.active_connection_key {
+            return;
+        }
+        if self.transaction_status.is_open() {
+            self.pending_transaction_action =
+                Some(PendingTransactionAction::Switch(key.to_string()));
+        } else {
+            self.switch_connection(key.to_string());
+        }
     }
-}
 
-impl eframe::App for Rosemary {
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    fn switch_connection(&mut self, key: String) {
+        self.active_connection_key = key;
+        self.editor_session = EditorSession::default();
+        self.transaction_status = TransactionStatus::Idle;
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if !self.db_pool.is_some() {
-            self.connection_modal_open = true;
+    pub fn request_disconnect(&mut self, key: &str) {
+        if key == self.active_connection_key && self.transaction_status.is_open() {
+            self.pending_transaction_action =
+                Some(PendingTransactionAction::Disconnect(key.to_string()));
+        } else {
+            self.disconnect(key);
+        }
+    }
+
+    fn disconnect(&mut self, key: &str) {
+        let Some(idx) = self
+            .live_connections
+            .iter()
+            .position(|live| live.key == key)
+        else {
+            return;
+        };
+        let live = self.live_connections.remove(idx);
+        let pool = live.pool.clone();
+        tokio::spawn(async move { pool.close().await });
+        if self.table_info.as_ref().is_some_and(|info| info.key == key) {
+            self.table_info = None;
+        }
+
+        if key == self.active_connection_key {
+            let next = self
+                .live_connections
+                .first()
+                .map_or_else(String::new, |live| live.key.clone());
+            self.switch_connection(next);
+        }
+    }
+
+    /// Opens the database list for the editor's connection. Picking a
+    /// database opens it as another live connection.
+    pub fn open_databases(&mut self) {
+        let Some(id) = self
+            .active_connection()
+            .map(|live| live.connection_id.clone())
+        else {
+            return;
+        };
+        self.connect_to_id = id;
+        self.db_select_modal_open = true;
+        self.get_databases();
+    }
+
+    pub fn refresh_tables(&mut self, key: &str) {
+        if let Some(live) = self
+            .live_connections
+            .iter_mut()
+            .find(|live| live.key == key)
+        {
+            live.tables = Vec::new();
+            live.should_fetch_table_list = true;
+            live.table_details.clear();
+            live.schema_objects.clear();
+            live.extensions = None;
+            live.search_objects = None;
+            live.table_sizes.clear();
+            live.table_estimates = None;
+            live.data_types = None;
+            live.loading.clear();
+        }
+    }
+
+    pub fn remove_connection(&mut self, id: &str) {
+        let Some(idx) = self.connection_list.iter().position(|conn| conn.id == id) else {
+            return;
+        };
+        let connection = self.connection_list.remove(idx);
+        if self
+            .pending_secret
+            .as_ref()
+            .is_some_and(|(connection_id, _)| *connection_id == connection.id)
+        {
+            self.pending_secret = None;
+        }
+        if self.vault.is_unlocked() {
+            if let Err(e) = self.vault.remove(&connection.id) {
+                eprintln!("{e}");
+            }
+        } else if self.vault.exists() {
+            self.pending_secret_removals.push(connection.id);
+        }
+    }
+
+    fn has_plaintext_passwords(&self) -> bool {
+        self.connection_list
+            .iter()
+            .any(|conn| !conn.db_password.is_empty())
+    }
+
+    fn after_vault_unlocked(&mut self) -> Result<(), String> {
+        if let Some((connection_id, password)) = self.pending_secret.take() {
+            self.vault.set(&connection_id, &password)?;
+        }
+        while let Some(connection_id) = self.pending_secret_removals.last() {
+            self.vault.remove(connection_id)?;
+            self.pending_secret_removals.pop();
+        }
+
+        // Move passwords saved by older versions out of the app storage.
+        for conn in &mut self.connection_list {
+            if !conn.db_password.is_empty() {
+                self.vault.set(&conn.id, &conn.db_password)?;
+                conn.db_password.clear();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn submit_credentials(&mut self) {
+        let Some(prompt) = self.credential_prompt else {
+            return;
+        };
+
+        let result = match prompt {
+            CredentialPrompt::CreateVault if self.credential_input != self.credential_confirm => {
+                Err(String::from("The passwords don't match"))
+            }
+            CredentialPrompt::CreateVault => self
+                .vault
+                .create(&self.credential_input)
+                .and_then(|_| self.after_vault_unlocked()),
+            CredentialPrompt::UnlockVault => self
+                .vault
+                .unlock(&self.credential_input)
+                .and_then(|_| self.after_vault_unlocked()),
+            CredentialPrompt::ConnectionPassword => {
+                self.prompted_password = Some(self.credential_input.clone());
+                Ok(())
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.close_credential_prompt();
+                if self.connect_after_credentials {
+                    self.connect_after_credentials = false;
+                    self.connect_to_db();
+                }
+            }
+            Err(e) => self.credential_error = e,
+        }
+    }
+
+    pub fn close_credential_prompt(&mut self) {
+        self.credential_prompt = None;
+        self.credential_reason.clear();
+        self.credential_input.clear();
+        self.credential_confirm.clear();
+        self.credential_error.clear();
+    }
+
+    /// Resolves the password for the connection being opened. Returns `None`
+    /// when the user has to be asked for a password first.
+    fn connection_password(&mut self, conn: &SavedConnection) -> Option<String> {
+        if conn.prompt_for_password {
+            if let Some(password) = self.prompted_password.take() {
+                return Some(password);
+            }
+            self.credential_prompt = Some(CredentialPrompt::ConnectionPassword);
+            self.credential_reason = format!("Password for {}", conn.connection_name);
+            self.connect_after_credentials = true;
+            return None;
+        }
+
+        if !conn.db_password.is_empty() {
+            return Some(conn.db_password.clone());
+        }
+
+        let vault_password = if !self.vault.exists() {
+            None
+        } else if self.vault.is_unlocked() {
+            self.vault.get(&conn.id).map(String::from)
+        } else {
+            self.prompt_for_vault("Unlock the vault to use the saved password.");
+            self.connect_after_credentials = true;
+            return None;
+        };
+
+        vault_password
+            .or_else(|| {
+                let database = if self.selected_db.trim().is_empty() {
+                    &conn.db_name
+                } else {
+                    &self.selected_db
+                };
+                pgpass_password(conn, database)
+            })
+            .or(Some(String::new()))
+    }
+
+    /// Fills the connection form so an imported connection can be reviewed before saving.
+    pub fn fill_connection_form(&mut self, connection: SavedConnection) {
+        self.editing_connection = None;
+        self.connection_name = connection.connection_name;
+        self.db_host = connection.db_host;
+        self.db_port = connection.db_port;
+        self.db_user = connection.db_user;
+        self.db_password = connection.db_password;
+        self.db_name = connection.db_name;
+        self.prompt_for_password = connection.prompt_for_password;
+        self.db_use_ssh_tunnel = connection.ssh_tunnel.is_some();
+        self.db_ssh_tunnel = connection.ssh_tunnel.unwrap_or_default();
+        self.connection_group = connection.group;
+        self.connection_colour = connection.colour;
+        self.connection_notes = connection.notes;
+        self.db_read_only = connection.read_only;
+        self.db_production = connection.production;
+        self.db_session = connection.session;
+        let mut params = connection.params;
+        if let Some(application_name) = params.remove("application_name") {
+            if self.db_session.application_name.is_empty() {
+                self.db_session.application_name = application_name;
+            }
+        }
+        self.db_ssl_mode = params.remove("sslmode").unwrap_or_default();
+        self.db_ssl_root_cert = params.remove("sslrootcert").unwrap_or_default();
+        self.db_ssl_cert = params.remove("sslcert").unwrap_or_default();
+        self.db_ssl_key = params.remove("sslkey").unwrap_or_default();
+        self.db_params = format_key_values(&params);
+        self.connection_form_error.clear();
+        self.connection_import_warnings.clear();
+    }
+
+    /// Fills the connection form from an import and shows its warnings.
+    pub fn import_connection(&mut self, imported: ImportedConnection) {
+        self.fill_connection_form(imported.connection);
+        self.connection_import_warnings = imported.warnings;
+    }
+
+    /// The password saved for a connection, if it can be read without prompting.
+    pub fn saved_password(&self, connection: &SavedConnection) -> Option<String> {
+        if !connection.db_password.is_empty() {
+            return Some(connection.db_password.clone());
+        }
+        if connection.prompt_for_password || !self.vault.is_unlocked() {
+            return None;
+        }
+        self.vault.get(&connection.id).map(String::from)
+    }
+
+    fn connect_to_db(&mut self) {
+        let Some(conn) = self
+            .connection_list
+            .iter()
+            .find(|conn| conn.id == self.connect_to_id)
+            .cloned()
+        else {
+            self.connect_error = String::from("The connection no longer exists");
+            return;
+        };
+        let Some(password) = self.connection_password(&conn) else {
+            return;
+        };
+        let database = if !&self.selected_db.trim().is_empty() {
+            std::mem::take(&mut self.selected_db)
+        } else {
+            conn.db_name.clone()
+        };
+
+        let open = self
+            .live_connections
+            .iter()
+            .find(|live| live.connection_id == conn.id && live.database == database)
+            .map(|live| live.key.clone());
+        if let Some(key) = open {
+            self.switch_connection(key);
+            self.connection_modal_open = false;
+            return;
+        }
+
+        self.cancel_connect();
+        self.connect_error.clear();
+        self.connecting_to = conn.connection_name.clone();
+        self.connect_started = Some(Instant::now());
+        let tx = self.connect_tx.clone();
+        self.connect_task = Some(tokio::spawn(async move {
+            let _ = tx.send(open_connection(conn, database, password).await);
+        }));
+    }
+
+    /// Reasons to confirm a statement before running it: anything that
+    /// changes data or the schema on a production connection, and updates or
+    /// deletes without a `WHERE` anywhere.
+    fn safety_warnings(&self, query: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(conn) = self.connected_connection().filter(|conn| conn.production) {
+            let kind = classify_statement(query);
+            if kind.modifies_data() {
+                let change = if kind == StatementKind::Ddl {
+                    "changes the schema"
+                } else {
+                    "changes data"
+                };
+                warnings.push(format!(
+                    "This statement {} on the production connection \"{}\".",
+                    change, conn.connection_name
+                ));
+            }
+        }
+        if let Some(keyword) = unfiltered_modification(query) {
+            warnings.push(format!(
+                "{} without a WHERE clause affects every row.",
+                keyword
+            ));
+        }
+        warnings
+    }
+
+    /// Runs a query on the editor's connection, asking for confirmation first
+    /// if it looks risky.
+    pub fn submit_query(&mut self, ctx: &egui::Context, query: String, query_idx: usize) {
+        let warnings = self.safety_warnings(&query);
+        if warnings.is_empty() {
+            self.run_query(ctx, query, query_idx);
+        } else {
+            self.pending_execution = Some(PendingExecution {
+                query,
+                query_idx,
+                warnings,
+            });
+        }
+    }
+
+    /// Runs a query, asking for its bind parameters first if it has placeholders.
+    fn run_query(&mut self, ctx: &egui::Context, query_str: String, query_idx: usize) {
+        let (prepared_query, param_names) = prepare_placeholders(&query_str);
+        if param_names.is_empty() {
+            self.execute(ctx, query_str, Vec::new(), query_idx);
+        } else {
+            let saved = self
+                .recent_query_params
+                .iter()
+                .find(|(query, _)| query == query_str.trim())
+                .map(|(_, params)| params);
+            let params = param_names
+                .into_iter()
+                .map(|name| {
+                    saved
+                        .and_then(|saved| saved.iter().find(|p| p.name == name))
+                        .cloned()
+                        .unwrap_or(QueryParam {
+                            name,
+                            ..Default::default()
+                        })
+                })
+                .collect();
+            self.parameter_prompt = Some(ParameterPrompt {
+                original_query: query_str,
+                prepared_query,
+                query_idx,
+                params,
+            });
+        }
+    }
+
+    pub fn is_connecting(&self) -> bool {
+        self.connect_task.is_some()
+    }
+
+    pub fn cancel_connect(&mut self) {
+        if let Some(task) = self.connect_task.take() {
+            task.abort();
+        }
+        self.connect_started = None;
+        // Drop a result that arrived just before the attempt was cancelled.
+        while self.connect_rx.try_recv().is_ok() {}
+    }
+
+    fn finish_connect(&mut self, result: Result<OpenedConnection, String>) {
+        self.connect_task = None;
+        self.connect_started = None;
+        match result {
+            Ok(opened) => {
+                let live = LiveConnection::from(opened);
+                let key = live.key.clone();
+                self.live_connections.push(live);
+                self.switch_connection(key);
+                self.connection_modal_open = false;
+                self.reset_query_result_data(0);
+                if self.query_results.len() > 1 {
+                    self.reset_query_result_data(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to connect:\n{}", e);
+                self.connect_error = e;
+                self.connection_modal_open = true;
+            }
+        }
+    }
+
+    /// Tries a connection from the form without saving it or keeping the session.
+    pub fn start_connection_test(&mut self, connection: SavedConnection, password: String) {
+        if let Some(task) = self.connection_test_task.take() {
+            task.abort();
+        }
+        let password = if password.is_empty() {
+            pgpass_password(&connection, &connection.db_name).unwrap_or_default()
+        } else {
+            password
+        };
+        self.connection_test_result = None;
+        let tx = self.connection_test_tx.clone();
+        self.connection_test_task = Some(tokio::spawn(async move {
+            let result = match with_tunnel(connection).await {
+                Ok((conn, _tunnel)) => test_connection(&conn, &conn.db_name, &password).await,
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(result);
+        }));
+    }
+
+    fn request_connect_to_db(&mut self) {
+        if self.transaction_status.is_open() {
+            self.pending_transaction_action = Some(PendingTransactionAction::Connect);
+        } else {
+            self.connect_to_db();
+        }
+    }
+
+    /// Commits or rolls back in the background. The result arrives on
+    /// `transaction_end_rx`.
+    pub fn finish_transaction(&mut self, ctx: &egui::Context, commit: bool) {
+        self.ending_transaction = true;
+        self.transaction_error = None;
+        let session = self.editor_session.clone();
+        let status_tx = self.transaction_status_tx.clone();
+        let end_tx = self.transaction_end_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let _ = end_tx.send(end_transaction(session, commit, status_tx).await);
+            ctx.request_repaint();
+        });
+    }
+
+    fn resume_after_transaction(&mut self, ctx: &egui::Context, action: PendingTransactionAction) {
+        match action {
+            PendingTransactionAction::Connect => self.connect_to_db(),
+            PendingTransactionAction::Switch(key) => self.switch_connection(key),
+            PendingTransactionAction::Disconnect(key) => self.disconnect(&key),
+            PendingTransactionAction::Close => {
+                self.allow_close = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        ctx: &egui::Context,
+        query_str: String,
+        params: Vec<QueryParam>,
+        query_idx: usize,
+    ) {
+        self.reset_query_result_data(query_idx);
+
+        let db_pool = self.db_pool();
+        self.query_connection_key = self.active_connection_key.clone();
+        let session = self.editor_session.clone();
+        let auto_commit = self.auto_commit;
+        let tx = self.query_result_tx.clone();
+        let pid_tx = self.query_pid_tx.clone();
+        let status_tx = self.transaction_status_tx.clone();
+        let ctx = ctx.clone();
+
+        tokio::spawn(async move {
+            execute_query(
+                &db_pool,
+                session,
+                auto_commit,
+                query_str,
+                params,
+                tx,
+                pid_tx,
+                status_tx,
+            )
+            .await;
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn is_editor_dirty(&self) -> bool {
+        self.code != self.saved_code
+    }
+
+    fn request_file_action(&mut self, action: PendingFileAction) {
+        if self.is_editor_dirty() && !self.code.trim().is_empty() {
+            self.pending_file_action = Some(action);
+        } else {
+            self.run_file_action(action);
+        }
+    }
+
+    fn run_file_action(&mut self, action: PendingFileAction) {
+        match action {
+            PendingFileAction::New => {
+                self.code.clear();
+                self.saved_code.clear();
+                self.editor_file = None;
+                self.editor_file_modified = None;
+            }
+            PendingFileAction::Open(path) => self.open_file(path),
+            PendingFileAction::Close => self.allow_close_unsaved = true,
+        }
+    }
+
+    fn open_file(&mut self, path: PathBuf) {
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                self.code = contents.clone();
+                self.saved_code = contents;
+                self.editor_file_modified = file_modified(&path);
+                self.add_recent_file(&path);
+                self.editor_file = Some(path);
+                self.file_reload_prompt = false;
+                self.file_error.clear();
+            }
+            Err(e) => {
+                self.file_error = format!("Failed to open {}: {e}", path.display());
+            }
+        }
+    }
+
+    fn save_file(&mut self) -> bool {
+        match self.editor_file.clone() {
+            Some(path) => self.save_file_to(path),
+            None => self.save_file_as(),
+        }
+    }
+
+    fn save_file_as(&mut self) -> bool {
+        let mut dialog = sql_file_dialog();
+        if let Some(dir) = self.editor_file.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        match dialog.save_file() {
+            Some(path) => self.save_file_to(path),
+            None => false,
+        }
+    }
+
+    fn save_file_to(&mut self, path: PathBuf) -> bool {
+        match fs::write(&path, &self.code) {
+            Ok(()) => {
+                self.saved_code = self.code.clone();
+                self.editor_file_modified = file_modified(&path);
+                self.add_recent_file(&path);
+                self.editor_file = Some(path);
+                self.file_error.clear();
+                true
+            }
+            Err(e) => {
+                self.file_error = format!("Failed to save {}: {e}", path.display());
+                false
+            }
+        }
+    }
+
+    fn add_recent_file(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    fn check_for_external_changes(&mut self, ctx: &egui::Context) {
+        let Some(path) = &self.editor_file else {
+            return;
+        };
+        ctx.request_repaint_after(Duration::from_secs(1));
+
+        let now = ctx.input(|i| i.time);
+        if self.file_reload_prompt || now - self.last_file_check < 1.0 {
+            return;
+        }
+        self.last_file_check = now;
+
+        let modified = file_modified(path);
+        if modified.is_some() && modified != self.editor_file_modified {
+            self.file_reload_prompt = true;
+        }
+    }
+
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let file_name = self
+            .editor_file
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        let title = match file_name {
+            Some(name) if self.is_editor_dirty() => format!("rosemary - {name}*"),
+            Some(name) => format!("rosemary - {name}"),
+            None => String::from("rosemary"),
+        };
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    fn get_tables(&mut self) {
+        let runtime = Runtime::new().expect("Failed to create runtime");
+        for live in &mut self.live_connections {
+            if !live.should_fetch_table_list {
+                continue;
+            }
+            live.should_fetch_table_list = false;
+            let pool = &live.pool;
+            let table_rows_ref = &mut live.tables;
+            let schemas_ref = &mut live.schemas;
+
+            runtime.block_on(async move {
+                match get_public_tables(pool).await {
+                    Ok(rows) => {
+                        if !rows.is_empty() {
+                            *table_rows_ref = rows;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                    }
+                }
+                match get_schemas(pool).await {
+                    Ok(schemas) => *schemas_ref = schemas,
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Runs a catalog load in the background, unless the same load is
+    /// already running on the connection.
+    fn spawn_catalog_load<F>(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        load: CatalogLoad,
+        task: impl FnOnce(Pool<Postgres>) -> F,
+    ) where
+        F: Future<Output = CatalogEvent> + Send + 'static,
+    {
+        let Some(live) = self
+            .live_connections
+            .iter_mut()
+            .find(|live| live.key == key)
+        else {
+            return;
+        };
+        if !live.loading.insert(load) {
+            return;
+        }
+
+        let task = task(live.pool.clone());
+        let tx = self.catalog_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(task.await);
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn load_table_details(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: &str,
+    ) {
+        let load = CatalogLoad::Details(schema.to_string(), table.to_string());
+        let key_owned = key.to_string();
+        let table = (schema.to_string(), table.to_string());
+        self.spawn_catalog_load(ctx, key, load, |pool| async move {
+            let details = get_table_details(&pool, &table.0, &table.1)
+                .await
+                .map(Box::new)
+                .map_err(|e| e.to_string());
+            CatalogEvent::Details {
+                key: key_owned,
+                table,
+                details,
+            }
+        });
+    }
+
+    pub fn load_schema_objects(&mut self, ctx: &egui::Context, key: &str, schema: &str) {
+        let load = CatalogLoad::Objects(schema.to_string());
+        let key_owned = key.to_string();
+        let schema = schema.to_string();
+        self.spawn_catalog_load(ctx, key, load, |pool| async move {
+            let objects = get_schema_objects(&pool, &schema)
+                .await
+                .map_err(|e| e.to_string());
+            CatalogEvent::Objects {
+                key: key_owned,
+                schema,
+                objects,
+            }
+        });
+    }
+
+    pub fn load_extensions(&mut self, ctx: &egui::Context, key: &str) {
+        let key_owned = key.to_string();
+        self.spawn_catalog_load(ctx, key, CatalogLoad::Extensions, |pool| async move {
+            let extensions = get_extensions(&pool).await.map_err(|e| e.to_string());
+            CatalogEvent::Extensions {
+                key: key_owned,
+                extensions,
+            }
+        });
+    }
+
+    /// Reconstructs the `CREATE` script of an object in the background, for
+    /// the definition viewer or, with `copy`, the clipboard. Relations from
+    /// the table list are looked up by name, everything else by OID.
+    fn load_definition(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        kind: &str,
+        object: ObjectRef,
+        copy: bool,
+    ) {
+        let title = match &object {
+            ObjectRef::Oid(_, name) => name.clone(),
+            ObjectRef::Relation(schema, name) => qualified_name(schema, name),
+        };
+        let title = format!("{title} ({kind})");
+        let Some(pool) = self.live_pool(key) else {
+            self.definition_viewer = Some(DefinitionViewer {
+                title,
+                definition: Some(Err(String::from("The connection has been closed"))),
+            });
+            return;
+        };
+        if !copy {
+            self.definition_viewer = Some(DefinitionViewer {
+                title: title.clone(),
+                definition: None,
+            });
+        }
+
+        let kind = kind.to_string();
+        let tx = self.catalog_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let definition = async {
+                let oid = match object {
+                    ObjectRef::Oid(oid, _) => oid,
+                    ObjectRef::Relation(schema, name) => {
+                        get_relation_oid(&pool, &schema, &name).await?
+                    }
+                };
+                get_object_ddl(&pool, &kind, oid).await
+            }
+            .await
+            .map_err(|e| e.to_string());
+            let _ = tx.send(CatalogEvent::Definition {
+                title,
+                copy,
+                definition,
+            });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Opens the definition viewer with an object's DDL.
+    pub fn show_definition(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        kind: &str,
+        object: ObjectRef,
+    ) {
+        self.load_definition(ctx, key, kind, object, false);
+    }
+
+    /// Copies an object's DDL to the clipboard. Errors open the definition
+    /// viewer so they aren't lost.
+    pub fn copy_definition(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        kind: &str,
+        object: ObjectRef,
+    ) {
+        self.load_definition(ctx, key, kind, object, true);
+    }
+
+    fn live_pool(&self, key: &str) -> Option<Pool<Postgres>> {
+        self.live_connections
+            .iter()
+            .find(|live| live.key == key)
+            .map(|live| live.pool.clone())
+    }
+
+    /// Adds a generated script after what is in the editor, e.g. a migration
+    /// script from the schema compare.
+    pub fn append_to_editor(&mut self, script: &str) {
+        if !self.code.trim().is_empty() {
+            self.code.push_str("\n\n");
+        }
+        self.code.push_str(script);
+    }
+
+    /// Asks before running a query from the table context menu, e.g. a `TRUNCATE`.
+    pub fn confirm_query(&mut self, query: String, warning: String) {
+        let mut warnings = vec![warning];
+        warnings.extend(self.safety_warnings(&query));
+        self.pending_execution = Some(PendingExecution {
+            query,
+            query_idx: 0,
+            warnings,
+        });
+    }
+
+    /// Opens `action` once a table's details are loaded, loading them first
+    /// unless they already are and `reload` isn't asked for.
+    fn with_table_details(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: &str,
+        action: AfterDetails,
+        reload: bool,
+    ) {
+        let name = (schema.to_string(), table.to_string());
+        let loaded = self
+            .live_connections
+            .iter()
+            .any(|live| live.key == key && live.table_details.contains_key(&name));
+        self.after_details = Some((key.to_string(), name, action));
+        if reload || !loaded {
+            self.load_table_details(ctx, key, schema, table);
+        } else {
+            self.open_after_details(ctx);
+        }
+    }
+
+    /// Opens what was waiting on a table's details, once they are loaded.
+    fn open_after_details(&mut self, ctx: &egui::Context) {
+        let Some((key, (schema, table), action)) = self.after_details.take() else {
+            return;
+        };
+        let details = self
+            .live_connections
+            .iter()
+            .find(|live| live.key == key)
+            .and_then(|live| live.table_details.get(&(schema.clone(), table.clone())))
+            .cloned();
+        let Some(details) = details else {
+            return;
+        };
+        match action {
+            AfterDetails::Designer(status) => match details {
+                Ok(details) => {
+                    let original = TableDesign::from_details(&schema, &table, &details);
+                    self.show_table_designer(&key, Some(original.clone()), original);
+                    if let Some(designer) = &mut self.table_designer {
+                        designer.status = status;
+                    }
+                }
+                Err(e) => {
+                    self.definition_viewer = Some(DefinitionViewer {
+                        title: format!("{} (table)", qualified_name(&schema, &table)),
+                        definition: Some(Err(e)),
+                    });
+                }
+            },
+            AfterDetails::DataEditor => self.show_data_editor(ctx, &key, &schema, &table, details),
+            AfterDetails::Template(kind, template) => match details {
+                Ok(details) => {
+                    let sql = table_template(
+                        template,
+                        &kind,
+                        &schema,
+                        &table,
+                        &details.columns,
+                        details.primary_key().unwrap_or_default(),
+                    );
+                    self.use_table_template(&key, &schema, &table, &kind, template, sql);
+                }
+                Err(e) => {
+                    self.definition_viewer = Some(DefinitionViewer {
+                        title: format!("{} ({kind})", qualified_name(&schema, &table)),
+                        definition: Some(Err(e)),
+                    });
+                }
+            },
+        }
+    }
+
+    /// Builds a statement from the table context menu, loading the table's
+    /// columns first if they haven't been yet.
+    pub fn open_table_template(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: &str,
+        kind: &str,
+        template: TableTemplate,
+    ) {
+        let action = AfterDetails::Template(kind.to_string(), template);
+        self.with_table_details(ctx, key, schema, table, action, false);
+    }
+
+    /// Adds a statement from the table context menu to the editor and runs
+    /// it when the template asks for that. Destructive ones are confirmed first.
+    fn use_table_template(
+        &mut self,
+        key: &str,
+        schema: &str,
+        table: &str,
+        kind: &str,
+        template: TableTemplate,
+        sql: String,
+    ) {
+        if template.is_destructive() {
+            self.set_active_connection(key);
+            if self.active_connection_key == key {
+                let name = qualified_name(schema, table);
+                let warning = if template == TableTemplate::Drop {
+                    format!("This drops the {kind} {name}.")
+                } else {
+                    format!("This removes every row of {name}.")
+                };
+                self.confirm_query(sql, warning);
+            }
+            return;
+        }
+
+        if self.table_queries_are_additive && !self.code.trim().is_empty() {
+            self.code = format!("{}\n\n{sql}", self.code.trim_end());
+        } else {
+            self.code = sql;
+        }
+        if template.runs() {
+            self.set_active_connection(key);
+            if self.active_connection_key == key {
+                self.execute_requested = true;
+            }
+        }
+    }
+
+    /// Why changes can't be made on a live connection, and the name of the
+    /// connection if it is a production one.
+    fn change_guards(&self, key: &str) -> (Option<String>, Option<String>) {
+        let conn = self
+            .live_connections
+            .iter()
+            .find(|live| live.key == key)
+            .and_then(|live| {
+                self.connection_list
+                    .iter()
+                    .find(|conn| conn.id == live.connection_id)
+            });
+        match conn {
+            Some(conn) => (
+                conn.read_only
+                    .then(|| String::from("The connection is read-only.")),
+                conn.production.then(|| conn.connection_name.clone()),
+            ),
+            None => (None, None),
+        }
+    }
+
+    /// Counts a table's rows in the background, for the label in the table list.
+    pub fn load_table_size(&mut self, ctx: &egui::Context, key: &str, schema: &str, table: &str) {
+        let Some(pool) = self.live_pool(key) else {
+            return;
+        };
+        let tx = self.table_event_tx.clone();
+        let ctx = ctx.clone();
+        let key = key.to_string();
+        let table = (schema.to_string(), table.to_string());
+        tokio::spawn(async move {
+            let size = get_table_size(&pool, &table.0, &table.1)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = tx.send(TableEvent::Size { key, table, size });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Shows a table in the details panel, loading its statistics if the
+    /// panel is open.
+    pub fn select_table(&mut self, ctx: &egui::Context, key: &str, schema: &str, table: &str) {
+        if !self
+            .table_info
+            .as_ref()
+            .is_some_and(|info| info.is(key, schema, table))
+        {
+            self.table_info = Some(TableInfo::new(key, schema, table));
+        }
+        if self.show_table_info
+            && self
+                .table_info
+                .as_ref()
+                .is_some_and(|info| info.stats.is_none() && !info.loading)
+        {
+            self.load_table_stats(ctx);
+        }
+    }
+
+    /// Reads the sizes and statistics of the table in the details panel in
+    /// the background.
+    pub fn load_table_stats(&mut self, ctx: &egui::Context) {
+        let Some(pool) = self
+            .table_info
+            .as_ref()
+            .and_then(|info| self.live_pool(&info.key))
+        else {
+            return;
+        };
+        let Some(info) = self.table_info.as_mut() else {
+            return;
+        };
+        info.loading = true;
+        let tx = self.table_event_tx.clone();
+        let ctx = ctx.clone();
+        let key = info.key.clone();
+        let table = (info.schema.clone(), info.table.clone());
+        tokio::spawn(async move {
+            let stats = tokio::try_join!(
+                get_table_stats(&pool, &table.0, &table.1),
+                get_index_stats(&pool, &table.0, &table.1)
+            )
+            .map(Box::new)
+            .map_err(|e| e.to_string());
+            let _ = tx.send(TableEvent::Stats { key, table, stats });
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn load_table_estimates(&mut self, ctx: &egui::Context, key: &str) {
+        let key_owned = key.to_string();
+        self.spawn_catalog_load(ctx, key, CatalogLoad::Estimates, |pool| async move {
+            let estimates = get_table_estimates(&pool)
+                .await
+                .map(|estimates| {
+                    estimates
+                        .into_iter()
+                        .map(|estimate| {
+                            ((estimate.schema.clone(), estimate.table.clone()), estimate)
+                        })
+                        .collect()
+                })
+                .map_err(|e| e.to_string());
+            CatalogEvent::Estimates {
+                key: key_owned,
+                estimates,
+            }
+        });
+    }
+
+    pub fn open_data_editor(&mut self, ctx: &egui::Context, key: &str, schema: &str, table: &str) {
+        self.with_table_details(ctx, key, schema, table, AfterDetails::DataEditor, false);
+    }
+
+    fn show_data_editor(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: &str,
+        details: Result<TableDetails, String>,
+    ) {
+        let mut editor = match details {
+            Ok(details) => DataEditor::new(key, schema, table, &details),
+            Err(e) => {
+                let mut editor = DataEditor::new(key, schema, table, &TableDetails::default());
+                editor.error = Some(e);
+                editor
+            }
+        };
+        let (read_only, production) = self.change_guards(key);
+        if read_only.is_some() {
+            editor.read_only = read_only;
+        }
+        editor.production = production;
+        let has_columns = !editor.columns.is_empty();
+        self.data_editor = Some(editor);
+        if has_columns {
+            self.load_editor_rows(ctx);
+        }
+    }
+
+    fn load_editor_rows(&mut self, ctx: &egui::Context) {
+        let pool = self
+            .data_editor
+            .as_ref()
+            .and_then(|editor| self.live_pool(&editor.key));
+        let (Some(editor), Some(pool)) = (&mut self.data_editor, pool) else {
+            return;
+        };
+        editor.loading = true;
+        editor.error = None;
+        let id = editor.id.clone();
+        let sql = editor.select_sql();
+        let tx = self.table_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let rows = fetch_text_rows(&pool, &sql)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = tx.send(TableEvent::EditorRows { id, rows });
+            ctx.request_repaint();
+        });
+    }
+
+    fn save_data_editor(&mut self, ctx: &egui::Context) {
+        let pool = self
+            .data_editor
+            .as_ref()
+            .and_then(|editor| self.live_pool(&editor.key));
+        let (Some(editor), Some(pool)) = (&mut self.data_editor, pool) else {
+            return;
+        };
+        editor.saving = true;
+        editor.confirm_save = false;
+        editor.error = None;
+        let id = editor.id.clone();
+        let changes = editor.changes();
+        let tx = self.table_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let result = apply_row_changes(&pool, changes).await;
+            let _ = tx.send(TableEvent::EditorSaved { id, result });
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn load_data_types(&mut self, ctx: &egui::Context, key: &str) {
+        let key_owned = key.to_string();
+        self.spawn_catalog_load(ctx, key, CatalogLoad::DataTypes, |pool| async move {
+            let types = get_data_types(&pool).await.map_err(|e| e.to_string());
+            CatalogEvent::DataTypes {
+                key: key_owned,
+                types,
+            }
+        });
+    }
+
+    /// Opens the table designer on an existing table, or on a new one in
+    /// `schema` when `table` is `None`.
+    pub fn open_table_designer(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: Option<&str>,
+    ) {
+        if self
+            .live_connections
+            .iter()
+            .any(|live| live.key == key && live.data_types.is_none())
+        {
+            self.load_data_types(ctx, key);
+        }
+
+        match table {
+            // Changes are worked out against the catalog as it is now.
+            Some(table) => self.with_table_details(
+                ctx,
+                key,
+                schema,
+                table,
+                AfterDetails::Designer(String::new()),
+                true,
+            ),
+            None => self.show_table_designer(key, None, TableDesign::new(schema)),
+        }
+    }
+
+    fn show_table_designer(
+        &mut self,
+        key: &str,
+        original: Option<TableDesign>,
+        design: TableDesign,
+    ) {
+        let mut designer = TableDesigner::new(key, original, design);
+        (designer.read_only, designer.production) = self.change_guards(key);
+        self.table_designer = Some(designer);
+    }
+
+    fn apply_table_design(&mut self, ctx: &egui::Context) {
+        let pool = self
+            .table_designer
+            .as_ref()
+            .and_then(|designer| self.live_pool(&designer.key));
+        let (Some(designer), Some(pool)) = (&mut self.table_designer, pool) else {
+            return;
+        };
+        let statements = match designer.script() {
+            Ok(statements) => statements,
+            Err(e) => {
+                designer.error = Some(e);
+                return;
+            }
+        };
+        designer.applying = true;
+        designer.confirm_apply = false;
+        designer.error = None;
+        let id = designer.id.clone();
+        let tx = self.table_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let result = apply_ddl(&pool, statements).await;
+            let _ = tx.send(TableEvent::DesignApplied { id, result });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Opens the schema compare, with the editor's connection as the source.
+    pub fn open_schema_compare(&mut self) {
+        let source = self.active_connection_key.clone();
+        let target = self
+            .live_connections
+            .iter()
+            .find(|live| live.key != source)
+            .map(|live| live.key.clone())
+            .unwrap_or_default();
+        self.schema_compare = Some(SchemaCompare::new(&source, &target));
+    }
+
+    fn compare_schemas(&mut self, ctx: &egui::Context) {
+        let Some(compare) = &mut self.schema_compare else {
+            return;
+        };
+        let (Some(source), Some(target)) = (
+            self.live_connections
+                .iter()
+                .find(|live| live.key == compare.source_key),
+            self.live_connections
+                .iter()
+                .find(|live| live.key == compare.target_key),
+        ) else {
+            compare.error = Some(String::from("Both connections must be open"));
+            return;
+        };
+        let (source, target) = (source.pool.clone(), target.pool.clone());
+        compare.loading = true;
+        compare.error = None;
+        let id = compare.id.clone();
+        let tx = self.compare_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let result =
+                tokio::try_join!(get_schema_snapshot(&source), get_schema_snapshot(&target))
+                    .map_err(|e| e.to_string());
+            let _ = tx.send(CompareEvent::Schemas { id, result });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Opens the object search, loading the objects of connections that
+    /// haven't been searched yet.
+    pub fn open_object_search(&mut self, ctx: &egui::Context) {
+        let mut search = ObjectSearch::default();
+        let mut unloaded = Vec::new();
+        for live in &self.live_connections {
+            match &live.search_objects {
+                Some(Ok(objects)) => search.objects.extend(
+                    objects
+                        .iter()
+                        .map(|object| (live.key.clone(), object.clone())),
+                ),
+                Some(Err(e)) => search.errors.push(format!("{}: {e}", live.label())),
+                None => unloaded.push(live.key.clone()),
+            }
+        }
+        search.loading = unloaded.len();
+        self.object_search = Some(search);
+
+        for key in unloaded {
+            let key_owned = key.clone();
+            self.spawn_catalog_load(ctx, &key, CatalogLoad::SearchObjects, |pool| async move {
+                let objects = get_search_objects(&pool).await.map_err(|e| e.to_string());
+                CatalogEvent::SearchObjects {
+                    key: key_owned,
+                    objects,
+                }
+            });
+        }
+    }
+
+    /// Opens the data compare from a connection, on one of its tables when given.
+    pub fn open_data_compare(&mut self, key: &str, table: Option<(String, String)>) {
+        let target = self
+            .live_connections
+            .iter()
+            .find(|live| live.key != key)
+            .map(|live| live.key.clone())
+            .unwrap_or_default();
+        self.data_compare = Some(DataCompare::new(key, &target, table));
+    }
+
+    fn compare_data(&mut self, ctx: &egui::Context) {
+        let Some(compare) = &mut self.data_compare else {
+            return;
+        };
+        let spec = match compare.spec() {
+            Ok(spec) => spec,
+            Err(e) => {
+                compare.error = Some(e);
+                return;
+            }
+        };
+        let (Some(source), Some(target)) = (
+            self.live_connections
+                .iter()
+                .find(|live| live.key == compare.source_key),
+            self.live_connections
+                .iter()
+                .find(|live| live.key == compare.target_key),
+        ) else {
+            compare.error = Some(String::from("Both connections must be open"));
+            return;
+        };
+        let (source, target) = (source.pool.clone(), target.pool.clone());
+        compare.id = new_connection_id();
+        compare.error = None;
+        compare.progress = 0;
+        compare.sync_table = match (compare.use_queries, &compare.target_table) {
+            (false, Some((schema, table))) => Some(qualified_name(schema, table)),
+            _ => None,
+        };
+        compare.set_result(None);
+        let id = compare.id.clone();
+        let tx = self.compare_event_tx.clone();
+        let ctx = ctx.clone();
+        compare.task = Some(tokio::spawn(async move {
+            let progress = |rows| {
+                let _ = tx.send(CompareEvent::DataProgress {
+                    id: id.clone(),
+                    rows,
+                });
+                ctx.request_repaint();
+            };
+            let result = compare_data(&source, &target, &spec, progress).await;
+            let _ = tx.send(CompareEvent::Data { id, result });
+            ctx.request_repaint();
+        }));
+    }
+
+    fn handle_compare_event(&mut self, event: CompareEvent) {
+        match event {
+            CompareEvent::Schemas { id, result } => {
+                let Some(compare) = self.schema_compare.as_mut().filter(|c| c.id == id) else {
+                    return;
+                };
+                compare.loading = false;
+                match result {
+                    Ok((source, target)) => compare.set_snapshots(source, target),
+                    Err(e) => {
+                        eprintln!("Failed to compare schemas: {e}");
+                        compare.error = Some(e);
+                    }
+                }
+            }
+            CompareEvent::DataProgress { id, rows } => {
+                if let Some(compare) = self.data_compare.as_mut().filter(|c| c.id == id) {
+                    compare.progress = rows;
+                }
+            }
+            CompareEvent::Data { id, result } => {
+                let Some(compare) = self.data_compare.as_mut().filter(|c| c.id == id) else {
+                    return;
+                };
+                compare.task = None;
+                match result {
+                    Ok(diff) => compare.set_result(Some(diff)),
+                    Err(e) => {
+                        eprintln!("Failed to compare data: {e}");
+                        compare.error = Some(e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Opens the ER diagram of a schema. When `table` is given only it and
+    /// the tables related to it are shown.
+    pub fn open_er_diagram(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        schema: &str,
+        table: Option<&str>,
+    ) {
+        let Some(live) = self.live_connections.iter().find(|live| live.key == key) else {
+            return;
+        };
+        let layout_key = format!("{}/{}", live.connection_id, live.database);
+        let pool = live.pool.clone();
+        let key = key.to_string();
+        let schema = schema.to_string();
+        let table = table.map(str::to_string);
+        let tx = self.catalog_event_tx.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let columns = get_diagram_columns(&pool, &schema).await?;
+                let foreign_keys = get_diagram_foreign_keys(&pool, &schema).await?;
+                Ok::<_, sqlx::Error>((columns, foreign_keys))
+            }
+            .await
+            .map_err(|e| e.to_string());
+            let _ = tx.send(CatalogEvent::ErDiagram {
+                key,
+                layout_key,
+                schema,
+                table,
+                result,
+            });
+            ctx.request_repaint();
+        });
+    }
+
+    fn export_er_diagram_svg(&mut self) {
+        let Some(diagram) = &mut self.er_diagram else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("SVG", &["svg"])
+            .set_file_name(format!("{}.svg", diagram.schema))
+            .save_file()
+        else {
+            return;
+        };
+        match fs::write(&path, diagram.to_svg()) {
+            Ok(()) => {
+                diagram.error = None;
+                diagram.status = format!("Saved {}", path.display());
+            }
+            Err(e) => diagram.error = Some(format!("Failed to save {}: {e}", path.display())),
+        }
+    }
+
+    /// Saves the part of the screenshot that shows the diagram canvas.
+    fn export_er_diagram_png(&mut self, ctx: &egui::Context, image: &egui::ColorImage) {
+        let Some(diagram) = &mut self.er_diagram else {
+            return;
+        };
+        let pixels_per_point = ctx.pixels_per_point();
+        let screen = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(image.width() as f32, image.height() as f32) / pixels_per_point,
+        );
+        let canvas = diagram.canvas.intersect(screen);
+        if !canvas.is_positive() {
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name(format!("{}.png", diagram.schema))
+            .save_file()
+        else {
+            return;
+        };
+        match write_png(&path, &image.region(&canvas, Some(pixels_per_point))) {
+            Ok(()) => {
+                diagram.error = None;
+                diagram.status = format!("Saved {}", path.display());
+            }
+            Err(e) => diagram.error = Some(format!("Failed to save {}: {e}", path.display())),
+        }
+    }
+
+    /// Marks a catalog load as done and returns its connection, unless the
+    /// connection was closed meanwhile.
+    fn finish_catalog_load(
+        &mut self,
+        key: &str,
+        load: &CatalogLoad,
+    ) -> Option<&mut LiveConnection> {
+        let live = self
+            .live_connections
+            .iter_mut()
+            .find(|live| live.key == key)?;
+        live.loading.remove(load);
+        Some(live)
+    }
+
+    fn handle_catalog_event(&mut self, ctx: &egui::Context, event: CatalogEvent) {
+        match event {
+            CatalogEvent::Details {
+                key,
+                table,
+                details,
+            } => {
+                let load = CatalogLoad::Details(table.0.clone(), table.1.clone());
+                let Some(live) = self.finish_catalog_load(&key, &load) else {
+                    return;
+                };
+                live.table_details
+                    .insert(table.clone(), details.map(|details| *details));
+                if self
+                    .after_details
+                    .as_ref()
+                    .is_some_and(|(pending, name, _)| *pending == key && *name == table)
+                {
+                    self.open_after_details(ctx);
+                }
+            }
+            CatalogEvent::Objects {
+                key,
+                schema,
+                objects,
+            } => {
+                let load = CatalogLoad::Objects(schema.clone());
+                if let Some(live) = self.finish_catalog_load(&key, &load) {
+                    live.schema_objects.insert(schema, objects);
+                }
+            }
+            CatalogEvent::Extensions { key, extensions } => {
+                if let Some(live) = self.finish_catalog_load(&key, &CatalogLoad::Extensions) {
+                    live.extensions = Some(extensions);
+                }
+            }
+            CatalogEvent::Estimates { key, estimates } => {
+                if let Some(live) = self.finish_catalog_load(&key, &CatalogLoad::Estimates) {
+                    live.table_estimates = Some(estimates);
+                }
+            }
+            CatalogEvent::SearchObjects { key, objects } => {
+                let Some(live) = self.finish_catalog_load(&key, &CatalogLoad::SearchObjects) else {
+                    return;
+                };
+                let label = live.label();
+                live.search_objects = Some(objects.clone());
+                if let Some(search) = &mut self.object_search {
+                    search.loading = search.loading.saturating_sub(1);
+                    match objects {
+                        Ok(objects) => search
+                            .objects
+                            .extend(objects.into_iter().map(|object| (key.clone(), object))),
+                        Err(e) => search.errors.push(format!("{label}: {e}")),
+                    }
+                    search.update_results();
+                }
+            }
+            CatalogEvent::DataTypes { key, types } => {
+                if let Some(live) = self.finish_catalog_load(&key, &CatalogLoad::DataTypes) {
+                    live.data_types = Some(types);
+                }
+            }
+            CatalogEvent::Definition {
+                title,
+                copy,
+                definition,
+            } => match definition {
+                Ok(definition) if copy => ctx.copy_text(definition),
+                definition => {
+                    // A viewer closed while it was loading stays closed.
+                    if copy
+                        || self
+                            .definition_viewer
+                            .as_ref()
+                            .is_some_and(|viewer| viewer.title == title)
+                    {
+                        self.definition_viewer = Some(DefinitionViewer {
+                            title,
+                            definition: Some(definition),
+                        });
+                    }
+                }
+            },
+            CatalogEvent::ErDiagram {
+                key,
+                layout_key,
+                schema,
+                table,
+                result,
+            } => match result {
+                Ok((columns, foreign_keys)) => {
+                    let mut diagram = ErDiagram::new(
+                        &key,
+                        &layout_key,
+                        &schema,
+                        columns,
+                        foreign_keys,
+                        self.diagram_layouts.get(&layout_key),
+                    );
+                    if let Some(table) = table {
+                        diagram.focus(&qualified_name(&schema, &table));
+                    }
+                    self.er_diagram = Some(diagram);
+                }
+                Err(e) => {
+                    self.definition_viewer = Some(DefinitionViewer {
+                        title: format!("{schema} (ER diagram)"),
+                        definition: Some(Err(e)),
+                    });
+                }
+            },
+        }
+    }
+
+    fn handle_table_event(&mut self, ctx: &egui::Context, event: TableEvent) {
+        match event {
+            TableEvent::Size { key, table, size } => {
+                if let Err(e) = &size {
+                    eprintln!("Failed to count the rows of {}.{}: {e}", table.0, table.1);
+                }
+                if let Some(live) = self
+                    .live_connections
+                    .iter_mut()
+                    .find(|live| live.key == key)
+                {
+                    live.table_sizes.insert(table, size);
+                }
+            }
+            TableEvent::Stats { key, table, stats } => {
+                if let Err(e) = &stats {
+                    eprintln!(
+                        "Failed to read the statistics of {}.{}: {e}",
+                        table.0, table.1
+                    );
+                }
+                if let Some(info) = self
+                    .table_info
+                    .as_mut()
+                    .filter(|info| info.is(&key, &table.0, &table.1))
+                {
+                    info.stats = Some(stats.map(|stats| *stats));
+                    info.loading = false;
+                }
+            }
+            TableEvent::EditorRows { id, rows } => {
+                let Some(editor) = self.data_editor.as_mut().filter(|editor| editor.id == id)
+                else {
+                    return;
+                };
+                editor.loading = false;
+                match rows {
+                    Ok(rows) => editor.set_rows(rows),
+                    Err(e) => editor.error = Some(e),
+                }
+            }
+            TableEvent::EditorSaved { id, result } => {
+                let Some(editor) = self.data_editor.as_mut().filter(|editor| editor.id == id)
+                else {
+                    return;
+                };
+                editor.saving = false;
+                match result {
+                    Ok(count) => {
+                        editor.status = format!("Saved {count} changes");
+                        self.load_editor_rows(ctx);
+                    }
+                    Err(e) => editor.error = Some(e),
+                }
+            }
+            TableEvent::DesignApplied { id, result } => {
+                let Some(designer) = self
+                    .table_designer
+                    .as_mut()
+                    .filter(|designer| designer.id == id)
+                else {
+                    return;
+                };
+                designer.applying = false;
+                if let Err(e) = result {
+                    designer.error = Some(e);
+                    return;
+                }
+                // Reopen on the table as it is now, so further changes are
+                // worked out against the new catalog state.
+                let key = designer.key.clone();
+                let schema = designer.design.schema.trim().to_string();
+                let name = designer.design.name.trim().to_string();
+                self.refresh_tables(&key);
+                self.load_data_types(ctx, &key);
+                let action = AfterDetails::Designer(String::from("Applied."));
+                self.with_table_details(ctx, &key, &schema, &name, action, true);
+            }
+        }
+    }
+
+    fn get_databases(&mut self) {
+        self.databases = Vec::new();
+        let db_pool = self.db_pool();
+        let databases_rows_ref = &mut self.databases;
+
+        let runtime = Runtime::new().expect("Failed to create runtime");
+        runtime.block_on(async move {
+            if let Some(pool) = &db_pool {
+                match get_database_names(pool).await {
+                    Ok(rows) => {
+                        if !rows.is_empty() {
+                            *databases_rows_ref = rows;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    fn get_running_queries(&mut self) {
+        self.running_queries = Vec::new();
+        let db_pool = self.db_pool();
+        let running_queries_rows_ref = &mut self.running_queries;
+
+        let runtime = Runtime::new().expect("Failed to create runtime");
+        runtime.block_on(async move {
+            if let Some(pool) = &db_pool {
+                match get_running_queries_data(pool).await {
+                    Ok(rows) => {
+                        if !rows.is_empty() {
+                            *running_queries_rows_ref = rows;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl eframe::App for Rosemary {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.live_connections.is_empty() {
+            self.connection_modal_open = true;
+        }
+
+        if !self.vault_migration_checked {
+            self.vault_migration_checked = true;
+            if self.has_plaintext_passwords() {
+                self.prompt_for_vault(
+                    "Some saved passwords are stored in plain text. \
+                     Set a master password to move them into an encrypted vault.",
+                );
+            }
+        }
+
+        while let Ok(status) = self.transaction_status_rx.try_recv() {
+            self.transaction_status = status;
+        }
+        while let Ok(result) = self.transaction_end_rx.try_recv() {
+            self.ending_transaction = false;
+            match result {
+                Ok(()) => {
+                    if let Some(action) = self.after_transaction.take() {
+                        self.pending_transaction_action = None;
+                        self.resume_after_transaction(ctx, action);
+                    }
+                }
+                Err(e) => {
+                    // The "Open transaction" window stays open with the error.
+                    eprintln!("{e}");
+                    self.after_transaction = None;
+                    self.transaction_error = Some(e);
+                }
+            }
+        }
+
+        while let Ok(event) = self.table_event_rx.try_recv() {
+            self.handle_table_event(ctx, event);
+        }
+        while let Ok(event) = self.compare_event_rx.try_recv() {
+            self.handle_compare_event(event);
+        }
+        while let Ok(event) = self.catalog_event_rx.try_recv() {
+            self.handle_catalog_event(ctx, event);
+        }
+
+        if let Ok(result) = self.connect_rx.try_recv() {
+            self.finish_connect(result);
+        }
+        if let Ok(result) = self.connection_test_rx.try_recv() {
+            self.connection_test_task = None;
+            self.connection_test_result = Some(result);
+        }
+        if self.is_connecting() || self.connection_test_task.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.transaction_status.is_open()
+            && !self.allow_close
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.pending_transaction_action = Some(PendingTransactionAction::Close);
+        } else if ctx.input(|i| i.viewport().close_requested())
+            && self.editor_file.is_some()
+            && self.is_editor_dirty()
+            && !self.allow_close_unsaved
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.pending_file_action = Some(PendingFileAction::Close);
+        }
+
+        self.check_for_external_changes(ctx);
+        self.update_window_title(ctx);
+
+        if ctx.input_mut(|i| {
+            i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::S,
+            )
+        }) {
+            self.save_file_as();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::S)) {
+            self.save_file();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P))
+            && !self.live_connections.is_empty()
+        {
+            self.open_object_search(ctx);
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::O)) {
+            if let Some(path) = sql_file_dialog().pick_file() {
+                self.request_file_action(PendingFileAction::Open(path));
+            }
         }
 
-        if self.db_pool.is_some() && self.should_fetch_table_list {
+        if self
+            .live_connections
+            .iter()
+            .any(|live| live.should_fetch_table_list)
+        {
             self.get_tables();
         }
 
         set_theme(ctx, ROSEMARY_DARK);
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-                });
-                ui.menu_button("Connection", |ui| {
-                    if ui.button("Connections").clicked() {
-                        self.connection_modal_open = true;
-                    }
-                    if ui.button("Databases").clicked() {
-                        self.db_select_modal_open = true;
-                        self.get_databases();
-                    }
-                });
-                ui.menu_button("Queries", |ui| {
-                    ui.checkbox(&mut self.table_queries_are_additive, "Additive queries");
-                    ui.separator();
-                    if ui.button("Running queries").clicked() {
-                        self.running_queries_modal_open = true;
-                        self.get_running_queries();
-                    }
-                    if ui.button("Cancel running query").clicked() {
-                        if let Ok(pid) = self.query_pid_rx.try_recv() {
-                            let db_pool = self.db_pool.clone().unwrap();
-                            tokio::spawn(async move {
-                                if let Err(err) = cancel_query(&db_pool, pid).await {
-                                    eprintln!("Failed to cancel query: {}", err);
+        let chrome = self.connection_chrome(ctx);
+        egui::TopBottomPanel::top("top_panel")
+            .frame(chrome)
+            .show(ctx, |ui| {
+                egui::menu::bar(ui, |ui| {
+                    ui.menu_button("File", |ui| {
+                        if ui.button("New").clicked() {
+                            ui.close_menu();
+                            self.request_file_action(PendingFileAction::New);
+                        }
+                        if ui.button("Open…").clicked() {
+                            ui.close_menu();
+                            if let Some(path) = sql_file_dialog().pick_file() {
+                                self.request_file_action(PendingFileAction::Open(path));
+                            }
+                        }
+                        ui.add_enabled_ui(!self.recent_files.is_empty(), |ui| {
+                            ui.menu_button("Recent Files", |ui| {
+                                let mut to_open = None;
+                                for path in &self.recent_files {
+                                    if ui.button(path.display().to_string()).clicked() {
+                                        to_open = Some(path.clone());
+                                    }
+                                }
+                                ui.separator();
+                                if ui.button("Clear").clicked() {
+                                    self.recent_files.clear();
+                                    ui.close_menu();
+                                }
+                                if let Some(path) = to_open {
+                                    ui.close_menu();
+                                    self.request_file_action(PendingFileAction::Open(path));
                                 }
                             });
+                        });
+                        ui.separator();
+                        if ui.button("Save").clicked() {
+                            ui.close_menu();
+                            self.save_file();
+                        }
+                        if ui.button("Save As…").clicked() {
+                            ui.close_menu();
+                            self.save_file_as();
+                        }
+                        ui.separator();
+                        if ui.button("Quit").clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    });
+                    ui.menu_button("Connection", |ui| {
+                        if ui.button("Connections").clicked() {
+                            self.connection_modal_open = true;
+                        }
+                        if ui.button("Databases").clicked() {
+                            self.open_databases();
+                        }
+                        if ui
+                            .add_enabled(
+                                !self.live_connections.is_empty(),
+                                egui::Button::new("Find object…").shortcut_text("Ctrl+P"),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.open_object_search(ctx);
+                        }
+                    });
+                    ui.menu_button("Compare", |ui| {
+                        let enabled = self.live_connections.len() > 1;
+                        if ui
+                            .add_enabled(enabled, egui::Button::new("Schemas…"))
+                            .on_disabled_hover_text("Open two connections to compare them")
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.open_schema_compare();
+                        }
+                        if ui
+                            .add_enabled(enabled, egui::Button::new("Data…"))
+                            .on_disabled_hover_text("Open two connections to compare them")
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            let key = self.active_connection_key.clone();
+                            self.open_data_compare(&key, None);
+                        }
+                    });
+                    ui.menu_button("Queries", |ui| {
+                        ui.checkbox(&mut self.table_queries_are_additive, "Additive queries");
+                        ui.checkbox(&mut self.show_library, "Query library");
+                        if ui
+                            .checkbox(&mut self.show_table_info, "Table details")
+                            .changed()
+                        {
+                            if let Some(info) = &self.table_info {
+                                let (key, schema, table) =
+                                    (info.key.clone(), info.schema.clone(), info.table.clone());
+                                self.select_table(ctx, &key, &schema, &table);
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Running queries").clicked() {
+                            self.running_queries_modal_open = true;
+                            self.get_running_queries();
                         }
+                        if ui.button("Cancel running query").clicked() {
+                            // The PID belongs to the server the query was sent to.
+                            let db_pool = self
+                                .live_connections
+                                .iter()
+                                .find(|live| live.key == self.query_connection_key)
+                                .map(|live| live.pool.clone());
+                            if let (Ok(pid), Some(db_pool)) =
+                                (self.query_pid_rx.try_recv(), db_pool)
+                            {
+                                tokio::spawn(async move {
+                                    if let Err(err) = cancel_query(&db_pool, pid).await {
+                                        eprintln!("Failed to cancel query: {}", err);
+                                    }
+                                });
+                            }
+                        }
+                    });
+                    if self.is_connecting() {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.horizontal(|ui| show_connect_progress(ui, self));
+                        });
+                    } else if self.active_connection().is_some() {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            show_connection_status(ui, self)
+                        });
                     }
                 });
             });
-        });
         //TODO: Make this more dynamic
-        let mut should_execute = false;
+        let mut should_execute = std::mem::take(&mut self.execute_requested);
         let mut should_execute_secondary = false;
 
         for key in 0..=9 {
@@ -387,15 +3100,55 @@ impl eframe::App for Rosemary {
             }
         }
 
+        if let Some(conn) = self.connected_connection().filter(|conn| conn.production) {
+            let text = format!(
+                "PRODUCTION: {}. Statements that change data or the schema ask for confirmation.",
+                conn.connection_name
+            );
+            egui::TopBottomPanel::top("production_banner")
+                .frame(
+                    egui::Frame::side_top_panel(&ctx.style())
+                        .fill(egui::Color32::from_rgb(160, 20, 20)),
+                )
+                .show(ctx, |ui| {
+                    ui.label(
+                        egui::RichText::new(text)
+                            .strong()
+                            .color(egui::Color32::WHITE),
+                    );
+                });
+        }
+
+        if self.show_library {
+            egui::SidePanel::right("library").show(ctx, |ui| {
+                show_library_panel(ui, self);
+            });
+        }
+
+        if self.show_table_info {
+            let connection = self.table_info.as_ref().and_then(|info| {
+                self.live_connections
+                    .iter()
+                    .find(|live| live.key == info.key)
+                    .map(|live| live.label())
+            });
+            let mut info_action = None;
+            egui::SidePanel::right("table_info")
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    info_action =
+                        show_table_info_panel(ui, self.table_info.as_ref(), connection.as_deref());
+                });
+            if let Some(TableInfoAction::Reload) = info_action {
+                self.load_table_stats(ctx);
+            }
+        }
+
         egui::SidePanel::left("editor").show(ctx, |ui| {
             show_editor_panel(ui, self, &mut should_execute, &mut should_execute_secondary);
             ui.separator();
             if self.show_table_list {
-                let shift_pressed = if ctx.input(|i| i.modifiers.shift) {
-                    true
-                } else {
-                    false
-                };
+                let shift_pressed = ctx.input(|i| i.modifiers.shift);
                 show_tables_panel(
                     ui,
                     self,
@@ -407,11 +3160,9 @@ impl eframe::App for Rosemary {
         });
 
         if (should_execute || should_execute_secondary) && !self.code.trim().is_empty() {
-            let query_vec: Vec<&str> = self
-                .code
-                .split(';')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
+            let query_vec: Vec<&str> = split_statements(&self.code)
+                .into_iter()
+                .map(|statement| statement.text)
                 .collect();
 
             let idx = self.query_to_execute.saturating_sub(1);
@@ -431,17 +3182,7 @@ impl eframe::App for Rosemary {
             //to allow for tabs in the future...
             let query_idx = if should_execute { 0 } else { 1 };
 
-            self.reset_query_result_data(query_idx);
-
-            let db_pool = self.db_pool.clone();
-            let tx = self.query_result_tx.clone();
-            let pid_tx = self.query_pid_tx.clone();
-            let ctx = ctx.clone();
-
-            tokio::spawn(async move {
-                execute_query(&db_pool, query_str, tx, pid_tx).await;
-                ctx.request_repaint();
-            });
+            self.submit_query(ctx, query_str, query_idx);
         }
 
         if let Ok(q_res) = self.query_result_rx.try_recv() {
@@ -457,10 +3198,8 @@ impl eframe::App for Rosemary {
                     String::from("error_message"),
                     String::from(ROSEMARY_SORT_COL_STR),
                 ];
-                self.query_results[query_idx].parsed_res_rows = vec![vec![
-                    CellValue::Text(error_message),
-                    CellValue::BigInt(0 as i64),
-                ]];
+                self.query_results[query_idx].parsed_res_rows =
+                    vec![vec![CellValue::Text(error_message), CellValue::BigInt(0)]];
             } else if !res_rows.is_empty() {
                 let mut col_names: Vec<String> = res_rows[0]
                     .columns()
@@ -481,7 +3220,7 @@ impl eframe::App for Rosemary {
                                 convert_type(
                                     col.type_info().to_string().to_uppercase().as_str(),
                                     col,
-                                    &row,
+                                    row,
                                 )
                             })
                             .collect();
@@ -516,10 +3255,12 @@ impl eframe::App for Rosemary {
             }
         });
 
-        egui::TopBottomPanel::bottom("pagination_panel").show(ctx, |ui| {
-            show_query_metrics_panel(ui, self);
-            show_pagination_panel(ui, self);
-        });
+        egui::TopBottomPanel::bottom("pagination_panel")
+            .frame(chrome)
+            .show(ctx, |ui| {
+                show_query_metrics_panel(ui, self);
+                show_pagination_panel(ui, self);
+            });
 
         if self.connection_modal_open {
             let mut connect_to_db = false;
@@ -535,28 +3276,29 @@ impl eframe::App for Rosemary {
             self.connection_modal_open = connections_modal_open;
 
             if connect_to_db {
-                self.connect_to_db();
+                self.request_connect_to_db();
             }
         }
 
-        if self.db_select_modal_open && self.db_pool.is_some() {
-            if self.databases.len() > 0 {
-                let mut connect_to_db = false;
+        if self.db_select_modal_open
+            && self.active_connection().is_some()
+            && !self.databases.is_empty()
+        {
+            let mut connect_to_db = false;
 
-                let mut db_select_modal_open = self.db_select_modal_open;
-                egui::Window::new("Databases")
-                    .collapsible(false)
-                    .resizable(false)
-                    .open(&mut db_select_modal_open)
-                    .show(ctx, |ui| {
-                        connect_to_db = show_databases_panel(ui, self);
-                    });
-                self.db_select_modal_open = db_select_modal_open;
+            let mut db_select_modal_open = self.db_select_modal_open;
+            egui::Window::new("Databases")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut db_select_modal_open)
+                .show(ctx, |ui| {
+                    connect_to_db = show_databases_panel(ui, self);
+                });
+            self.db_select_modal_open = db_select_modal_open;
 
-                if connect_to_db {
-                    self.connect_to_db();
-                    self.db_select_modal_open = false;
-                }
+            if connect_to_db {
+                self.request_connect_to_db();
+                self.db_select_modal_open = false;
             }
         }
 
@@ -571,5 +3313,568 @@ impl eframe::App for Rosemary {
                 });
             self.running_queries_modal_open = running_queries_modal_open;
         }
+
+        if self.credential_prompt.is_some() {
+            let mut credential_prompt_open = true;
+            let mut submit = false;
+            egui::Window::new("Credentials")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut credential_prompt_open)
+                .show(ctx, |ui| {
+                    submit = show_credentials_panel(ui, self);
+                });
+
+            if submit {
+                self.submit_credentials();
+            } else if !credential_prompt_open {
+                self.close_credential_prompt();
+                // A password waiting for the vault is kept, the connections
+                // panel offers to save it until the vault is unlocked.
+                self.connect_after_credentials = false;
+            }
+        }
+
+        if let Some(prompt) = &mut self.parameter_prompt {
+            let mut resolution = None;
+
+            egui::Window::new("Query parameters")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    resolution = show_query_params_panel(ui, prompt);
+                });
+
+            if let Some(execute) = resolution {
+                if let Some(prompt) = self.parameter_prompt.take() {
+                    if execute {
+                        let query = prompt.original_query.trim();
+                        self.recent_query_params
+                            .retain(|(recent, _)| recent != query);
+                        self.recent_query_params
+                            .insert(0, (query.to_string(), prompt.params.clone()));
+                        self.recent_query_params.truncate(MAX_RECENT_QUERY_PARAMS);
+                        self.execute(ctx, prompt.prepared_query, prompt.params, prompt.query_idx);
+                    }
+                }
+            }
+        }
+
+        if let Some(action) = self.pending_file_action.clone() {
+            let mut resolution = None;
+
+            egui::Window::new("Unsaved changes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let file_name = self
+                        .editor_file
+                        .as_deref()
+                        .and_then(Path::file_name)
+                        .map_or_else(
+                            || String::from("The editor"),
+                            |name| name.to_string_lossy().into_owned(),
+                        );
+                    ui.label(format!("{file_name} has unsaved changes."));
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            resolution = Some(Some(true));
+                        }
+                        if ui.button("Discard").clicked() {
+                            resolution = Some(Some(false));
+                        }
+                        if ui.button("Cancel").clicked() {
+                            resolution = Some(None);
+                        }
+                    });
+                });
+
+            if let Some(resolution) = resolution {
+                self.pending_file_action = None;
+                if let Some(save) = resolution {
+                    if !save || self.save_file() {
+                        let closing = action == PendingFileAction::Close;
+                        self.run_file_action(action);
+                        if closing {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.file_reload_prompt {
+            if let Some(path) = self.editor_file.clone() {
+                egui::Window::new("File changed on disk")
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.label(format!("{} was changed outside Rosemary.", path.display()));
+                        if self.is_editor_dirty() {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "Reloading will discard your unsaved changes.",
+                            );
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Reload").clicked() {
+                                self.open_file(path.clone());
+                            }
+                            if ui.button("Keep editor version").clicked() {
+                                self.editor_file_modified = file_modified(&path);
+                                self.file_reload_prompt = false;
+                            }
+                        });
+                    });
+            }
+        }
+
+        if !self.file_error.is_empty() {
+            let mut file_error_open = true;
+            egui::Window::new("File error")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut file_error_open)
+                .show(ctx, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, &self.file_error);
+                });
+            if !file_error_open {
+                self.file_error.clear();
+            }
+        }
+
+        if let Some(action) = self.pending_transaction_action.clone() {
+            let mut resolution = None;
+
+            egui::Window::new("Open transaction")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "The current session is {}.",
+                        self.transaction_status.label().to_lowercase()
+                    ));
+                    ui.label("Commit or roll back before continuing?");
+                    if let Some(e) = &self.transaction_error {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(!self.ending_transaction, |ui| {
+                            if ui.button("Commit").clicked() {
+                                resolution = Some(Some(true));
+                            }
+                            if ui.button("Rollback").clicked() {
+                                resolution = Some(Some(false));
+                            }
+                            if ui.button("Cancel").clicked() {
+                                resolution = Some(None);
+                            }
+                        });
+                        if self.ending_transaction {
+                            ui.spinner();
+                        }
+                    });
+                });
+
+            match resolution {
+                Some(Some(commit)) => {
+                    self.after_transaction = Some(action);
+                    self.finish_transaction(ctx, commit);
+                }
+                Some(None) => {
+                    self.pending_transaction_action = None;
+                    self.transaction_error = None;
+                }
+                None => {}
+            }
+        }
+
+        if let Some(viewer) = &self.definition_viewer {
+            let mut open = true;
+            let mut to_editor = None;
+
+            egui::Window::new(&viewer.title)
+                .id(egui::Id::new("definition_viewer"))
+                .open(&mut open)
+                .resizable(true)
+                .show(ctx, |ui| match &viewer.definition {
+                    None => {
+                        ui.spinner();
+                    }
+                    Some(Ok(definition)) => {
+                        ui.horizontal(|ui| {
+                            if ui.button("Copy").clicked() {
+                                ui.ctx().copy_text(definition.clone());
+                            }
+                            if ui.button("Open in editor").clicked() {
+                                to_editor = Some(definition.clone());
+                            }
+                        });
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(
+                                egui::TextEdit::multiline(&mut definition.as_str())
+                                    .code_editor()
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                });
+
+            if let Some(definition) = to_editor {
+                if !self.code.trim().is_empty() {
+                    self.code.push_str("\n\n");
+                }
+                self.code.push_str(&definition);
+                open = false;
+            }
+            if !open {
+                self.definition_viewer = None;
+            }
+        }
+
+        if let Some(editor) = &mut self.data_editor {
+            let mut open = true;
+            let mut editor_action = None;
+
+            egui::Window::new(format!("Edit {}", editor.title()))
+                .id(egui::Id::new("data_editor"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([800.0, 500.0])
+                .show(ctx, |ui| {
+                    editor_action = show_data_editor_panel(ui, editor);
+                });
+
+            match editor_action {
+                Some(DataEditorAction::Reload) => {
+                    editor.status.clear();
+                    self.load_editor_rows(ctx);
+                }
+                Some(DataEditorAction::Save) => self.save_data_editor(ctx),
+                Some(DataEditorAction::PreviousPage) => {
+                    editor.offset = editor.offset.saturating_sub(PAGE_SIZE);
+                    editor.status.clear();
+                    self.load_editor_rows(ctx);
+                }
+                Some(DataEditorAction::NextPage) => {
+                    editor.offset += PAGE_SIZE;
+                    editor.status.clear();
+                    self.load_editor_rows(ctx);
+                }
+                None => {}
+            }
+            if !open {
+                self.data_editor = None;
+            }
+        }
+
+        if let Some(designer) = &mut self.table_designer {
+            let mut open = true;
+            let mut designer_action = None;
+            let live = self
+                .live_connections
+                .iter()
+                .find(|live| live.key == designer.key);
+            let (types, types_error) = match live.and_then(|live| live.data_types.as_ref()) {
+                Some(Ok(types)) => (types.as_slice(), None),
+                Some(Err(e)) => (&[][..], Some(e)),
+                None => (&[][..], None),
+            };
+            let tables: Vec<String> = live
+                .map(|live| {
+                    live.tables
+                        .iter()
+                        .filter(|table| table.table_type.as_deref() == Some("BASE TABLE"))
+                        .filter_map(|table| {
+                            Some(qualified_name(
+                                table.table_schema.as_deref()?,
+                                table.table_name.as_deref()?,
+                            ))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            egui::Window::new(designer.title())
+                .id(egui::Id::new("table_designer"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([900.0, 600.0])
+                .show(ctx, |ui| {
+                    if let Some(e) = types_error {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Failed to load the data types: {e}"),
+                        );
+                    }
+                    designer_action = show_table_designer_panel(ui, designer, types, &tables);
+                });
+
+            match designer_action {
+                Some(DesignerAction::Apply) => self.apply_table_design(ctx),
+                Some(DesignerAction::OpenInEditor(script)) => self.append_to_editor(&script),
+                None => {}
+            }
+            if !open {
+                self.table_designer = None;
+            }
+        }
+
+        if let Some(diagram) = &mut self.er_diagram {
+            let mut open = true;
+            let mut diagram_action = None;
+
+            egui::Window::new(diagram.title())
+                .id(egui::Id::new("er_diagram"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([900.0, 600.0])
+                .show(ctx, |ui| {
+                    diagram_action = show_er_diagram_panel(ui, diagram);
+                });
+
+            let screenshot = ctx.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Screenshot {
+                        user_data, image, ..
+                    } => user_data
+                        .data
+                        .as_ref()?
+                        .downcast_ref::<String>()
+                        .filter(|id| **id == diagram.id)
+                        .map(|_| image.clone()),
+                    _ => None,
+                })
+            });
+
+            match diagram_action {
+                Some(DiagramAction::Reload) => {
+                    let (key, schema) = (diagram.key.clone(), diagram.schema.clone());
+                    let (hidden, scene_rect) = (diagram.hidden.clone(), diagram.scene_rect);
+                    self.open_er_diagram(ctx, &key, &schema, None);
+                    if let Some(diagram) = &mut self.er_diagram {
+                        diagram.hidden = hidden;
+                        diagram.scene_rect = scene_rect;
+                    }
+                }
+                Some(DiagramAction::LayoutChanged) => {
+                    self.diagram_layouts
+                        .insert(diagram.layout_key.clone(), diagram.saved_positions());
+                }
+                Some(DiagramAction::ExportSvg) => self.export_er_diagram_svg(),
+                Some(DiagramAction::ExportPng) => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::new(
+                        diagram.id.clone(),
+                    )));
+                }
+                None => {}
+            }
+            if let Some(image) = screenshot {
+                self.export_er_diagram_png(ctx, &image);
+            }
+            if !open {
+                self.er_diagram = None;
+            }
+        }
+
+        if let Some(compare) = &mut self.schema_compare {
+            let mut open = true;
+            let mut compare_action = None;
+            let connections: Vec<(String, String)> = self
+                .live_connections
+                .iter()
+                .map(|live| (live.key.clone(), live.label()))
+                .collect();
+
+            egui::Window::new("Compare schemas")
+                .id(egui::Id::new("schema_compare"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([1000.0, 650.0])
+                .show(ctx, |ui| {
+                    compare_action = show_schema_compare_panel(ui, compare, &connections);
+                });
+
+            match compare_action {
+                Some(SchemaCompareAction::Compare) => self.compare_schemas(ctx),
+                Some(SchemaCompareAction::OpenInEditor(script)) => self.append_to_editor(&script),
+                None => {}
+            }
+            if !open {
+                self.schema_compare = None;
+            }
+        }
+
+        if let Some(search) = &mut self.object_search {
+            let mut open = true;
+            let mut search_action = None;
+            let connections: Vec<(String, String)> = self
+                .live_connections
+                .iter()
+                .map(|live| (live.key.clone(), live.label()))
+                .collect();
+
+            egui::Window::new("Find object")
+                .id(egui::Id::new("object_search"))
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .default_width(640.0)
+                .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+                .show(ctx, |ui| {
+                    search_action = show_object_search_panel(ui, search, &connections);
+                });
+
+            match search_action {
+                Some(ObjectSearchAction::Reveal) => {
+                    if let Some((key, object)) = search.selected_object() {
+                        self.tree_reveal = Some(TreeReveal {
+                            key: key.clone(),
+                            object: object.clone(),
+                        });
+                        self.table_filter.clear();
+                        self.show_table_list = true;
+                        open = false;
+                    }
+                }
+                Some(ObjectSearchAction::Insert) => {
+                    if let Some((_, object)) = search.selected_object() {
+                        let name = qualified_object_name(object);
+                        insert_into_editor(ctx, &mut self.code, &name);
+                        ctx.memory_mut(|m| m.request_focus(editor_id()));
+                        open = false;
+                    }
+                }
+                Some(ObjectSearchAction::Close) => open = false,
+                None => {}
+            }
+            if !open {
+                self.object_search = None;
+            }
+        }
+
+        if let Some(compare) = &mut self.data_compare {
+            let mut open = true;
+            let mut compare_action = None;
+            let connections: Vec<(String, String)> = self
+                .live_connections
+                .iter()
+                .map(|live| (live.key.clone(), live.label()))
+                .collect();
+            let tables = |key: &str| -> Vec<(String, String)> {
+                self.live_connections
+                    .iter()
+                    .find(|live| live.key == key)
+                    .map(|live| {
+                        live.tables
+                            .iter()
+                            .filter_map(|table| {
+                                Some((table.table_schema.clone()?, table.table_name.clone()?))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            let source_tables = tables(&compare.source_key);
+            let target_tables = tables(&compare.target_key);
+
+            egui::Window::new("Compare data")
+                .id(egui::Id::new("data_compare"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([1000.0, 650.0])
+                .show(ctx, |ui| {
+                    compare_action = show_data_compare_panel(
+                        ui,
+                        compare,
+                        &connections,
+                        &source_tables,
+                        &target_tables,
+                    );
+                });
+
+            match compare_action {
+                Some(DataCompareAction::Compare) => self.compare_data(ctx),
+                Some(DataCompareAction::Cancel) => {
+                    if let Some(task) = compare.task.take() {
+                        task.abort();
+                    }
+                }
+                Some(DataCompareAction::OpenInEditor(script)) => self.append_to_editor(&script),
+                None => {}
+            }
+            if !open {
+                if let Some(task) = self.data_compare.take().and_then(|c| c.task) {
+                    task.abort();
+                }
+            }
+        }
+
+        if let Some(pending) = &self.pending_execution {
+            let mut confirmed = None;
+
+            egui::Window::new("Confirm query")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    for warning in &pending.warnings {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", warning));
+                    }
+                    ui.add_space(4.0);
+                    ui.label(egui::RichText::new(&pending.query).monospace());
+                    ui.horizontal(|ui| {
+                        if ui.button("Run anyway").clicked() {
+                            confirmed = Some(true);
+                        }
+                        if ui.button("Cancel").clicked()
+                            || ui.input(|i| i.key_pressed(egui::Key::Escape))
+                        {
+                            confirmed = Some(false);
+                        }
+                    });
+                });
+
+            if let Some(confirmed) = confirmed {
+                if let Some(pending) = self.pending_execution.take() {
+                    if confirmed {
+                        self.run_query(ctx, pending.query, pending.query_idx);
+                    }
+                }
+            }
+        }
+
+        if let Some(id) = self.pending_connection_delete.clone() {
+            let mut confirmed = None;
+            let name = self
+                .connection_list
+                .iter()
+                .find(|conn| conn.id == id)
+                .map_or("", |conn| conn.connection_name.as_str());
+
+            egui::Window::new("Delete connection")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("Delete \"{}\" and its saved password?", name));
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            confirmed = Some(true);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+                    });
+                });
+
+            if let Some(confirmed) = confirmed {
+                self.pending_connection_delete = None;
+                if confirmed {
+                    self.remove_connection(&id);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,148 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{new_connection_id, SavedConnection},
+    connection_string::ImportedConnection,
+};
+
+#[derive(Serialize, Deserialize, Default)]
+struct ConnectionFile {
+    #[serde(default)]
+    connections: Vec<SavedConnection>,
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+/// Writes the connections to a JSON file, or TOML if the path ends in `.toml`.
+/// Passwords are never exported.
+pub fn export_connections(path: &Path, connections: &[SavedConnection]) -> Result<(), String> {
+    let file = ConnectionFile {
+        connections: connections
+            .iter()
+            .map(|connection| SavedConnection {
+                db_password: String::new(),
+                ..connection.clone()
+            })
+            .collect(),
+    };
+    let contents = if is_toml(path) {
+        toml::to_string_pretty(&file).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?
+    };
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Files can come from anyone, so an SSH tunnel that ssh would misread is
+/// dropped and session statements, which run on every connect, are taken out
+/// and shown instead. Valid tunnels are kept but pointed out.
+fn sanitize(imported: &mut ImportedConnection) {
+    let connection = &mut imported.connection;
+    if let Some(tunnel) = &connection.ssh_tunnel {
+        match tunnel.validate() {
+            Ok(()) => {
+                let destination = if tunnel.user.trim().is_empty() {
+                    tunnel.host.trim().to_string()
+                } else {
+                    format!("{}@{}", tunnel.user.trim(), tunnel.host.trim())
+                };
+                imported.warnings.push(format!(
+                    "Connects through an SSH tunnel to {destination}, check it before connecting"
+                ));
+            }
+            Err(e) => {
+                connection.ssh_tunnel = None;
+                imported
+                    .warnings
+                    .push(format!("The SSH tunnel was removed: {e}"));
+            }
+        }
+    }
+    let statements = std::mem::take(&mut connection.session.extra_statements);
+    if !statements.trim().is_empty() {
+        imported.warnings.push(format!(
+            "These session statements were not imported, add them in the form if you trust them:\n{}",
+            statements.trim()
+        ));
+    }
+}
+
+/// Reads connections exported by `export_connections`. Connections whose id
+/// is already in `existing` get a new id, and any passwords are dropped.
+pub fn import_connections(
+    path: &Path,
+    existing: &[SavedConnection],
+) -> Result<Vec<ImportedConnection>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let file: ConnectionFile = if is_toml(path) {
+        toml::from_str(&contents).map_err(|e| e.to_string())?
+    } else {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())?
+    };
+
+    Ok(file
+        .connections
+        .into_iter()
+        .map(|mut connection| {
+            if existing.iter().any(|conn| conn.id == connection.id) {
+                connection.id = new_connection_id();
+            }
+            connection.db_password.clear();
+            let mut imported = connection.into();
+            sanitize(&mut imported);
+            imported
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh_tunnel::SshTunnelConfig;
+
+    #[test]
+    fn strips_unsafe_tunnels_and_session_statements() {
+        let mut connection = SavedConnection {
+            connection_name: String::from("shared"),
+            db_password: String::from("secret"),
+            ssh_tunnel: Some(SshTunnelConfig {
+                host: String::from("-oProxyCommand=sh -c id"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        connection.session.extra_statements = String::from("DROP TABLE users;");
+        let mut safe = SavedConnection {
+            connection_name: String::from("bastion"),
+            ssh_tunnel: Some(SshTunnelConfig {
+                host: String::from("bastion.example.com"),
+                user: String::from("deploy"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        safe.id = String::from("safe");
+
+        let path = std::env::temp_dir().join("rosemary-import-sanitize.json");
+        export_connections(&path, &[connection, safe]).unwrap();
+        let imported = import_connections(&path, &[]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let unsafe_one = &imported[0];
+        assert!(unsafe_one.connection.ssh_tunnel.is_none());
+        assert!(unsafe_one.connection.session.extra_statements.is_empty());
+        assert!(unsafe_one.connection.db_password.is_empty());
+        assert!(unsafe_one.warnings[0].contains("SSH tunnel was removed"));
+        assert!(unsafe_one.warnings[1].contains("DROP TABLE users;"));
+
+        let safe = &imported[1];
+        assert!(safe.connection.ssh_tunnel.is_some());
+        assert!(safe.warnings[0].contains("deploy@bastion.example.com"));
+    }
+}
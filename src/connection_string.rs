@@ -0,0 +1,473 @@
+use std::collections::BTreeMap;
+
+use urlencoding::{decode, encode};
+
+use crate::app::SavedConnection;
+
+const DEFAULT_PORT: &str = "5432";
+
+/// Parameters kept in `SavedConnection::params` that connecting understands.
+pub const SUPPORTED_PARAMS: &[&str] = &[
+    "sslmode",
+    "sslrootcert",
+    "sslcert",
+    "sslkey",
+    "options",
+    "application_name",
+    "connect_timeout",
+    "target_session_attrs",
+];
+
+/// A connection read from a connection string, file or service definition,
+/// with warnings about the parts of it that won't be used.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedConnection {
+    pub connection: SavedConnection,
+    pub warnings: Vec<String>,
+}
+
+impl From<SavedConnection> for ImportedConnection {
+    fn from(connection: SavedConnection) -> Self {
+        let warnings = connection
+            .params
+            .keys()
+            .filter(|key| !SUPPORTED_PARAMS.contains(&key.as_str()))
+            .map(|key| {
+                format!("The connection parameter \"{key}\" is not supported and will be ignored")
+            })
+            .collect();
+        Self {
+            connection,
+            warnings,
+        }
+    }
+}
+
+/// Parses a libpq connection URI (`postgresql://...`) or a `key=value`
+/// connection string into a connection. Hosts and ports are kept as comma
+/// separated lists, and parameters without a field of their own end up in
+/// `params`.
+pub fn parse_connection_string(input: &str) -> Result<ImportedConnection, String> {
+    let input = input.trim();
+    let params = if input.starts_with("postgresql://") || input.starts_with("postgres://") {
+        parse_uri(input)?
+    } else {
+        parse_key_values(input)?
+    };
+    if params.is_empty() {
+        return Err(String::from("The connection string is empty"));
+    }
+    Ok(connection_from_params(params).into())
+}
+
+/// Builds a connection from libpq keywords such as `host`, `port` and `dbname`.
+pub fn connection_from_params(params: BTreeMap<String, String>) -> SavedConnection {
+    let mut connection = SavedConnection::default();
+    for (key, value) in params {
+        match key.as_str() {
+            "host" => connection.db_host = value,
+            "port" => connection.db_port = value,
+            "user" => connection.db_user = value,
+            "password" => connection.db_password = value,
+            "dbname" => connection.db_name = value,
+            "application_name" => connection.session.application_name = value,
+            // The URI form also accepts `ssl=true` as a spelling of `sslmode=require`.
+            "ssl" if value == "true" => {
+                connection
+                    .params
+                    .insert(String::from("sslmode"), String::from("require"));
+            }
+            _ => {
+                connection.params.insert(key, value);
+            }
+        }
+    }
+    if connection.connection_name.is_empty() {
+        connection.connection_name = connection.db_host.clone();
+    }
+    connection
+}
+
+fn decode_component(value: &str) -> Result<String, String> {
+    decode(value)
+        .map(|value| value.into_owned())
+        .map_err(|_| format!("Invalid percent-encoding in \"{value}\""))
+}
+
+fn parse_uri(input: &str) -> Result<BTreeMap<String, String>, String> {
+    let rest = input
+        .strip_prefix("postgresql://")
+        .or_else(|| input.strip_prefix("postgres://"))
+        .unwrap_or(input);
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+    let mut params = BTreeMap::new();
+    let hostspec = match authority.rsplit_once('@') {
+        Some((userinfo, hostspec)) => {
+            let (user, password) = match userinfo.split_once(':') {
+                Some((user, password)) => (user, Some(password)),
+                None => (userinfo, None),
+            };
+            if !user.is_empty() {
+                params.insert(String::from("user"), decode_component(user)?);
+            }
+            if let Some(password) = password {
+                params.insert(String::from("password"), decode_component(password)?);
+            }
+            hostspec
+        }
+        None => authority,
+    };
+
+    if !hostspec.is_empty() {
+        let mut hosts = Vec::new();
+        let mut ports = Vec::new();
+        for spec in hostspec.split(',') {
+            let (host, port) = if let Some(spec) = spec.strip_prefix('[') {
+                let (host, rest) = spec
+                    .split_once(']')
+                    .ok_or_else(|| format!("Missing \"]\" in IPv6 host \"[{spec}\""))?;
+                (host.to_string(), rest.strip_prefix(':').unwrap_or(rest))
+            } else {
+                let (host, port) = spec.split_once(':').unwrap_or((spec, ""));
+                (decode_component(host)?, port)
+            };
+            if !port.is_empty() && port.parse::<u16>().is_err() {
+                return Err(format!("Invalid port \"{port}\""));
+            }
+            hosts.push(host);
+            ports.push(port.to_string());
+        }
+        params.insert(String::from("host"), hosts.join(","));
+        if ports.iter().any(|port| !port.is_empty()) {
+            let ports: Vec<&str> = ports
+                .iter()
+                .map(|port| if port.is_empty() { DEFAULT_PORT } else { port })
+                .collect();
+            let port = if ports.iter().all(|port| *port == ports[0]) {
+                ports[0].to_string()
+            } else {
+                ports.join(",")
+            };
+            params.insert(String::from("port"), port);
+        }
+    }
+
+    if !path.is_empty() {
+        params.insert(String::from("dbname"), decode_component(path)?);
+    }
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Missing \"=\" after \"{pair}\" in the query string"))?;
+        params.insert(decode_component(key)?, decode_component(value)?);
+    }
+
+    Ok(params)
+}
+
+/// Parses `key=value` pairs separated by whitespace. Values may be single
+/// quoted, and `\'` and `\\` escape a quote or backslash.
+pub fn parse_key_values(input: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut params = BTreeMap::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some('=') {
+            return Err(format!(
+                "Missing \"=\" after \"{key}\" in the connection string"
+            ));
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'\'').is_some() {
+            loop {
+                match chars.next() {
+                    Some('\\') => value.extend(chars.next()),
+                    Some('\'') => break,
+                    Some(c) => value.push(c),
+                    None => return Err(format!("Unterminated quoted value for \"{key}\"")),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                if c == '\\' {
+                    value.extend(chars.next());
+                } else {
+                    value.push(c);
+                }
+            }
+        }
+        params.insert(key, value);
+    }
+
+    Ok(params)
+}
+
+/// Formats parameters as a `key=value` connection string, quoting values where needed.
+pub fn format_key_values(params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .map(|(key, value)| {
+            if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '\'') {
+                format!("{key}={}", value.replace('\\', "\\\\"))
+            } else {
+                format!(
+                    "{key}='{}'",
+                    value.replace('\\', "\\\\").replace('\'', "\\'")
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Pairs each host with its port. Like libpq, a single port applies to every host.
+pub fn host_port_pairs(hosts: &str, ports: &str) -> Result<Vec<(String, u16)>, String> {
+    let hosts: Vec<&str> = hosts.split(',').map(str::trim).collect();
+    let ports: Vec<&str> = ports.split(',').map(str::trim).collect();
+    if ports.len() != 1 && ports.len() != hosts.len() {
+        return Err(format!(
+            "{} ports were given for {} hosts",
+            ports.len(),
+            hosts.len()
+        ));
+    }
+
+    hosts
+        .iter()
+        .enumerate()
+        .map(|(idx, host)| {
+            let port = if ports.len() == 1 {
+                ports[0]
+            } else {
+                ports[idx]
+            };
+            let port = if port.is_empty() { DEFAULT_PORT } else { port };
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid port \"{port}\""))?;
+            let host = if host.is_empty() { "localhost" } else { host };
+            Ok((host.to_string(), port))
+        })
+        .collect()
+}
+
+/// Formats a connection as a libpq URI. The password is only included when given.
+pub fn to_uri(connection: &SavedConnection, password: Option<&str>) -> String {
+    let mut uri = String::from("postgresql://");
+
+    if !connection.db_user.is_empty() {
+        uri += &encode(&connection.db_user);
+        if let Some(password) = password.filter(|password| !password.is_empty()) {
+            uri.push(':');
+            uri += &encode(password);
+        }
+        uri.push('@');
+    }
+
+    let hosts: Vec<&str> = connection.db_host.split(',').map(str::trim).collect();
+    let ports: Vec<&str> = connection.db_port.split(',').map(str::trim).collect();
+    let hostspecs: Vec<String> = hosts
+        .iter()
+        .enumerate()
+        .map(|(idx, host)| {
+            let mut spec = if host.contains(':') {
+                format!("[{host}]")
+            } else {
+                encode(host).into_owned()
+            };
+            let port = ports.get(idx).or(ports.first()).copied().unwrap_or("");
+            if !port.is_empty() {
+                spec.push(':');
+                spec += port;
+            }
+            spec
+        })
+        .collect();
+    uri += &hostspecs.join(",");
+
+    if !connection.db_name.is_empty() {
+        uri.push('/');
+        uri += &encode(&connection.db_name);
+    }
+
+    let application_name = connection.session.application_name.trim();
+    let application_name =
+        (!application_name.is_empty()).then_some(("application_name", application_name));
+    let query: Vec<String> = connection
+        .params
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .chain(application_name)
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect();
+    if !query.is_empty() {
+        uri.push('?');
+        uri += &query.join("&");
+    }
+
+    uri
+}
+
+/// Splits a libpq `options` value such as `-c search_path=app -c geqo=off`
+/// into setting names and values.
+pub fn parse_options(options: &str) -> Result<Vec<(String, String)>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = options.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => word.extend(chars.next()),
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut settings = Vec::new();
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let setting = if word == "-c" {
+            words
+                .next()
+                .ok_or_else(|| String::from("Missing setting after \"-c\" in options"))?
+        } else if let Some(setting) = word.strip_prefix("--").or(word.strip_prefix("-c")) {
+            setting.to_string()
+        } else {
+            return Err(format!("Unsupported option \"{word}\""));
+        };
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Missing \"=\" in option \"{setting}\""))?;
+        settings.push((name.replace('-', "_"), value.to_string()));
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_uris_with_ipv6_and_several_hosts() {
+        let imported =
+            parse_connection_string("postgresql://[::1]:5433,h2/db?sslmode=require").unwrap();
+        let connection = imported.connection;
+        assert_eq!(connection.db_host, "::1,h2");
+        assert_eq!(connection.db_port, "5433,5432");
+        assert_eq!(connection.db_name, "db");
+        assert_eq!(connection.params["sslmode"], "require");
+        assert!(imported.warnings.is_empty());
+        assert_eq!(
+            host_port_pairs(&connection.db_host, &connection.db_port).unwrap(),
+            [(String::from("::1"), 5433), (String::from("h2"), 5432)]
+        );
+    }
+
+    #[test]
+    fn decodes_uri_components() {
+        let connection =
+            parse_connection_string("postgres://us%40er:p%3Ass@%2Fvar%2Frun:6543/my%20db")
+                .unwrap()
+                .connection;
+        assert_eq!(connection.db_user, "us@er");
+        assert_eq!(connection.db_password, "p:ss");
+        assert_eq!(connection.db_host, "/var/run");
+        assert_eq!(connection.db_port, "6543");
+        assert_eq!(connection.db_name, "my db");
+
+        assert!(parse_connection_string("postgresql://[::1/db").is_err());
+        assert!(parse_connection_string("postgresql://h:port/db").is_err());
+        assert!(parse_connection_string("postgresql://h/db?sslmode").is_err());
+    }
+
+    #[test]
+    fn parses_quoted_key_values() {
+        let params =
+            parse_key_values(r"host=h  password = 'it\'s a \\ secret' dbname=d\ b").unwrap();
+        assert_eq!(params["host"], "h");
+        assert_eq!(params["password"], r"it's a \ secret");
+        assert_eq!(params["dbname"], "d b");
+        assert!(parse_key_values("host").is_err());
+        assert!(parse_key_values("password='open").is_err());
+
+        assert_eq!(
+            parse_key_values(&format_key_values(&params)).unwrap(),
+            params
+        );
+    }
+
+    #[test]
+    fn warns_about_unsupported_params() {
+        let imported =
+            parse_connection_string("host=h sslmode=verify-ca gssencmode=disable").unwrap();
+        assert_eq!(imported.connection.params["gssencmode"], "disable");
+        assert_eq!(imported.warnings.len(), 1);
+        assert!(imported.warnings[0].contains("gssencmode"));
+    }
+
+    #[test]
+    fn pairs_hosts_with_ports() {
+        assert_eq!(
+            host_port_pairs("a,b", "1").unwrap(),
+            [(String::from("a"), 1), (String::from("b"), 1)]
+        );
+        assert_eq!(
+            host_port_pairs("", "").unwrap(),
+            [(String::from("localhost"), 5432)]
+        );
+        assert!(host_port_pairs("a,b,c", "1,2").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_a_uri() {
+        let connection = parse_connection_string(
+            "postgresql://me:pw@[::1]:5433,h2:5434/db?application_name=app&connect_timeout=5",
+        )
+        .unwrap()
+        .connection;
+        let uri = to_uri(&connection, Some("pw"));
+        assert_eq!(
+            uri,
+            "postgresql://me:pw@[::1]:5433,h2:5434/db?connect_timeout=5&application_name=app"
+        );
+        let reparsed = parse_connection_string(&uri).unwrap().connection;
+        assert_eq!(reparsed.db_host, connection.db_host);
+        assert_eq!(reparsed.db_port, connection.db_port);
+        assert_eq!(reparsed.session.application_name, "app");
+    }
+
+    #[test]
+    fn splits_libpq_options() {
+        assert_eq!(
+            parse_options(r"-c search_path=app\ ,public --geqo=off -cwork-mem=4MB").unwrap(),
+            [
+                (String::from("search_path"), String::from("app ,public")),
+                (String::from("geqo"), String::from("off")),
+                (String::from("work_mem"), String::from("4MB")),
+            ]
+        );
+        assert!(parse_options("-c").is_err());
+        assert!(parse_options("-x foo").is_err());
+    }
+}
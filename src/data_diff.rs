@@ -0,0 +1,618 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{Pool, Postgres};
+use tokio::task::JoinHandle;
+
+use crate::{
+    app::new_connection_id,
+    query_functions::{
+        pg_compare::{get_primary_key, get_query_columns},
+        pg_data::fetch_text_rows_with_params,
+    },
+    sql::{qualified_name, quote_identifier},
+};
+
+/// Rows read from each side at a time.
+pub const CHUNK_SIZE: usize = 1000;
+/// Differences kept for the report and the sync script. Any more are only counted.
+pub const MAX_DIFFERENCES: usize = 10_000;
+
+/// A row with every value as text.
+pub type Row = Vec<Option<String>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    Table { schema: String, table: String },
+    Query(String),
+}
+
+impl DataSource {
+    /// Aliased as `t`, so the key can be ordered by its own type rather than
+    /// the text the columns are read as.
+    fn relation(&self) -> String {
+        match self {
+            Self::Table { schema, table } => format!("{} AS t", qualified_name(schema, table)),
+            Self::Query(sql) => format!("({}) AS t", sql.trim().trim_end_matches(';')),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowDiff {
+    /// Only in the source.
+    Missing(Row),
+    /// Only in the target.
+    Extra(Row),
+    /// The same key on both sides, with the indexes of the columns that differ.
+    Changed {
+        source: Row,
+        target: Row,
+        columns: Vec<usize>,
+    },
+}
+
+/// Source rows waiting for the target rows in their key range.
+struct PendingRows {
+    keys: Vec<Row>,
+    rows: HashMap<Row, Row>,
+    /// Keys already read from the target.
+    seen: HashSet<Row>,
+}
+
+/// The differences between the rows of two tables or queries, matched on key columns.
+#[derive(Debug, Clone, Default)]
+pub struct DataDiff {
+    /// The columns both sides have, in source order.
+    pub columns: Vec<String>,
+    /// Indexes of the key columns in `columns`.
+    pub key: Vec<usize>,
+    pub rows: Vec<RowDiff>,
+    pub missing: usize,
+    pub extra: usize,
+    pub changed: usize,
+    pub same: usize,
+}
+
+impl DataDiff {
+    pub fn new(columns: Vec<String>, key: Vec<usize>) -> Self {
+        Self {
+            columns,
+            key,
+            ..Default::default()
+        }
+    }
+
+    /// Whether every difference is in `rows`, so the sync script is complete.
+    pub fn is_complete(&self) -> bool {
+        self.rows.len() == self.missing + self.extra + self.changed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing + self.extra + self.changed == 0
+    }
+
+    fn key_of(&self, row: &Row) -> Row {
+        self.key.iter().map(|&idx| row[idx].clone()).collect()
+    }
+
+    fn push(&mut self, diff: RowDiff) {
+        match &diff {
+            RowDiff::Missing(_) => self.missing += 1,
+            RowDiff::Extra(_) => self.extra += 1,
+            RowDiff::Changed { .. } => self.changed += 1,
+        }
+        if self.rows.len() < MAX_DIFFERENCES {
+            self.rows.push(diff);
+        }
+    }
+
+    fn pending(&self, source: Vec<Row>) -> Result<PendingRows, String> {
+        let mut pending = PendingRows {
+            keys: Vec::with_capacity(source.len()),
+            rows: HashMap::with_capacity(source.len()),
+            seen: HashSet::new(),
+        };
+        for row in source {
+            let key = self.key_of(&row);
+            if pending.rows.insert(key.clone(), row).is_some() {
+                return Err(duplicate_key(&key, "source"));
+            }
+            pending.keys.push(key);
+        }
+        Ok(pending)
+    }
+
+    /// Matches target rows with the pending source rows.
+    fn match_target(&mut self, pending: &mut PendingRows, target: Vec<Row>) -> Result<(), String> {
+        for row in target {
+            let key = self.key_of(&row);
+            if !pending.seen.insert(key.clone()) {
+                return Err(duplicate_key(&key, "target"));
+            }
+            match pending.rows.remove(&key) {
+                Some(source) => {
+                    let columns: Vec<usize> = (0..self.columns.len())
+                        .filter(|&idx| source[idx] != row[idx])
+                        .collect();
+                    if columns.is_empty() {
+                        self.same += 1;
+                    } else {
+                        self.push(RowDiff::Changed {
+                            source,
+                            target: row,
+                            columns,
+                        });
+                    }
+                }
+                None => self.push(RowDiff::Extra(row)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Source rows no target row matched are missing from the target.
+    fn finish_chunk(&mut self, mut pending: PendingRows) {
+        for key in pending.keys {
+            if let Some(row) = pending.rows.remove(&key) {
+                self.push(RowDiff::Missing(row));
+            }
+        }
+    }
+
+    /// Statements that make the target table match the source: deletes,
+    /// updates, then inserts. Values are untyped literals so they take the
+    /// column types.
+    pub fn sync_script(&self, table: &str) -> Vec<String> {
+        let names: Vec<String> = self
+            .columns
+            .iter()
+            .map(|name| quote_identifier(name))
+            .collect();
+        let condition = |row: &Row| {
+            self.key
+                .iter()
+                .map(|&idx| format!("{} = {}", names[idx], literal(&row[idx])))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+        let mut statements = Vec::new();
+        for diff in &self.rows {
+            if let RowDiff::Extra(row) = diff {
+                statements.push(format!("DELETE FROM {table} WHERE {};", condition(row)));
+            }
+        }
+        for diff in &self.rows {
+            if let RowDiff::Changed {
+                source,
+                target,
+                columns,
+            } = diff
+            {
+                let assignments: Vec<String> = columns
+                    .iter()
+                    .map(|&idx| format!("{} = {}", names[idx], literal(&source[idx])))
+                    .collect();
+                statements.push(format!(
+                    "UPDATE {table} SET {} WHERE {};",
+                    assignments.join(", "),
+                    condition(target)
+                ));
+            }
+        }
+        for diff in &self.rows {
+            if let RowDiff::Missing(row) = diff {
+                let values: Vec<String> = row.iter().map(literal).collect();
+                statements.push(format!(
+                    "INSERT INTO {table} ({}) VALUES ({});",
+                    names.join(", "),
+                    values.join(", ")
+                ));
+            }
+        }
+        statements
+    }
+}
+
+fn duplicate_key(key: &Row, side: &str) -> String {
+    let values: Vec<String> = key.iter().map(literal).collect();
+    format!(
+        "The key ({}) appears more than once in the {side}. The key columns have to be unique.",
+        values.join(", ")
+    )
+}
+
+fn literal(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("'{}'", value.replace('\'', "''")),
+        None => String::from("NULL"),
+    }
+}
+
+/// Reads rows as text in key order, skipping rows with a `NULL` key. `after`
+/// and `up_to` bound the key range with parameters, so a chunk starts where
+/// the last one ended.
+fn chunk_sql(
+    from: &str,
+    columns: &[String],
+    key: &[(String, String)],
+    after: bool,
+    up_to: bool,
+    limit: Option<usize>,
+) -> String {
+    let select: Vec<String> = columns
+        .iter()
+        .map(|name| format!("{}::text", quote_identifier(name)))
+        .collect();
+    let key_names: Vec<String> = key
+        .iter()
+        .map(|(name, _)| format!("t.{}", quote_identifier(name)))
+        .collect();
+    let mut conditions: Vec<String> = key_names
+        .iter()
+        .map(|name| format!("{name} IS NOT NULL"))
+        .collect();
+    let mut param = 0;
+    let mut bound = |op: &str| {
+        let values: Vec<String> = key
+            .iter()
+            .map(|(_, data_type)| {
+                param += 1;
+                format!("CAST(${param} AS {data_type})")
+            })
+            .collect();
+        format!("({}) {op} ({})", key_names.join(", "), values.join(", "))
+    };
+    if after {
+        conditions.push(bound(">"));
+    }
+    if up_to {
+        conditions.push(bound("<="));
+    }
+    let mut sql = format!(
+        "SELECT {} FROM {from} WHERE {} ORDER BY {}",
+        select.join(", "),
+        conditions.join(" AND "),
+        key_names.join(", ")
+    );
+    if let Some(limit) = limit {
+        sql += &format!(" LIMIT {limit}");
+    }
+    sql
+}
+
+async fn fetch_chunk(
+    db: &Pool<Postgres>,
+    sql: &str,
+    after: &Option<Row>,
+    up_to: &Option<Row>,
+) -> Result<Vec<Row>, String> {
+    let params: Vec<Option<String>> = after.iter().chain(up_to).flatten().cloned().collect();
+    fetch_text_rows_with_params(db, sql, &params)
+        .await
+        .map_err(|e| format!("{e}\n{sql}"))
+}
+
+/// What to compare: the source is what the target should look like.
+#[derive(Debug, Clone)]
+pub struct DataCompareSpec {
+    pub source: DataSource,
+    pub target: DataSource,
+    /// Columns the rows are matched on. The source table's primary key when empty.
+    pub key_columns: Vec<String>,
+}
+
+/// Compares the rows of two tables or queries a chunk at a time, so only one
+/// chunk of source rows is held at once. Each chunk of the source is followed
+/// by the target rows in the same key range. `progress` is called with the
+/// number of source rows compared so far.
+pub async fn compare_data(
+    source_db: &Pool<Postgres>,
+    target_db: &Pool<Postgres>,
+    spec: &DataCompareSpec,
+    progress: impl Fn(usize),
+) -> Result<DataDiff, String> {
+    let source_from = spec.source.relation();
+    let target_from = spec.target.relation();
+    let source_columns = get_query_columns(source_db, &format!("SELECT * FROM {source_from}"))
+        .await
+        .map_err(|e| format!("Source: {e}"))?;
+    let target_columns = get_query_columns(target_db, &format!("SELECT * FROM {target_from}"))
+        .await
+        .map_err(|e| format!("Target: {e}"))?;
+
+    let key_columns = match (&spec.source, spec.key_columns.is_empty()) {
+        (_, false) => spec.key_columns.clone(),
+        (DataSource::Table { schema, table }, true) => {
+            let key = get_primary_key(source_db, schema, table)
+                .await
+                .map_err(|e| e.to_string())?;
+            if key.is_empty() {
+                return Err(format!(
+                    "{} has no primary key, pick the columns to match rows on",
+                    qualified_name(schema, table)
+                ));
+            }
+            key
+        }
+        (DataSource::Query(_), true) => {
+            return Err(String::from("Pick the columns to match rows on"));
+        }
+    };
+
+    let columns: Vec<(String, String)> = source_columns
+        .into_iter()
+        .filter(|(name, _)| target_columns.iter().any(|(other, _)| other == name))
+        .collect();
+    let mut key = Vec::new();
+    let mut key_types = Vec::new();
+    for name in &key_columns {
+        let Some(idx) = columns.iter().position(|(column, _)| column == name) else {
+            return Err(format!("Both sides need the key column {name}"));
+        };
+        key.push(idx);
+        key_types.push(columns[idx].clone());
+    }
+    let names: Vec<String> = columns.into_iter().map(|(name, _)| name).collect();
+
+    let first_source = chunk_sql(
+        &source_from,
+        &names,
+        &key_types,
+        false,
+        false,
+        Some(CHUNK_SIZE),
+    );
+    let next_source = chunk_sql(
+        &source_from,
+        &names,
+        &key_types,
+        true,
+        false,
+        Some(CHUNK_SIZE),
+    );
+    let target_sql = |after: bool, up_to: bool| {
+        chunk_sql(
+            &target_from,
+            &names,
+            &key_types,
+            after,
+            up_to,
+            Some(CHUNK_SIZE),
+        )
+    };
+
+    let mut diff = DataDiff::new(names.clone(), key);
+    let mut cursor: Option<Row> = None;
+    let mut compared = 0;
+    loop {
+        let sql = if cursor.is_some() {
+            &next_source
+        } else {
+            &first_source
+        };
+        let source = fetch_chunk(source_db, sql, &cursor, &None).await?;
+        let last = source.len() < CHUNK_SIZE;
+        // The last chunk takes every remaining target row.
+        let up_to = match source.last() {
+            Some(row) if !last => Some(diff.key_of(row)),
+            _ => None,
+        };
+        compared += source.len();
+        let mut pending = diff.pending(source)?;
+
+        let mut target_cursor = cursor.clone();
+        loop {
+            let sql = target_sql(target_cursor.is_some(), up_to.is_some());
+            let target = fetch_chunk(target_db, &sql, &target_cursor, &up_to).await?;
+            let done = target.len() < CHUNK_SIZE;
+            target_cursor = target.last().map(|row| diff.key_of(row));
+            diff.match_target(&mut pending, target)?;
+            if done {
+                break;
+            }
+        }
+        diff.finish_chunk(pending);
+        progress(compared);
+
+        if last {
+            break;
+        }
+        cursor = up_to;
+    }
+    Ok(diff)
+}
+
+/// A data compare between two connections, as shown in its window.
+#[derive(Debug)]
+pub struct DataCompare {
+    /// Tells results for this compare apart from ones for an earlier one.
+    pub id: String,
+    pub source_key: String,
+    pub target_key: String,
+    pub use_queries: bool,
+    pub source_table: Option<(String, String)>,
+    pub target_table: Option<(String, String)>,
+    pub source_sql: String,
+    pub target_sql: String,
+    /// Comma separated. The source table's primary key is used when empty.
+    pub key_columns: String,
+    pub task: Option<JoinHandle<()>>,
+    /// Source rows compared so far.
+    pub progress: usize,
+    pub result: Option<DataDiff>,
+    /// The table the sync script changes, when the target is a table.
+    pub sync_table: Option<String>,
+    /// The sync script for `result`, written once when the result arrives.
+    pub script: String,
+    pub show_missing: bool,
+    pub show_extra: bool,
+    pub show_changed: bool,
+    pub error: Option<String>,
+}
+
+impl DataCompare {
+    pub fn new(source_key: &str, target_key: &str, table: Option<(String, String)>) -> Self {
+        Self {
+            id: new_connection_id(),
+            source_key: source_key.to_string(),
+            target_key: target_key.to_string(),
+            use_queries: false,
+            source_table: table.clone(),
+            target_table: table,
+            source_sql: String::new(),
+            target_sql: String::new(),
+            key_columns: String::new(),
+            task: None,
+            progress: 0,
+            result: None,
+            sync_table: None,
+            script: String::new(),
+            show_missing: true,
+            show_extra: true,
+            show_changed: true,
+            error: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn spec(&self) -> Result<DataCompareSpec, String> {
+        let key_columns = self
+            .key_columns
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+        let (source, target) = if self.use_queries {
+            if self.source_sql.trim().is_empty() || self.target_sql.trim().is_empty() {
+                return Err(String::from("Write a query for both sides"));
+            }
+            (
+                DataSource::Query(self.source_sql.clone()),
+                DataSource::Query(self.target_sql.clone()),
+            )
+        } else {
+            let (Some((schema, table)), Some((target_schema, target_table))) =
+                (&self.source_table, &self.target_table)
+            else {
+                return Err(String::from("Pick a table on both sides"));
+            };
+            (
+                DataSource::Table {
+                    schema: schema.clone(),
+                    table: table.clone(),
+                },
+                DataSource::Table {
+                    schema: target_schema.clone(),
+                    table: target_table.clone(),
+                },
+            )
+        };
+        Ok(DataCompareSpec {
+            source,
+            target,
+            key_columns,
+        })
+    }
+
+    pub fn set_result(&mut self, diff: Option<DataDiff>) {
+        self.script = match (&diff, &self.sync_table) {
+            (Some(diff), Some(table)) => diff.sync_script(table).join("\n"),
+            _ => String::new(),
+        };
+        self.result = diff;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[Option<&str>]) -> Row {
+        values.iter().map(|value| value.map(String::from)).collect()
+    }
+
+    #[test]
+    fn bounds_chunks_by_key() {
+        let key = [
+            (String::from("tenant"), String::from("integer")),
+            (String::from("Id"), String::from("uuid")),
+        ];
+        let columns = [
+            String::from("tenant"),
+            String::from("Id"),
+            String::from("name"),
+        ];
+        assert_eq!(
+            chunk_sql("public.t AS t", &columns, &key, false, false, Some(10)),
+            "SELECT tenant::text, \"Id\"::text, name::text FROM public.t AS t \
+             WHERE t.tenant IS NOT NULL AND t.\"Id\" IS NOT NULL ORDER BY t.tenant, t.\"Id\" LIMIT 10"
+        );
+        assert_eq!(
+            chunk_sql("public.t AS t", &columns, &key, true, true, None),
+            "SELECT tenant::text, \"Id\"::text, name::text FROM public.t AS t \
+             WHERE t.tenant IS NOT NULL AND t.\"Id\" IS NOT NULL \
+             AND (t.tenant, t.\"Id\") > (CAST($1 AS integer), CAST($2 AS uuid)) \
+             AND (t.tenant, t.\"Id\") <= (CAST($3 AS integer), CAST($4 AS uuid)) \
+             ORDER BY t.tenant, t.\"Id\""
+        );
+    }
+
+    #[test]
+    fn matches_rows_across_target_chunks() {
+        let mut diff = DataDiff::new(vec![String::from("id"), String::from("name")], vec![0]);
+        let mut pending = diff
+            .pending(vec![
+                row(&[Some("1"), Some("a")]),
+                row(&[Some("2"), Some("b")]),
+                row(&[Some("3"), None]),
+            ])
+            .unwrap();
+        diff.match_target(&mut pending, vec![row(&[Some("1"), Some("a")])])
+            .unwrap();
+        diff.match_target(
+            &mut pending,
+            vec![row(&[Some("3"), Some("c")]), row(&[Some("4"), Some("d")])],
+        )
+        .unwrap();
+        diff.finish_chunk(pending);
+
+        assert_eq!(
+            (diff.missing, diff.extra, diff.changed, diff.same),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(
+            diff.rows,
+            [
+                RowDiff::Changed {
+                    source: row(&[Some("3"), None]),
+                    target: row(&[Some("3"), Some("c")]),
+                    columns: vec![1],
+                },
+                RowDiff::Extra(row(&[Some("4"), Some("d")])),
+                RowDiff::Missing(row(&[Some("2"), Some("b")])),
+            ]
+        );
+        assert_eq!(
+            diff.sync_script("public.t"),
+            [
+                "DELETE FROM public.t WHERE id = '4';",
+                "UPDATE public.t SET name = NULL WHERE id = '3';",
+                "INSERT INTO public.t (id, name) VALUES ('2', 'b');",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let diff = DataDiff::new(vec![String::from("id")], vec![0]);
+        let error = diff
+            .pending(vec![row(&[Some("it's")]), row(&[Some("it's")])])
+            .err()
+            .unwrap();
+        assert!(error.contains("('it''s')"), "{error}");
+    }
+}
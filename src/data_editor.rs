@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    app::new_connection_id,
+    query_functions::pg_schema::{TableColumn, TableDetails},
+    sql::{qualified_name, quote_identifier},
+    table_sql::is_writable,
+};
+
+pub const PAGE_SIZE: usize = 100;
+
+/// A cell of a row that hasn't been inserted yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewCell {
+    /// Left out of the `INSERT` so the column default applies.
+    Default,
+    Value(Option<String>),
+}
+
+/// A statement and its parameters, all bound as text and cast to the column types.
+pub type RowChange = (String, Vec<Option<String>>);
+
+/// A page of a table's rows being edited. Values are read and written as text
+/// so every column type can be edited, and rows are matched on the primary key.
+#[derive(Debug)]
+pub struct DataEditor {
+    /// Tells results for this editor apart from ones for an editor that was closed.
+    pub id: String,
+    pub key: String,
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<TableColumn>,
+    pub key_columns: Vec<String>,
+    /// Why rows can't be changed, e.g. the table has no primary key.
+    pub read_only: Option<String>,
+    /// Name of the production connection, whose changes are confirmed before saving.
+    pub production: Option<String>,
+    pub offset: usize,
+    pub rows: Vec<Vec<Option<String>>>,
+    pub has_more: bool,
+    pub edits: BTreeMap<(usize, usize), Option<String>>,
+    pub deleted: BTreeSet<usize>,
+    pub inserted: Vec<Vec<NewCell>>,
+    pub loading: bool,
+    pub saving: bool,
+    pub confirm_save: bool,
+    pub error: Option<String>,
+    pub status: String,
+}
+
+impl DataEditor {
+    pub fn new(key: &str, schema: &str, table: &str, details: &TableDetails) -> Self {
+        let key_columns = details.primary_key().map(<[String]>::to_vec);
+        Self {
+            id: new_connection_id(),
+            key: key.to_string(),
+            schema: schema.to_string(),
+            table: table.to_string(),
+            columns: details.columns.clone(),
+            read_only: key_columns
+                .is_none()
+                .then(|| String::from("The table has no primary key, so rows can't be edited.")),
+            key_columns: key_columns.unwrap_or_default(),
+            production: None,
+            offset: 0,
+            rows: Vec::new(),
+            has_more: false,
+            edits: BTreeMap::new(),
+            deleted: BTreeSet::new(),
+            inserted: Vec::new(),
+            loading: false,
+            saving: false,
+            confirm_save: false,
+            error: None,
+            status: String::new(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        qualified_name(&self.schema, &self.table)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.edits.is_empty() || !self.deleted.is_empty() || !self.inserted.is_empty()
+    }
+
+    pub fn is_editable(&self, column: usize) -> bool {
+        self.read_only.is_none() && is_writable(&self.columns[column])
+    }
+
+    pub fn value(&self, row: usize, column: usize) -> Option<&str> {
+        match self.edits.get(&(row, column)) {
+            Some(edit) => edit.as_deref(),
+            None => self.rows[row][column].as_deref(),
+        }
+    }
+
+    /// Changes a loaded cell. Setting it back to the loaded value drops the edit.
+    pub fn set_value(&mut self, row: usize, column: usize, value: Option<String>) {
+        if self.rows[row][column] == value {
+            self.edits.remove(&(row, column));
+        } else {
+            self.edits.insert((row, column), value);
+        }
+    }
+
+    pub fn add_row(&mut self) {
+        self.inserted
+            .push(vec![NewCell::Default; self.columns.len()]);
+    }
+
+    pub fn discard(&mut self) {
+        self.edits.clear();
+        self.deleted.clear();
+        self.inserted.clear();
+        self.confirm_save = false;
+    }
+
+    /// Takes a page of rows. One row more than a page is loaded to tell
+    /// whether there is a next page.
+    pub fn set_rows(&mut self, mut rows: Vec<Vec<Option<String>>>) {
+        self.has_more = rows.len() > PAGE_SIZE;
+        rows.truncate(PAGE_SIZE);
+        self.rows = rows;
+        self.discard();
+    }
+
+    /// Reads a page of rows as text, in primary key order.
+    pub fn select_sql(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("{}::text", quote_identifier(&column.name)))
+            .collect();
+        let mut sql = format!("SELECT {} FROM {}", columns.join(", "), self.title());
+        if !self.key_columns.is_empty() {
+            let order: Vec<String> = self
+                .key_columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect();
+            sql += &format!(" ORDER BY {}", order.join(", "));
+        }
+        sql + &format!(" LIMIT {} OFFSET {}", PAGE_SIZE + 1, self.offset)
+    }
+
+    fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+
+    /// `"col" = CAST($n AS type)` for each key column, with the loaded values.
+    fn key_condition(&self, row: usize, params: &mut Vec<Option<String>>) -> String {
+        self.key_columns
+            .iter()
+            .filter_map(|name| self.column_index(name))
+            .map(|idx| {
+                params.push(self.rows[row][idx].clone());
+                let column = &self.columns[idx];
+                format!(
+                    "{} = CAST(${} AS {})",
+                    quote_identifier(&column.name),
+                    params.len(),
+                    column.data_type
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// The statements that save the pending changes: deletes, then updates,
+    /// then inserts.
+    pub fn changes(&self) -> Vec<RowChange> {
+        let name = self.title();
+        let mut changes = Vec::new();
+
+        for &row in &self.deleted {
+            let mut params = Vec::new();
+            let condition = self.key_condition(row, &mut params);
+            changes.push((format!("DELETE FROM {name} WHERE {condition}"), params));
+        }
+
+        let mut updated_rows: BTreeMap<usize, Vec<(usize, &Option<String>)>> = BTreeMap::new();
+        for ((row, column), value) in &self.edits {
+            if !self.deleted.contains(row) {
+                updated_rows.entry(*row).or_default().push((*column, value));
+            }
+        }
+        for (row, values) in updated_rows {
+            let mut params = Vec::new();
+            let assignments: Vec<String> = values
+                .into_iter()
+                .map(|(idx, value)| {
+                    params.push(value.clone());
+                    let column = &self.columns[idx];
+                    format!(
+                        "{} = CAST(${} AS {})",
+                        quote_identifier(&column.name),
+                        params.len(),
+                        column.data_type
+                    )
+                })
+                .collect();
+            let condition = self.key_condition(row, &mut params);
+            changes.push((
+                format!(
+                    "UPDATE {name} SET {} WHERE {condition}",
+                    assignments.join(", ")
+                ),
+                params,
+            ));
+        }
+
+        for cells in &self.inserted {
+            let mut params = Vec::new();
+            let mut names = Vec::new();
+            let mut values = Vec::new();
+            for (column, cell) in self.columns.iter().zip(cells) {
+                if let NewCell::Value(value) = cell {
+                    params.push(value.clone());
+                    names.push(quote_identifier(&column.name));
+                    values.push(format!("CAST(${} AS {})", params.len(), column.data_type));
+                }
+            }
+            let sql = if names.is_empty() {
+                format!("INSERT INTO {name} DEFAULT VALUES")
+            } else {
+                format!(
+                    "INSERT INTO {name} ({}) VALUES ({})",
+                    names.join(", "),
+                    values.join(", ")
+                )
+            };
+            changes.push((sql, params));
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_functions::pg_schema::TableIndex;
+
+    fn editor() -> DataEditor {
+        let column = |name: &str, data_type: &str| TableColumn {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            not_null: false,
+            default_value: None,
+            identity: String::new(),
+            generated: String::new(),
+            comment: None,
+        };
+        let details = TableDetails {
+            columns: vec![
+                column("id", "integer"),
+                column("Note", "text"),
+                column("qty", "numeric(10,2)"),
+            ],
+            indexes: vec![TableIndex {
+                oid: sqlx::postgres::types::Oid(1),
+                name: String::from("t_pkey"),
+                definition: String::new(),
+                is_primary: true,
+                is_unique: true,
+                columns: vec![String::from("id")],
+                method: String::from("btree"),
+                keys: vec![String::from("id")],
+                predicate: None,
+                is_constraint: true,
+            }],
+            ..Default::default()
+        };
+        let mut editor = DataEditor::new("key", "public", "t", &details);
+        editor.set_rows(vec![
+            vec![Some(String::from("1")), None, Some(String::from("2.00"))],
+            vec![Some(String::from("2")), Some(String::from("b")), None],
+        ]);
+        editor
+    }
+
+    #[test]
+    fn reads_pages_in_key_order() {
+        let editor = editor();
+        assert_eq!(editor.read_only, None);
+        assert_eq!(
+            editor.select_sql(),
+            "SELECT id::text, \"Note\"::text, qty::text FROM public.t ORDER BY id LIMIT 101 OFFSET 0"
+        );
+        assert!(!editor.has_more);
+    }
+
+    #[test]
+    fn reverting_a_cell_drops_the_edit() {
+        let mut editor = editor();
+        editor.set_value(0, 1, Some(String::from("a")));
+        assert!(editor.is_dirty());
+        editor.set_value(0, 1, None);
+        assert!(!editor.is_dirty());
+    }
+
+    #[test]
+    fn builds_deletes_updates_and_inserts() {
+        let mut editor = editor();
+        editor.deleted.insert(1);
+        editor.set_value(1, 1, Some(String::from("ignored")));
+        editor.set_value(0, 1, Some(String::from("a")));
+        editor.set_value(0, 2, None);
+        editor.add_row();
+        editor.add_row();
+        editor.inserted[1][1] = NewCell::Value(Some(String::from("new")));
+
+        let one = |value: &str| Some(String::from(value));
+        assert_eq!(
+            editor.changes(),
+            [
+                (
+                    String::from("DELETE FROM public.t WHERE id = CAST($1 AS integer)"),
+                    vec![one("2")]
+                ),
+                (
+                    String::from(
+                        "UPDATE public.t SET \"Note\" = CAST($1 AS text), \
+                         qty = CAST($2 AS numeric(10,2)) WHERE id = CAST($3 AS integer)"
+                    ),
+                    vec![one("a"), None, one("1")]
+                ),
+                (String::from("INSERT INTO public.t DEFAULT VALUES"), vec![]),
+                (
+                    String::from("INSERT INTO public.t (\"Note\") VALUES (CAST($1 AS text))"),
+                    vec![one("new")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tables_without_a_primary_key_are_read_only() {
+        let editor = DataEditor::new("key", "public", "t", &TableDetails::default());
+        assert!(editor.read_only.is_some());
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use egui::{pos2, vec2, ColorImage, Pos2, Rect, Vec2};
+
+use crate::{
+    app::new_connection_id,
+    query_functions::pg_data::{DiagramColumn, DiagramForeignKey},
+    sql::qualified_name,
+};
+
+/// Sizes of the drawn tables. Text is monospace so node sizes can be worked
+/// out without measuring it, the same way for the canvas and for SVG.
+pub const FONT_SIZE: f32 = 12.0;
+pub const CHAR_WIDTH: f32 = 7.5;
+pub const HEADER_HEIGHT: f32 = 24.0;
+pub const ROW_HEIGHT: f32 = 18.0;
+pub const PADDING: f32 = 8.0;
+/// Width of the PK/FK marker in characters.
+const MARKER_CHARS: usize = 3;
+/// Offset of column names from the marker.
+pub const MARKER_WIDTH: f32 = (MARKER_CHARS + 1) as f32 * CHAR_WIDTH;
+const COLUMN_GAP: f32 = 80.0;
+const ROW_GAP: f32 = 30.0;
+/// Layout columns taller than this are wrapped into another column.
+const MAX_COLUMN_HEIGHT: f32 = 1200.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErColumn {
+    pub name: String,
+    pub data_type: String,
+    pub primary_key: bool,
+    pub foreign_key: bool,
+}
+
+impl ErColumn {
+    pub fn marker(&self) -> &'static str {
+        match (self.primary_key, self.foreign_key) {
+            (true, true) => "PF",
+            (true, false) => "PK",
+            (false, true) => "FK",
+            (false, false) => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErTable {
+    /// Qualified name, which is also the key of the table's position.
+    pub name: String,
+    pub columns: Vec<ErColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErRelation {
+    pub name: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub foreign_table: String,
+    pub foreign_columns: Vec<String>,
+}
+
+/// A line from a foreign key column to the column it references. The
+/// directions are +1 when the line leaves or enters a table on its right
+/// side and -1 on its left side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErEdge {
+    pub from: Pos2,
+    pub from_dir: f32,
+    pub to: Pos2,
+    pub to_dir: f32,
+}
+
+impl ErEdge {
+    /// Control points of the curve drawn for the edge.
+    pub fn bezier(&self) -> [Pos2; 4] {
+        let bend = ((self.to.x - self.from.x).abs() / 2.0).max(40.0);
+        [
+            self.from,
+            self.from + vec2(self.from_dir * bend, 0.0),
+            self.to + vec2(self.to_dir * bend, 0.0),
+            self.to,
+        ]
+    }
+}
+
+/// The tables of a schema and the foreign keys between them.
+#[derive(Debug)]
+pub struct ErDiagram {
+    /// Tells screenshots for this diagram apart from other ones.
+    pub id: String,
+    pub key: String,
+    /// Positions are saved under this key, made of the saved connection and
+    /// the database so they survive reconnecting.
+    pub layout_key: String,
+    pub schema: String,
+    pub tables: Vec<ErTable>,
+    pub relations: Vec<ErRelation>,
+    /// Tables left out of the diagram.
+    pub hidden: BTreeSet<String>,
+    pub positions: HashMap<String, Pos2>,
+    /// The part of the diagram in view, changed by panning and zooming.
+    pub scene_rect: Rect,
+    /// Where the canvas was last drawn on screen, for PNG exports.
+    pub canvas: Rect,
+    pub error: Option<String>,
+    pub status: String,
+}
+
+impl ErDiagram {
+    pub fn new(
+        key: &str,
+        layout_key: &str,
+        schema: &str,
+        columns: Vec<DiagramColumn>,
+        foreign_keys: Vec<DiagramForeignKey>,
+        saved: Option<&HashMap<String, [f32; 2]>>,
+    ) -> Self {
+        let relations: Vec<ErRelation> = foreign_keys
+            .into_iter()
+            .map(|foreign_key| ErRelation {
+                name: foreign_key.name,
+                table: qualified_name(schema, &foreign_key.table_name),
+                columns: foreign_key.columns,
+                foreign_table: qualified_name(
+                    &foreign_key.foreign_schema,
+                    &foreign_key.foreign_table,
+                ),
+                foreign_columns: foreign_key.foreign_columns,
+            })
+            .collect();
+
+        let mut tables: Vec<ErTable> = Vec::new();
+        for column in columns {
+            let name = qualified_name(schema, &column.table_name);
+            if tables.last().is_none_or(|table| table.name != name) {
+                tables.push(ErTable {
+                    name: name.clone(),
+                    columns: Vec::new(),
+                });
+            }
+            let foreign_key = relations.iter().any(|relation| {
+                relation.table == name && relation.columns.contains(&column.column_name)
+            });
+            if let Some(table) = tables.last_mut() {
+                table.columns.push(ErColumn {
+                    name: column.column_name,
+                    data_type: column.data_type,
+                    primary_key: column.primary_key,
+                    foreign_key,
+                });
+            }
+        }
+
+        let positions = saved
+            .map(|saved| {
+                saved
+                    .iter()
+                    .map(|(name, [x, y])| (name.clone(), pos2(*x, *y)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut diagram = Self {
+            id: new_connection_id(),
+            key: key.to_string(),
+            layout_key: layout_key.to_string(),
+            schema: schema.to_string(),
+            tables,
+            relations,
+            hidden: BTreeSet::new(),
+            positions,
+            scene_rect: Rect::NOTHING,
+            canvas: Rect::NOTHING,
+            error: None,
+            status: String::new(),
+        };
+        diagram.place_new_tables();
+        diagram.fit();
+        diagram
+    }
+
+    pub fn title(&self) -> String {
+        format!("ER diagram: {}", self.schema)
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &ErTable> {
+        self.tables
+            .iter()
+            .filter(|table| !self.hidden.contains(&table.name))
+    }
+
+    /// Shows only `table` and the tables it references or is referenced by.
+    pub fn focus(&mut self, table: &str) {
+        let mut shown = BTreeSet::from([table.to_string()]);
+        for relation in &self.relations {
+            if relation.table == table {
+                shown.insert(relation.foreign_table.clone());
+            }
+            if relation.foreign_table == table {
+                shown.insert(relation.table.clone());
+            }
+        }
+        self.hidden = self
+            .tables
+            .iter()
+            .map(|table| table.name.clone())
+            .filter(|name| !shown.contains(name))
+            .collect();
+        self.place_new_tables();
+        self.fit();
+    }
+
+    pub fn node_size(table: &ErTable) -> Vec2 {
+        let name_chars = table.columns.iter().map(|c| c.name.chars().count()).max();
+        let type_chars = table
+            .columns
+            .iter()
+            .map(|c| c.data_type.chars().count())
+            .max();
+        let row_chars = MARKER_CHARS + 1 + name_chars.unwrap_or(0) + 2 + type_chars.unwrap_or(0);
+        let chars = row_chars.max(table.name.chars().count() + 1);
+        vec2(
+            chars as f32 * CHAR_WIDTH + 2.0 * PADDING,
+            HEADER_HEIGHT + table.columns.len() as f32 * ROW_HEIGHT + PADDING / 2.0,
+        )
+    }
+
+    pub fn node_rect(&self, table: &ErTable) -> Rect {
+        let position = self.positions.get(&table.name).copied().unwrap_or_default();
+        Rect::from_min_size(position, Self::node_size(table))
+    }
+
+    /// Vertical centre of a column's row, or of the header if there is no
+    /// such column.
+    fn row_y(&self, table: &ErTable, column: Option<&String>) -> f32 {
+        let rect = self.node_rect(table);
+        match column.and_then(|name| table.columns.iter().position(|c| c.name == *name)) {
+            Some(idx) => rect.top() + HEADER_HEIGHT + (idx as f32 + 0.5) * ROW_HEIGHT,
+            None => rect.top() + HEADER_HEIGHT / 2.0,
+        }
+    }
+
+    /// Edges of the foreign keys whose tables are both shown.
+    pub fn edges(&self) -> Vec<ErEdge> {
+        let visible: HashMap<&str, &ErTable> = self
+            .visible()
+            .map(|table| (table.name.as_str(), table))
+            .collect();
+        self.relations
+            .iter()
+            .filter_map(|relation| {
+                let table = visible.get(relation.table.as_str())?;
+                let foreign_table = visible.get(relation.foreign_table.as_str())?;
+                let from_rect = self.node_rect(table);
+                let to_rect = self.node_rect(foreign_table);
+                let from_y = self.row_y(table, relation.columns.first());
+                let to_y = self.row_y(foreign_table, relation.foreign_columns.first());
+                let (from_dir, to_dir) = if relation.table == relation.foreign_table {
+                    (1.0, 1.0)
+                } else if from_rect.center().x <= to_rect.center().x {
+                    (1.0, -1.0)
+                } else {
+                    (-1.0, 1.0)
+                };
+                let side =
+                    |rect: Rect, dir: f32| if dir > 0.0 { rect.right() } else { rect.left() };
+                Some(ErEdge {
+                    from: pos2(side(from_rect, from_dir), from_y),
+                    from_dir,
+                    to: pos2(side(to_rect, to_dir), to_y),
+                    to_dir,
+                })
+            })
+            .collect()
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.visible()
+            .map(|table| self.node_rect(table))
+            .fold(Rect::NOTHING, |bounds, rect| bounds.union(rect))
+    }
+
+    /// Brings all shown tables into view.
+    pub fn fit(&mut self) {
+        let bounds = self.bounds();
+        self.scene_rect = if bounds.is_positive() {
+            bounds.expand(2.0 * PADDING)
+        } else {
+            Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0))
+        };
+    }
+
+    /// Lays out the shown tables in columns so that tables are to the right
+    /// of the tables they reference.
+    pub fn auto_layout(&mut self) {
+        let names: Vec<String> = self.visible().map(|table| table.name.clone()).collect();
+        let layout = self.layout(&names, Pos2::ZERO);
+        self.positions.extend(layout);
+        self.fit();
+    }
+
+    /// Lays out shown tables that have no position yet, below the rest.
+    pub fn place_new_tables(&mut self) {
+        let placed = self.bounds();
+        let new: Vec<String> = self
+            .visible()
+            .filter(|table| !self.positions.contains_key(&table.name))
+            .map(|table| table.name.clone())
+            .collect();
+        if new.is_empty() {
+            return;
+        }
+        let origin = if placed.is_positive() {
+            pos2(placed.left(), placed.bottom() + COLUMN_GAP)
+        } else {
+            Pos2::ZERO
+        };
+        let layout = self.layout(&new, origin);
+        self.positions.extend(layout);
+    }
+
+    fn layout(&self, names: &[String], origin: Pos2) -> Vec<(String, Pos2)> {
+        let included: BTreeSet<&str> = names.iter().map(String::as_str).collect();
+        // A table's rank is the length of the longest chain of references
+        // from it, capped so that cycles end.
+        let mut ranks: HashMap<&str, usize> = names.iter().map(|name| (name.as_str(), 0)).collect();
+        for _ in 0..names.len() {
+            let mut changed = false;
+            for relation in &self.relations {
+                let (table, foreign) = (relation.table.as_str(), relation.foreign_table.as_str());
+                if table == foreign || !included.contains(table) || !included.contains(foreign) {
+                    continue;
+                }
+                let rank = (ranks[foreign] + 1).min(names.len());
+                if rank > ranks[table] {
+                    ranks.insert(table, rank);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut columns: Vec<Vec<&ErTable>> = Vec::new();
+        for table in self
+            .tables
+            .iter()
+            .filter(|t| included.contains(t.name.as_str()))
+        {
+            let rank = ranks[table.name.as_str()];
+            if columns.len() <= rank {
+                columns.resize(rank + 1, Vec::new());
+            }
+            columns[rank].push(table);
+        }
+
+        let mut positions = Vec::new();
+        let mut x = origin.x;
+        for column in columns.iter().filter(|column| !column.is_empty()) {
+            let mut y = origin.y;
+            let mut width: f32 = 0.0;
+            for table in column {
+                let size = Self::node_size(table);
+                if y > origin.y && y + size.y > origin.y + MAX_COLUMN_HEIGHT {
+                    x += width + COLUMN_GAP;
+                    y = origin.y;
+                    width = 0.0;
+                }
+                positions.push((table.name.clone(), pos2(x, y)));
+                y += size.y + ROW_GAP;
+                width = width.max(size.x);
+            }
+            x += width + COLUMN_GAP;
+        }
+        positions
+    }
+
+    /// Positions to save, including those of hidden tables.
+    pub fn saved_positions(&self) -> HashMap<String, [f32; 2]> {
+        self.positions
+            .iter()
+            .map(|(name, position)| (name.clone(), [position.x, position.y]))
+            .collect()
+    }
+
+    /// The shown tables and their foreign keys as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let bounds = self.bounds().expand(2.0 * PADDING);
+        let bounds = if bounds.is_positive() {
+            bounds
+        } else {
+            Rect::from_min_size(Pos2::ZERO, Vec2::splat(1.0))
+        };
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"{x} {y} {w} {h}\" font-family=\"monospace\" font-size=\"{FONT_SIZE}\">\n\
+             <rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+            x = bounds.left(),
+            y = bounds.top(),
+            w = bounds.width(),
+            h = bounds.height(),
+        );
+
+        for edge in self.edges() {
+            let [a, b, c, d] = edge.bezier();
+            svg += &format!(
+                "<path d=\"M {} {} C {} {}, {} {}, {} {}\" fill=\"none\" stroke=\"#888888\"/>\n\
+                 <circle cx=\"{}\" cy=\"{}\" r=\"3\" fill=\"#888888\"/>\n",
+                a.x, a.y, b.x, b.y, c.x, c.y, d.x, d.y, d.x, d.y
+            );
+        }
+
+        for table in self.visible() {
+            let rect = self.node_rect(table);
+            let text_x = rect.left() + PADDING;
+            svg += &format!(
+                "<g>\n<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" \
+                 fill=\"white\" stroke=\"#444444\"/>\n\
+                 <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{HEADER_HEIGHT}\" rx=\"4\" \
+                 fill=\"#dde4ee\" stroke=\"#444444\"/>\n\
+                 <text x=\"{text_x}\" y=\"{}\" font-weight=\"bold\">{}</text>\n",
+                rect.left(),
+                rect.top(),
+                rect.width(),
+                rect.height(),
+                rect.left(),
+                rect.top(),
+                rect.width(),
+                rect.top() + HEADER_HEIGHT / 2.0 + FONT_SIZE / 3.0,
+                xml_escape(&table.name),
+            );
+            for (idx, column) in table.columns.iter().enumerate() {
+                let y =
+                    rect.top() + HEADER_HEIGHT + (idx as f32 + 0.5) * ROW_HEIGHT + FONT_SIZE / 3.0;
+                svg += &format!(
+                    "<text x=\"{text_x}\" y=\"{y}\" fill=\"#aa6600\">{}</text>\
+                     <text x=\"{}\" y=\"{y}\">{}</text>\
+                     <text x=\"{}\" y=\"{y}\" text-anchor=\"end\" fill=\"#777777\">{}</text>\n",
+                    column.marker(),
+                    text_x + MARKER_WIDTH,
+                    xml_escape(&column.name),
+                    rect.right() - PADDING,
+                    xml_escape(&column.data_type),
+                );
+            }
+            svg += "</g>\n";
+        }
+        svg + "</svg>\n"
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn write_png(path: &Path, image: &ColorImage) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        image.width() as u32,
+        image.height() as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(image.as_raw())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagram(saved: Option<&HashMap<String, [f32; 2]>>) -> ErDiagram {
+        let column = |table: &str, name: &str, primary_key: bool| DiagramColumn {
+            table_name: table.to_string(),
+            column_name: name.to_string(),
+            data_type: String::from("integer"),
+            primary_key,
+        };
+        let foreign_key = |table: &str, column: &str, foreign_table: &str| DiagramForeignKey {
+            name: format!("{table}_{column}_fkey"),
+            table_name: table.to_string(),
+            columns: vec![column.to_string()],
+            foreign_schema: String::from("public"),
+            foreign_table: foreign_table.to_string(),
+            foreign_columns: vec![String::from("id")],
+        };
+        ErDiagram::new(
+            "key",
+            "connection/db",
+            "public",
+            vec![
+                column("customers", "id", true),
+                column("lines", "order_id", true),
+                column("lines", "n", true),
+                column("notes", "id", true),
+                column("orders", "id", true),
+                column("orders", "customer_id", false),
+            ],
+            vec![
+                foreign_key("lines", "order_id", "orders"),
+                foreign_key("orders", "customer_id", "customers"),
+            ],
+            saved,
+        )
+    }
+
+    #[test]
+    fn references_are_laid_out_left_to_right() {
+        let diagram = diagram(None);
+        let x = |name: &str| diagram.positions[name].x;
+        assert!(x("public.customers") < x("public.orders"));
+        assert!(x("public.orders") < x("public.lines"));
+        assert_eq!(x("public.notes"), x("public.customers"));
+
+        let lines = &diagram.tables[1];
+        assert_eq!(lines.columns[0].marker(), "PF");
+        assert_eq!(lines.columns[1].marker(), "PK");
+
+        let edges = diagram.edges();
+        assert_eq!(edges.len(), 2);
+        let orders = diagram.node_rect(&diagram.tables[3]);
+        // lines is right of orders, so lines.order_id points at the right side
+        // of the row for orders.id.
+        assert_eq!(
+            edges[0].to,
+            pos2(
+                orders.right(),
+                orders.top() + HEADER_HEIGHT + ROW_HEIGHT / 2.0
+            )
+        );
+    }
+
+    #[test]
+    fn saved_positions_are_kept_and_new_tables_placed_below() {
+        let saved = HashMap::from([(String::from("public.orders"), [500.0, 40.0])]);
+        let diagram = diagram(Some(&saved));
+        assert_eq!(diagram.positions["public.orders"], pos2(500.0, 40.0));
+        assert!(diagram.positions["public.customers"].y > 40.0);
+        assert_eq!(diagram.saved_positions().len(), 4);
+    }
+
+    #[test]
+    fn focusing_hides_unrelated_tables() {
+        let mut diagram = diagram(None);
+        diagram.focus("public.customers");
+        assert_eq!(diagram.visible().count(), 2);
+        assert_eq!(diagram.edges().len(), 1);
+
+        let svg = diagram.to_svg();
+        assert!(svg.contains(">public.customers</text>"));
+        assert!(!svg.contains(">public.lines</text>"));
+        assert_eq!(svg.matches("<path").count(), 1);
+    }
+}
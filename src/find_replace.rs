@@ -0,0 +1,328 @@
+use std::ops::Range;
+
+use egui::{text::LayoutJob, Color32};
+use regex::{NoExpand, Regex, RegexBuilder};
+
+#[derive(Debug, Default)]
+pub struct FindReplace {
+    pub open: bool,
+    pub show_replace: bool,
+    pub query: String,
+    pub replacement: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub use_regex: bool,
+    /// Index of the selected match, used for navigation and its highlight.
+    pub current: usize,
+    /// Set when the editor should scroll to the selected match.
+    pub scroll_to_current: bool,
+    compiled: Option<CompiledQuery>,
+}
+
+/// The regex built for a query, kept until the query or its options change.
+#[derive(Debug)]
+struct CompiledQuery {
+    query: String,
+    case_sensitive: bool,
+    whole_word: bool,
+    use_regex: bool,
+    regex: Option<Result<Regex, String>>,
+}
+
+impl FindReplace {
+    /// The regex for the current query, `None` when the query is empty.
+    pub fn regex(&mut self) -> Option<&Result<Regex, String>> {
+        let stale = self.compiled.as_ref().is_none_or(|compiled| {
+            compiled.query != self.query
+                || compiled.case_sensitive != self.case_sensitive
+                || compiled.whole_word != self.whole_word
+                || compiled.use_regex != self.use_regex
+        });
+        if stale {
+            self.compiled = Some(CompiledQuery {
+                query: self.query.clone(),
+                case_sensitive: self.case_sensitive,
+                whole_word: self.whole_word,
+                use_regex: self.use_regex,
+                regex: self.build_regex(),
+            });
+        }
+        self.compiled
+            .as_ref()
+            .and_then(|compiled| compiled.regex.as_ref())
+    }
+
+    fn build_regex(&self) -> Option<Result<Regex, String>> {
+        if self.query.is_empty() {
+            return None;
+        }
+        let pattern = if self.use_regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        Some(
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .multi_line(true)
+                .build()
+                .map_err(|e| e.to_string()),
+        )
+    }
+
+    pub fn find_matches(&mut self, text: &str) -> Vec<Range<usize>> {
+        match self.regex() {
+            Some(Ok(regex)) => match_ranges(regex, text),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn step(&mut self, match_count: usize, forward: bool) {
+        if match_count == 0 {
+            return;
+        }
+        self.current = if forward {
+            (self.current + 1) % match_count
+        } else {
+            (self.current + match_count - 1) % match_count
+        };
+        self.scroll_to_current = true;
+    }
+
+    /// Replaces the selected match and returns whether the text changed.
+    pub fn replace_current(&mut self, text: &mut String) -> bool {
+        let Some(Ok(regex)) = self.regex().cloned() else {
+            return false;
+        };
+        let matches = match_ranges(&regex, text);
+        let Some(range) = matches.get(self.current).cloned() else {
+            return false;
+        };
+        let replacement = if self.use_regex {
+            regex.replace(&text[range.clone()], self.replacement.as_str())
+        } else {
+            regex.replace(&text[range.clone()], NoExpand(&self.replacement))
+        }
+        .into_owned();
+        text.replace_range(range, &replacement);
+        self.scroll_to_current = true;
+        true
+    }
+
+    /// Replaces every match and returns how many were replaced.
+    pub fn replace_all(&mut self, text: &mut String) -> usize {
+        let Some(Ok(regex)) = self.regex().cloned() else {
+            return 0;
+        };
+        let count = match_ranges(&regex, text).len();
+        let replaced = if self.use_regex {
+            regex.replace_all(text, self.replacement.as_str())
+        } else {
+            regex.replace_all(text, NoExpand(&self.replacement))
+        }
+        .into_owned();
+        *text = replaced;
+        self.current = 0;
+        count
+    }
+}
+
+pub fn match_ranges(regex: &Regex, text: &str) -> Vec<Range<usize>> {
+    regex
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| m.range())
+        .collect()
+}
+
+/// Splits the job's sections at match boundaries and gives the matches a background.
+pub fn highlight_matches(
+    job: &mut LayoutJob,
+    matches: &[Range<usize>],
+    current: usize,
+    match_colour: Color32,
+    current_colour: Color32,
+) {
+    if matches.is_empty() {
+        return;
+    }
+
+    let mut sections = Vec::with_capacity(job.sections.len() + matches.len() * 2);
+    let mut first_match = 0;
+    for section in job.sections.drain(..) {
+        let mut start = section.byte_range.start;
+        let end = section.byte_range.end;
+        let mut leading_space = section.leading_space;
+
+        // Both sections and matches are sorted, so skip the matches already passed.
+        while first_match < matches.len() && matches[first_match].end <= start {
+            first_match += 1;
+        }
+
+        for (idx, m) in matches.iter().enumerate().skip(first_match) {
+            if m.start >= end {
+                break;
+            }
+            if m.start > start {
+                let mut before = section.clone();
+                before.byte_range = start..m.start;
+                before.leading_space = leading_space;
+                leading_space = 0.0;
+                sections.push(before);
+                start = m.start;
+            }
+            let mut highlighted = section.clone();
+            highlighted.byte_range = start..m.end.min(end);
+            highlighted.leading_space = leading_space;
+            leading_space = 0.0;
+            highlighted.format.background = if idx == current {
+                current_colour
+            } else {
+                match_colour
+            };
+            start = highlighted.byte_range.end;
+            sections.push(highlighted);
+        }
+
+        if start < end {
+            let mut rest = section;
+            rest.byte_range = start..end;
+            rest.leading_space = leading_space;
+            sections.push(rest);
+        }
+    }
+    job.sections = sections;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{FontId, TextFormat};
+
+    fn ranges(pairs: &[(usize, usize)]) -> Vec<Range<usize>> {
+        pairs.iter().map(|&(start, end)| start..end).collect()
+    }
+
+    fn search(query: &str) -> FindReplace {
+        FindReplace {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_with_case_and_whole_word_options() {
+        let text = "select Name, name_two FROM names";
+        let mut find = search("name");
+        assert_eq!(
+            find.find_matches(text),
+            ranges(&[(7, 11), (13, 17), (27, 31)])
+        );
+
+        find.case_sensitive = true;
+        assert_eq!(find.find_matches(text), ranges(&[(13, 17), (27, 31)]));
+
+        find.case_sensitive = false;
+        find.whole_word = true;
+        assert_eq!(find.find_matches(text), ranges(&[(7, 11)]));
+
+        // Regex characters are literal unless regex mode is on.
+        let mut find = search("a.c");
+        assert_eq!(find.find_matches("abc a.c"), ranges(&[(4, 7)]));
+        find.use_regex = true;
+        assert_eq!(find.find_matches("abc a.c"), ranges(&[(0, 3), (4, 7)]));
+        assert!(search("").find_matches("abc").is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_regexes() {
+        let mut find = search("(unclosed");
+        find.use_regex = true;
+        assert!(matches!(find.regex(), Some(Err(_))));
+        assert!(find.find_matches("(unclosed").is_empty());
+        let mut text = String::from("(unclosed");
+        assert!(!find.replace_current(&mut text));
+        assert_eq!(find.replace_all(&mut text), 0);
+
+        find.use_regex = false;
+        assert_eq!(find.find_matches("(unclosed"), ranges(&[(0, 9)]));
+    }
+
+    #[test]
+    fn skips_empty_regex_matches() {
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!(match_ranges(&regex, "axxb"), ranges(&[(1, 3)]));
+    }
+
+    #[test]
+    fn replaces_literally_unless_using_regexes() {
+        let mut find = search("(\\w+)@");
+        find.replacement = String::from("$1 at ");
+        let mut text = String::from("(\\w+)@ and (\\w+)@");
+        find.current = 1;
+        assert!(find.replace_current(&mut text));
+        assert_eq!(text, "(\\w+)@ and $1 at ");
+
+        let mut find = search("(\\w+)@");
+        find.use_regex = true;
+        find.replacement = String::from("$1 at ");
+        let mut text = String::from("ann@ bob@");
+        assert!(find.replace_current(&mut text));
+        assert_eq!(text, "ann at  bob@");
+        assert_eq!(find.replace_all(&mut text), 1);
+        assert_eq!(text, "ann at  bob at ");
+
+        let mut find = search("$");
+        find.replacement = String::from("${0}");
+        let mut text = String::from("a $ b $");
+        assert_eq!(find.replace_all(&mut text), 2);
+        assert_eq!(text, "a ${0} b ${0}");
+        assert_eq!(find.current, 0);
+
+        find.current = 5;
+        assert!(!find.replace_current(&mut text));
+    }
+
+    #[test]
+    fn splits_sections_at_match_boundaries() {
+        let format = |colour| TextFormat::simple(FontId::monospace(12.0), colour);
+        let mut job = LayoutJob::default();
+        job.append("SELECT ", 0.0, format(Color32::RED));
+        job.append("name FROM t", 0.0, format(Color32::BLUE));
+        let (match_colour, current_colour) = (Color32::YELLOW, Color32::GREEN);
+
+        highlight_matches(&mut job, &[5..9, 12..16], 0, match_colour, current_colour);
+        let sections: Vec<(Range<usize>, Color32, Color32)> = job
+            .sections
+            .iter()
+            .map(|section| {
+                (
+                    section.byte_range.clone(),
+                    section.format.color,
+                    section.format.background,
+                )
+            })
+            .collect();
+        let none = Color32::TRANSPARENT;
+        assert_eq!(
+            sections,
+            [
+                (0..5, Color32::RED, none),
+                (5..7, Color32::RED, current_colour),
+                (7..9, Color32::BLUE, current_colour),
+                (9..12, Color32::BLUE, none),
+                (12..16, Color32::BLUE, match_colour),
+                (16..18, Color32::BLUE, none),
+            ]
+        );
+
+        let before = job.sections.len();
+        highlight_matches(&mut job, &[], 0, match_colour, current_colour);
+        assert_eq!(job.sections.len(), before);
+    }
+}
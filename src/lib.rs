@@ -1,8 +1,24 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+pub mod connection_file;
+pub mod connection_string;
+pub mod data_diff;
+pub mod data_editor;
+pub mod er_diagram;
+pub mod find_replace;
+pub mod libpq;
+pub mod library;
+pub mod object_search;
 pub mod postgres;
 pub mod query_functions;
+pub mod schema_diff;
+pub mod sql;
+pub mod ssh_tunnel;
+pub mod table_designer;
+pub mod table_sql;
+pub mod table_stats;
 pub mod themes;
 pub mod ui;
+pub mod vault;
 pub use app::Rosemary;
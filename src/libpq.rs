@@ -0,0 +1,300 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    app::SavedConnection,
+    connection_string::{connection_from_params, parse_connection_string, ImportedConnection},
+};
+
+const DEFAULT_PORT: &str = "5432";
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgPassEntry {
+    pub host: String,
+    pub port: String,
+    pub database: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl PgPassEntry {
+    fn matches(&self, host: &str, port: &str, database: &str, username: &str) -> bool {
+        let field_matches = |pattern: &str, value: &str| pattern == "*" || pattern == value;
+        field_matches(&self.host, host)
+            && field_matches(&self.port, port)
+            && field_matches(&self.database, database)
+            && field_matches(&self.username, username)
+    }
+}
+
+pub fn pgpass_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("PGPASSFILE") {
+        return Some(PathBuf::from(path));
+    }
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("postgresql/pgpass.conf"))
+    } else {
+        home_dir().map(|dir| dir.join(".pgpass"))
+    }
+}
+
+/// Parses `hostname:port:database:username:password` lines, where `\:` and `\\`
+/// escape a colon or backslash.
+pub fn parse_pgpass(contents: &str) -> Vec<PgPassEntry> {
+    contents
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = Vec::with_capacity(5);
+            let mut field = String::new();
+            let mut chars = line.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => field.extend(chars.next()),
+                    ':' if fields.len() < 4 => fields.push(std::mem::take(&mut field)),
+                    // Like libpq, anything after an unescaped colon in the password is ignored.
+                    ':' => break,
+                    _ => field.push(c),
+                }
+            }
+            fields.push(field);
+
+            match <[String; 5]>::try_from(fields) {
+                Ok([host, port, database, username, password]) => Some(PgPassEntry {
+                    host,
+                    port,
+                    database,
+                    username,
+                    password,
+                }),
+                Err(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// libpq ignores a password file that other users can read.
+fn pgpass_is_private(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.permissions().mode() & 0o077 != 0 => {
+                eprintln!(
+                    "Ignoring {}: it should not be readable by group or others (chmod 0600)",
+                    path.display()
+                );
+                false
+            }
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        path.exists()
+    }
+}
+
+/// Finds the password for a connection in the password file, using the first
+/// matching line like libpq does.
+pub fn lookup_pgpass(host: &str, port: &str, database: &str, username: &str) -> Option<String> {
+    let path = pgpass_path()?;
+    if !pgpass_is_private(&path) {
+        return None;
+    }
+    let contents = fs::read_to_string(&path).ok()?;
+
+    // Unix socket connections are matched by the "localhost" host name.
+    let host = if host.is_empty() || host.starts_with('/') {
+        "localhost"
+    } else {
+        host
+    };
+    let port = if port.is_empty() { DEFAULT_PORT } else { port };
+
+    parse_pgpass(&contents)
+        .into_iter()
+        .find(|entry| entry.matches(host, port, database, username))
+        .map(|entry| entry.password)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgService {
+    pub name: String,
+    pub params: BTreeMap<String, String>,
+}
+
+impl PgService {
+    pub fn to_connection(&self) -> ImportedConnection {
+        SavedConnection {
+            connection_name: self.name.clone(),
+            ..connection_from_params(self.params.clone())
+        }
+        .into()
+    }
+}
+
+/// Parses the INI style connection service file.
+pub fn parse_service_file(contents: &str) -> Vec<PgService> {
+    let mut services: Vec<PgService> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            services.push(PgService {
+                name: name.trim().to_string(),
+                params: BTreeMap::new(),
+            });
+        } else if let (Some(service), Some((key, value))) =
+            (services.last_mut(), line.split_once('='))
+        {
+            service
+                .params
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    services
+}
+
+fn service_file_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    match env::var_os("PGSERVICEFILE") {
+        Some(path) => paths.push(PathBuf::from(path)),
+        None => paths.extend(home_dir().map(|dir| dir.join(".pg_service.conf"))),
+    }
+    if let Some(dir) = env::var_os("PGSYSCONFDIR") {
+        paths.push(PathBuf::from(dir).join("pg_service.conf"));
+    }
+    paths
+}
+
+/// Loads the services from the user's service file and the system wide one,
+/// with the user's definitions taking precedence.
+pub fn load_services() -> Vec<PgService> {
+    let mut services: Vec<PgService> = Vec::new();
+    for path in service_file_paths() {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for service in parse_service_file(&contents) {
+            if !services.iter().any(|s| s.name == service.name) {
+                services.push(service);
+            }
+        }
+    }
+    services
+}
+
+/// Builds a connection from `PGHOST`, `PGPORT`, `PGUSER`, `PGPASSWORD` and
+/// `PGDATABASE`, if any of them are set.
+pub fn connection_from_pg_env() -> Option<SavedConnection> {
+    let var = |key: &str| env::var(key).ok().filter(|value| !value.is_empty());
+    let (host, port, user, password, database) = (
+        var("PGHOST"),
+        var("PGPORT"),
+        var("PGUSER"),
+        var("PGPASSWORD"),
+        var("PGDATABASE"),
+    );
+    if host.is_none() && port.is_none() && user.is_none() && database.is_none() {
+        return None;
+    }
+
+    let host = host.unwrap_or_else(|| String::from("localhost"));
+    Some(SavedConnection {
+        connection_name: format!("{host} (environment)"),
+        db_host: host,
+        db_port: port.unwrap_or_else(|| DEFAULT_PORT.to_string()),
+        db_user: user.unwrap_or_default(),
+        db_password: password.unwrap_or_default(),
+        db_name: database.unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+/// Builds a connection from the `DATABASE_URL` environment variable (or `.env` file).
+pub fn connection_from_database_url() -> Option<ImportedConnection> {
+    let database_url = env::var("DATABASE_URL").ok()?;
+    if !database_url.starts_with("postgres://") && !database_url.starts_with("postgresql://") {
+        return None;
+    }
+    let mut imported = parse_connection_string(&database_url).ok()?;
+    imported.connection.connection_name = format!("{} (DATABASE_URL)", imported.connection.db_host);
+    Some(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pgpass_escapes() {
+        let entries = parse_pgpass(
+            "# comment\n\
+             db\\:host:5432:*:me:pa\\:ss\\\\word\r\n\
+             \n\
+             *:*:*:*:secret:ignored after colon\n\
+             too:few:fields\n",
+        );
+        assert_eq!(
+            entries,
+            [
+                PgPassEntry {
+                    host: String::from("db:host"),
+                    port: String::from("5432"),
+                    database: String::from("*"),
+                    username: String::from("me"),
+                    password: String::from(r"pa:ss\word"),
+                },
+                PgPassEntry {
+                    host: String::from("*"),
+                    port: String::from("*"),
+                    database: String::from("*"),
+                    username: String::from("*"),
+                    password: String::from("secret"),
+                },
+            ]
+        );
+        assert!(entries[0].matches("db:host", "5432", "any", "me"));
+        assert!(!entries[0].matches("db", "5432", "any", "me"));
+    }
+
+    #[test]
+    fn parses_service_files() {
+        let services = parse_service_file(
+            "ignored=before any section\n\
+             [prod]\n\
+             # comment\n\
+             host = db.example.com\n\
+             port=5433\n\
+             dbname=app\n\
+             keepalives=1\n\
+             [ local ]\n\
+             host=/tmp\n",
+        );
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].name, "prod");
+        assert_eq!(services[0].params["host"], "db.example.com");
+        assert_eq!(services[1].name, "local");
+
+        let imported = services[0].to_connection();
+        assert_eq!(imported.connection.connection_name, "prod");
+        assert_eq!(imported.connection.db_port, "5433");
+        assert_eq!(imported.connection.db_name, "app");
+        assert_eq!(imported.warnings.len(), 1);
+        assert!(imported.warnings[0].contains("keepalives"));
+    }
+}
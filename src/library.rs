@@ -0,0 +1,273 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+/// A named query or snippet stored as a `.sql` file in the library directory.
+///
+/// Metadata lives in `-- key: value` comment lines at the top of the file so the
+/// files stay plain SQL that can be run or reviewed anywhere.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub folder: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub trigger: String,
+    pub body: String,
+}
+
+impl LibraryEntry {
+    pub fn parse(path: PathBuf, folder: String, contents: &str) -> Self {
+        let mut entry = LibraryEntry {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            folder,
+            ..Default::default()
+        };
+
+        let mut body_start = 0;
+        for line in contents.split_inclusive('\n') {
+            let Some((key, value)) = line
+                .trim_end()
+                .strip_prefix("--")
+                .and_then(|comment| comment.split_once(':'))
+            else {
+                break;
+            };
+            let value = value.trim().to_string();
+            match key.trim().to_lowercase().as_str() {
+                "name" => entry.name = value,
+                "description" => entry.description = value,
+                "tags" => {
+                    entry.tags = value
+                        .split(',')
+                        .map(|tag| tag.trim().to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect()
+                }
+                "trigger" => entry.trigger = value,
+                _ => break,
+            }
+            body_start += line.len();
+        }
+
+        entry.body = contents
+            .get(body_start..)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        entry
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let mut contents = format!("-- name: {}\n", self.name);
+        if !self.description.is_empty() {
+            contents += &format!("-- description: {}\n", self.description);
+        }
+        if !self.tags.is_empty() {
+            contents += &format!("-- tags: {}\n", self.tags.join(", "));
+        }
+        if !self.trigger.is_empty() {
+            contents += &format!("-- trigger: {}\n", self.trigger);
+        }
+        contents + &self.body + "\n"
+    }
+
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        filter.is_empty()
+            || self.name.to_lowercase().contains(&filter)
+            || self.description.to_lowercase().contains(&filter)
+            || self.folder.to_lowercase().contains(&filter)
+            || self.tags.iter().any(|tag| tag.to_lowercase() == filter)
+    }
+}
+
+pub fn default_library_dir() -> PathBuf {
+    eframe::storage_dir("rosemary")
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("queries")
+}
+
+/// Loads every `.sql` file below `dir`, using the relative directory as the folder.
+pub fn load_library(dir: &Path) -> io::Result<Vec<LibraryEntry>> {
+    let mut entries = Vec::new();
+    if dir.is_dir() {
+        load_dir(dir, dir, &mut entries)?;
+    }
+    entries.sort_by(|a, b| (&a.folder, &a.name).cmp(&(&b.folder, &b.name)));
+    Ok(entries)
+}
+
+fn load_dir(root: &Path, dir: &Path, entries: &mut Vec<LibraryEntry>) -> io::Result<()> {
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            load_dir(root, &path, entries)?;
+        } else if path.extension().is_some_and(|ext| ext == "sql") {
+            let folder = path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(root).ok())
+                .map(|folder| folder.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let contents = fs::read_to_string(&path)?;
+            entries.push(LibraryEntry::parse(path, folder, &contents));
+        }
+    }
+    Ok(())
+}
+
+/// Writes the entry to `<dir>/<folder>/<name>.sql`, creating folders as needed.
+///
+/// An existing file is only replaced when it is the entry's own file or
+/// `overwrite` is set, otherwise an `AlreadyExists` error is returned.
+pub fn save_entry(dir: &Path, entry: &LibraryEntry, overwrite: bool) -> io::Result<PathBuf> {
+    let file_name: String = entry
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if file_name.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Library entries need a name",
+        ));
+    }
+
+    let path = library_folder(dir, &entry.folder)?.join(format!("{file_name}.sql"));
+    if path.exists() && path != entry.path && !overwrite {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(&path, entry.to_file_contents())?;
+    Ok(path)
+}
+
+/// Resolves a folder typed by the user, which has to stay inside the library.
+fn library_folder(dir: &Path, folder: &str) -> io::Result<PathBuf> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Library folders must be relative and can't contain \"..\"",
+        )
+    };
+    if folder.starts_with(['/', '\\']) || Path::new(folder).has_root() {
+        return Err(invalid());
+    }
+    let mut path = dir.to_path_buf();
+    for part in folder.split(['/', '\\']).filter(|part| !part.is_empty()) {
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => path.push(name),
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(path)
+}
+
+/// Finds the snippet for a trigger, falling back to the only trigger starting with it.
+pub fn find_snippet<'a>(entries: &'a [LibraryEntry], trigger: &str) -> Option<&'a LibraryEntry> {
+    let with_trigger = || entries.iter().filter(|entry| !entry.trigger.is_empty());
+    with_trigger()
+        .find(|entry| entry.trigger == trigger)
+        .or_else(|| {
+            let mut candidates = with_trigger().filter(|entry| entry.trigger.starts_with(trigger));
+            match (candidates.next(), candidates.next()) {
+                (Some(entry), None) => Some(entry),
+                _ => None,
+            }
+        })
+}
+
+/// The "save to library" form, with tags kept as the comma separated text being edited.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryDraft {
+    pub name: String,
+    pub folder: String,
+    pub description: String,
+    pub tags: String,
+    pub trigger: String,
+}
+
+impl LibraryDraft {
+    pub fn to_entry(&self, body: &str) -> LibraryEntry {
+        LibraryEntry {
+            folder: self.folder.trim().to_string(),
+            name: self.name.trim().to_string(),
+            description: self.description.trim().to_string(),
+            tags: self
+                .tags
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            trigger: self.trigger.trim().to_string(),
+            body: body.trim().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rosemary-library-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(folder: &str, name: &str) -> LibraryEntry {
+        LibraryEntry {
+            folder: folder.to_string(),
+            name: name.to_string(),
+            body: String::from("select 1"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rejects_folders_outside_the_library() {
+        let dir = temp_library("outside");
+        for folder in ["..", "a/../..", "/etc", "\\tmp", "a/./b"] {
+            let error = save_entry(&dir, &entry(folder, "q"), false).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{folder}");
+        }
+        let path = save_entry(&dir, &entry("reports/monthly/", "q"), false).unwrap();
+        assert_eq!(path, dir.join("reports").join("monthly").join("q.sql"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_overwrites_the_entry_itself_or_when_asked() {
+        let dir = temp_library("overwrite");
+        let path = save_entry(&dir, &entry("", "q"), false).unwrap();
+
+        let error = save_entry(&dir, &entry("", "q"), false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        let mut loaded = load_library(&dir).unwrap().remove(0);
+        loaded.body = String::from("select 2");
+        save_entry(&dir, &loaded, false).unwrap();
+        save_entry(&dir, &entry("", "q"), true).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().ends_with("select 1\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{
+    query_functions::pg_schema::SearchObject,
+    sql::{qualified_name, quote_identifier},
+};
+
+/// Results listed at once.
+pub const MAX_RESULTS: usize = 50;
+
+fn is_word_start(chars: &[char], idx: usize) -> bool {
+    idx == 0
+        || matches!(chars[idx - 1], '_' | '.' | ' ' | '-')
+        || (chars[idx].is_uppercase() && chars[idx - 1].is_lowercase())
+}
+
+/// Scores `text` against a fuzzy `pattern`, whose characters all have to
+/// appear in order, ignoring case. Runs of characters and characters that
+/// start a word score higher. Returns the score and the indexes of the
+/// matched characters.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let first = *pattern.first()?;
+
+    let mut best: Option<(i32, Vec<usize>)> = None;
+    for start in (0..lower.len()).filter(|&idx| lower[idx] == first) {
+        let mut positions = vec![start];
+        for &c in &pattern[1..] {
+            let last = *positions.last().unwrap_or(&start);
+            // Keep a run going, otherwise jump to the next word that starts
+            // with the character, otherwise take its next appearance.
+            let next = if lower.get(last + 1) == Some(&c) {
+                Some(last + 1)
+            } else {
+                (last + 1..lower.len())
+                    .find(|&idx| lower[idx] == c && is_word_start(&chars, idx))
+                    .or_else(|| (last + 1..lower.len()).find(|&idx| lower[idx] == c))
+            };
+            match next {
+                Some(idx) => positions.push(idx),
+                None => break,
+            }
+        }
+        if positions.len() < pattern.len() {
+            continue;
+        }
+
+        let mut score = 0;
+        for (n, &idx) in positions.iter().enumerate() {
+            score += 1;
+            if is_word_start(&chars, idx) {
+                score += 6;
+            }
+            if n > 0 {
+                let gap = idx - positions[n - 1] - 1;
+                score += if gap == 0 { 8 } else { -(gap.min(3) as i32) };
+            }
+        }
+        if start == 0 {
+            score += 4;
+        }
+        if positions.len() == lower.len() {
+            score += 50;
+        }
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+/// Ties are listed with tables first and columns and indexes last.
+fn kind_rank(kind: &str) -> u8 {
+    match kind {
+        "table" => 0,
+        "view" | "materialized view" | "foreign table" => 1,
+        "function" | "procedure" | "aggregate" => 2,
+        "column" => 4,
+        "index" => 5,
+        _ => 3,
+    }
+}
+
+/// The name to insert into the editor. Columns are qualified by their table.
+pub fn qualified_object_name(object: &SearchObject) -> String {
+    match (&object.table, object.kind.as_str()) {
+        (Some(table), "column") => format!(
+            "{}.{}",
+            qualified_name(&object.schema, table),
+            quote_identifier(&object.name)
+        ),
+        _ => qualified_name(&object.schema, &object.name),
+    }
+}
+
+/// Matches an object on its name, or failing that on its schema and table
+/// too, e.g. `orders.cust` for a column.
+fn match_object(pattern: &str, object: &SearchObject) -> Option<(i32, Vec<usize>)> {
+    let name = fuzzy_match(pattern, &object.name);
+    let path = match &object.table {
+        Some(table) => format!("{}.{table}.{}", object.schema, object.name),
+        None => format!("{}.{}", object.schema, object.name),
+    };
+    let path = fuzzy_match(pattern, &path).map(|(score, _)| (score - 5, Vec::new()));
+    match (name, path) {
+        (Some(name), Some(path)) if path.0 > name.0 => Some(path),
+        (Some(name), _) => Some(name),
+        (None, path) => path,
+    }
+}
+
+/// The Ctrl+P search over the objects of every open connection.
+#[derive(Debug, Default)]
+pub struct ObjectSearch {
+    pub query: String,
+    /// Objects with the key of the connection they are in.
+    pub objects: Vec<(String, SearchObject)>,
+    /// Indexes into `objects` with the matched characters of the name, best first.
+    pub results: Vec<(usize, Vec<usize>)>,
+    pub selected: usize,
+    pub errors: Vec<String>,
+    /// Connections whose objects are still loading.
+    pub loading: usize,
+}
+
+impl ObjectSearch {
+    pub fn update_results(&mut self) {
+        let mut matches: Vec<(i32, usize, Vec<usize>)> = if self.query.trim().is_empty() {
+            Vec::new()
+        } else {
+            self.objects
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (_, object))| {
+                    match_object(&self.query, object)
+                        .map(|(score, positions)| (score, idx, positions))
+                })
+                .collect()
+        };
+        matches.sort_by_key(|(score, idx, _)| {
+            let object = &self.objects[*idx].1;
+            (-score, object.name.len(), kind_rank(&object.kind), *idx)
+        });
+        self.results = matches
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, idx, positions)| (idx, positions))
+            .collect();
+        self.selected = 0;
+    }
+
+    pub fn selected_object(&self) -> Option<&(String, SearchObject)> {
+        self.results
+            .get(self.selected)
+            .map(|(idx, _)| &self.objects[*idx])
+    }
+}
+
+/// An object the table tree expands to and scrolls into view.
+#[derive(Debug, Clone)]
+pub struct TreeReveal {
+    pub key: String,
+    pub object: SearchObject,
+}
+
+impl TreeReveal {
+    /// The table or view row that holds the object, for columns and indexes.
+    pub fn relation(&self) -> &str {
+        self.object.table.as_deref().unwrap_or(&self.object.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(kind: &str, table: Option<&str>, name: &str) -> SearchObject {
+        SearchObject {
+            kind: kind.to_string(),
+            schema: String::from("public"),
+            table: table.map(String::from),
+            name: name.to_string(),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert_eq!(
+            fuzzy_match("cuor", "customer_orders").unwrap().1,
+            [0, 1, 9, 10]
+        );
+        assert_eq!(fuzzy_match("CO", "customerOrders").unwrap().1, [0, 8]);
+        assert!(fuzzy_match("oc", "customer").is_none());
+        assert!(fuzzy_match("", "customer").is_none());
+    }
+
+    #[test]
+    fn prefers_runs_and_word_starts() {
+        let score = |text| fuzzy_match("ord", text).unwrap().0;
+        assert!(score("orders") > score("customer_orders"));
+        assert!(score("customer_orders") > score("old_records"));
+        assert!(score("ord") > score("orders"));
+    }
+
+    #[test]
+    fn ranks_objects_and_qualifies_names() {
+        let mut search = ObjectSearch {
+            query: String::from("orders.cust"),
+            objects: vec![
+                (String::from("a"), object("table", None, "customers")),
+                (
+                    String::from("a"),
+                    object("column", Some("orders"), "customer_id"),
+                ),
+            ],
+            ..Default::default()
+        };
+        search.update_results();
+        assert_eq!(search.results.len(), 1);
+        let (_, found) = search.selected_object().unwrap();
+        assert_eq!(qualified_object_name(found), "public.orders.customer_id");
+
+        search.query = String::from("cust");
+        search.update_results();
+        let names: Vec<&str> = search
+            .results
+            .iter()
+            .map(|(idx, _)| search.objects[*idx].1.name.as_str())
+            .collect();
+        assert_eq!(names, ["customers", "customer_id"]);
+        assert_eq!(
+            qualified_object_name(&object("index", Some("Orders"), "orders_pkey")),
+            "public.orders_pkey"
+        );
+    }
+}
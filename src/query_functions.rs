@@ -1,2 +1,8 @@
+pub mod pg_compare;
+pub mod pg_connection;
 pub mod pg_data;
+pub mod pg_ddl;
+pub mod pg_params;
 pub mod pg_query_handlers;
+pub mod pg_schema;
+pub mod pg_session;
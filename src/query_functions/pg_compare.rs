@@ -0,0 +1,388 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Column, Executor, Pool, Postgres};
+
+use super::pg_schema::get_object_definition;
+
+/// An object compared by the schema diff, identified by its kind, schema,
+/// table (for columns, constraints and indexes) and name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotObject {
+    /// e.g. `table`, `constraint`, `materialized view`, `enum` or `grant`.
+    pub kind: String,
+    pub schema: String,
+    /// The table of a column, constraint or index, or the object a grant is on
+    /// (e.g. `TABLE public.t`). Empty for other objects.
+    pub table: String,
+    /// Functions include their arguments and grants are named by grantee,
+    /// both already quoted.
+    pub name: String,
+    pub definition: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotColumn {
+    pub schema: String,
+    pub table: String,
+    pub name: String,
+    pub data_type: String,
+    /// Only set when it isn't the type's default collation.
+    pub collation: Option<String>,
+    pub default_value: Option<String>,
+    pub not_null: bool,
+    /// `a` for `GENERATED ALWAYS`, `d` for `BY DEFAULT`, otherwise empty.
+    pub identity: String,
+    /// Expression of a generated column, otherwise empty.
+    pub generated: String,
+}
+
+/// The objects of every schema except the system ones, leaving out objects
+/// that belong to extensions.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaSnapshot {
+    pub objects: Vec<SnapshotObject>,
+    pub columns: Vec<SnapshotColumn>,
+}
+
+async fn get_snapshot_objects(db: &Pool<Postgres>) -> Result<Vec<SnapshotObject>, sqlx::Error> {
+    sqlx::query_as!(
+        SnapshotObject,
+        r#"
+        WITH schemas AS (
+          SELECT n.oid, n.nspname::text AS nspname
+          FROM pg_namespace n
+          WHERE
+            n.nspname NOT LIKE 'pg\_%'
+            AND n.nspname <> 'information_schema'
+            AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_namespace'::regclass AND d.objid = n.oid AND d.deptype = 'e'
+            )
+        ),
+        relations AS (
+          SELECT c.oid, c.relname::text AS relname, c.relkind, c.relispartition, c.relpartbound, s.nspname
+          FROM pg_class c
+          JOIN schemas s ON s.oid = c.relnamespace
+          WHERE NOT EXISTS (
+            SELECT 1 FROM pg_depend d
+            WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype = 'e'
+          )
+        )
+        SELECT
+          kind AS "kind!",
+          schema AS "schema!",
+          "table" AS "table!",
+          name AS "name!",
+          definition AS "definition!"
+        FROM (
+          SELECT 'schema' AS kind, s.nspname AS schema, '' AS "table", s.nspname AS name, '' AS definition
+          FROM schemas s
+          UNION ALL
+          SELECT
+            'table',
+            r.nspname,
+            '',
+            r.relname,
+            CASE
+              WHEN r.relispartition THEN (
+                SELECT 'PARTITION OF ' || i.inhparent::regclass::text || ' ' || pg_get_expr(r.relpartbound, r.oid)
+                FROM pg_inherits i
+                WHERE i.inhrelid = r.oid
+              )
+              ELSE ''
+            END || coalesce(' PARTITION BY ' || pg_get_partkeydef(r.oid), '')
+          FROM relations r
+          WHERE r.relkind IN ('r', 'p')
+          UNION ALL
+          SELECT 'constraint', r.nspname, r.relname, con.conname::text, pg_get_constraintdef(con.oid)
+          FROM pg_constraint con
+          JOIN relations r ON r.oid = con.conrelid
+          WHERE con.conislocal AND con.conparentid = 0 AND con.contype IN ('p', 'u', 'f', 'c', 'x')
+          UNION ALL
+          SELECT 'index', r.nspname, r.relname, i.relname::text, pg_get_indexdef(x.indexrelid)
+          FROM pg_index x
+          JOIN relations r ON r.oid = x.indrelid
+          JOIN pg_class i ON i.oid = x.indexrelid
+          WHERE
+            NOT i.relispartition
+            AND NOT EXISTS (
+              SELECT 1 FROM pg_constraint con
+              WHERE con.conrelid = x.indrelid AND con.conindid = x.indexrelid AND con.contype IN ('p', 'u', 'x')
+            )
+          UNION ALL
+          SELECT
+            CASE r.relkind WHEN 'v' THEN 'view' ELSE 'materialized view' END,
+            r.nspname,
+            '',
+            r.relname,
+            pg_get_viewdef(r.oid, true)
+          FROM relations r
+          WHERE r.relkind IN ('v', 'm')
+          UNION ALL
+          SELECT
+            'sequence',
+            r.nspname,
+            '',
+            r.relname,
+            format(
+              'AS %s INCREMENT BY %s MINVALUE %s MAXVALUE %s START WITH %s CACHE %s %s',
+              format_type(s.seqtypid, NULL),
+              s.seqincrement,
+              s.seqmin,
+              s.seqmax,
+              s.seqstart,
+              s.seqcache,
+              CASE WHEN s.seqcycle THEN 'CYCLE' ELSE 'NO CYCLE' END
+            )
+          FROM relations r
+          JOIN pg_sequence s ON s.seqrelid = r.oid
+          -- Sequences of identity columns come with the column.
+          WHERE NOT EXISTS (
+            SELECT 1 FROM pg_depend d
+            WHERE d.classid = 'pg_class'::regclass AND d.objid = r.oid AND d.deptype = 'i'
+          )
+          UNION ALL
+          SELECT
+            CASE p.prokind WHEN 'p' THEN 'procedure' ELSE 'function' END,
+            s.nspname,
+            '',
+            quote_ident(p.proname) || '(' || pg_get_function_identity_arguments(p.oid) || ')',
+            pg_get_functiondef(p.oid)
+          FROM pg_proc p
+          JOIN schemas s ON s.oid = p.pronamespace
+          WHERE
+            p.prokind IN ('f', 'p')
+            AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+            )
+        ) objects
+        ORDER BY
+          kind,
+          schema,
+          "table",
+          name;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_snapshot_types(db: &Pool<Postgres>) -> Result<Vec<SnapshotObject>, sqlx::Error> {
+    let types = sqlx::query!(
+        r#"
+        SELECT
+          t.oid AS "oid!",
+          CASE t.typtype
+            WHEN 'e' THEN 'enum'
+            WHEN 'd' THEN 'domain'
+            WHEN 'r' THEN 'range'
+            ELSE 'composite'
+          END AS "kind!",
+          n.nspname::text AS "schema!",
+          t.typname::text AS "name!"
+        FROM
+          pg_type t
+          JOIN pg_namespace n ON n.oid = t.typnamespace
+          LEFT JOIN pg_class c ON c.oid = t.typrelid
+        WHERE
+          n.nspname NOT LIKE 'pg\_%'
+          AND n.nspname <> 'information_schema'
+          AND (t.typtype IN ('e', 'd', 'r') OR (t.typtype = 'c' AND c.relkind = 'c'))
+          AND NOT EXISTS (
+            SELECT 1 FROM pg_depend d
+            WHERE d.classid = 'pg_type'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+          )
+        ORDER BY
+          n.nspname,
+          t.typname;
+        "#
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut objects = Vec::new();
+    for data_type in types {
+        objects.push(SnapshotObject {
+            definition: get_object_definition(db, &data_type.kind, data_type.oid).await?,
+            kind: data_type.kind,
+            schema: data_type.schema,
+            table: String::new(),
+            name: data_type.name,
+        });
+    }
+    Ok(objects)
+}
+
+/// Privileges granted to roles other than the object's owner, one row per
+/// object and grantee.
+async fn get_snapshot_grants(db: &Pool<Postgres>) -> Result<Vec<SnapshotObject>, sqlx::Error> {
+    sqlx::query_as!(
+        SnapshotObject,
+        r#"
+        WITH schemas AS (
+          SELECT n.oid, n.nspname::text AS nspname, n.nspowner, n.nspacl
+          FROM pg_namespace n
+          WHERE n.nspname NOT LIKE 'pg\_%' AND n.nspname <> 'information_schema'
+        ),
+        privileges AS (
+          SELECT s.nspname AS schema, 'SCHEMA ' || quote_ident(s.nspname) AS object, s.nspowner AS owner, a.*
+          FROM schemas s, aclexplode(coalesce(s.nspacl, acldefault('n', s.nspowner))) a
+          UNION ALL
+          SELECT
+            s.nspname,
+            CASE c.relkind WHEN 'S' THEN 'SEQUENCE ' ELSE 'TABLE ' END
+              || quote_ident(s.nspname) || '.' || quote_ident(c.relname),
+            c.relowner,
+            a.*
+          FROM
+            pg_class c
+            JOIN schemas s ON s.oid = c.relnamespace,
+            aclexplode(coalesce(c.relacl, acldefault(CASE WHEN c.relkind = 'S' THEN 's' ELSE 'r' END::"char", c.relowner))) a
+          WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+          UNION ALL
+          SELECT
+            s.nspname,
+            CASE p.prokind WHEN 'p' THEN 'PROCEDURE ' ELSE 'FUNCTION ' END
+              || quote_ident(s.nspname) || '.' || quote_ident(p.proname)
+              || '(' || pg_get_function_identity_arguments(p.oid) || ')',
+            p.proowner,
+            a.*
+          FROM
+            pg_proc p
+            JOIN schemas s ON s.oid = p.pronamespace,
+            aclexplode(coalesce(p.proacl, acldefault('f', p.proowner))) a
+          WHERE p.prokind IN ('f', 'p')
+        )
+        SELECT
+          'grant' AS "kind!",
+          p.schema AS "schema!",
+          p.object AS "table!",
+          CASE WHEN p.grantee = 0 THEN 'PUBLIC' ELSE quote_ident(pg_get_userbyid(p.grantee)) END AS "name!",
+          string_agg(
+            p.privilege_type || CASE WHEN p.is_grantable THEN ' WITH GRANT OPTION' ELSE '' END,
+            ', ' ORDER BY p.privilege_type
+          ) AS "definition!"
+        FROM
+          privileges p
+        WHERE
+          p.grantee <> p.owner
+        GROUP BY
+          p.schema,
+          p.object,
+          p.grantee
+        ORDER BY
+          p.schema,
+          p.object,
+          2;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_snapshot_columns(db: &Pool<Postgres>) -> Result<Vec<SnapshotColumn>, sqlx::Error> {
+    sqlx::query_as!(
+        SnapshotColumn,
+        r#"
+        SELECT
+          n.nspname::text AS "schema!",
+          c.relname::text AS "table!",
+          a.attname::text AS "name!",
+          format_type(a.atttypid, a.atttypmod) AS "data_type!",
+          CASE WHEN a.attcollation <> t.typcollation THEN a.attcollation::regcollation::text END AS collation,
+          CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END AS default_value,
+          a.attnotnull AS "not_null!",
+          a.attidentity::text AS "identity!",
+          CASE WHEN a.attgenerated = 's' THEN pg_get_expr(d.adbin, d.adrelid) ELSE '' END AS "generated!"
+        FROM
+          pg_attribute a
+          JOIN pg_class c ON c.oid = a.attrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          JOIN pg_type t ON t.oid = a.atttypid
+          LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE
+          n.nspname NOT LIKE 'pg\_%'
+          AND n.nspname <> 'information_schema'
+          AND c.relkind IN ('r', 'p')
+          AND NOT c.relispartition
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+          AND a.attislocal
+          AND NOT EXISTS (
+            SELECT 1 FROM pg_depend dep
+            WHERE dep.classid = 'pg_class'::regclass AND dep.objid = c.oid AND dep.deptype = 'e'
+          )
+        ORDER BY
+          n.nspname,
+          c.relname,
+          a.attnum;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_schema_snapshot(db: &Pool<Postgres>) -> Result<SchemaSnapshot, sqlx::Error> {
+    let mut objects = get_snapshot_objects(db).await?;
+    objects.extend(get_snapshot_types(db).await?);
+    objects.extend(get_snapshot_grants(db).await?);
+    let columns = get_snapshot_columns(db).await?;
+    Ok(SchemaSnapshot { objects, columns })
+}
+
+/// Name and type of each column a query returns, without running it.
+pub async fn get_query_columns(
+    db: &Pool<Postgres>,
+    sql: &str,
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    let describe = db.describe(sql).await?;
+    let oids: Vec<i64> = describe
+        .columns()
+        .iter()
+        .map(|column| column.type_info().oid().map_or(0, |oid| i64::from(oid.0)))
+        .collect();
+    let types = sqlx::query_scalar!(
+        r#"
+        SELECT format_type(t::oid, NULL) AS "data_type!"
+        FROM unnest($1::int8[]) WITH ORDINALITY AS u(t, n)
+        ORDER BY n
+        "#,
+        &oids
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(describe
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .zip(types)
+        .collect())
+}
+
+/// The primary key columns of a table, in key order. Empty when it has none.
+pub async fn get_primary_key(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT a.attname::text AS "name!"
+        FROM
+          pg_index i
+          JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, n) ON true
+          JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+          JOIN pg_class c ON c.oid = i.indrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+          AND i.indisprimary
+        ORDER BY k.n
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
@@ -0,0 +1,270 @@
+use std::{io, str::FromStr, time::Duration};
+
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+    Connection, PgConnection, Pool, Postgres,
+};
+
+use crate::{
+    app::SavedConnection,
+    connection_string::{host_port_pairs, parse_options},
+    query_functions::pg_session::apply_session_settings,
+};
+
+const APPLICATION_NAME: &str = "Rosemary";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetSessionAttrs {
+    Any,
+    ReadWrite,
+    ReadOnly,
+    Primary,
+    Standby,
+    PreferStandby,
+}
+
+impl FromStr for TargetSessionAttrs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" | "any" => Ok(TargetSessionAttrs::Any),
+            "read-write" => Ok(TargetSessionAttrs::ReadWrite),
+            "read-only" => Ok(TargetSessionAttrs::ReadOnly),
+            "primary" => Ok(TargetSessionAttrs::Primary),
+            "standby" => Ok(TargetSessionAttrs::Standby),
+            "prefer-standby" => Ok(TargetSessionAttrs::PreferStandby),
+            _ => Err(format!("Invalid target_session_attrs \"{s}\"")),
+        }
+    }
+}
+
+/// Builds the options for one host of a connection, applying the libpq
+/// parameters stored on it.
+pub fn connect_options(
+    connection: &SavedConnection,
+    host: &str,
+    port: u16,
+    database: &str,
+    password: &str,
+) -> Result<PgConnectOptions, String> {
+    let mut options = PgConnectOptions::new_without_pgpass()
+        .host(host)
+        .port(port)
+        .username(&connection.db_user)
+        .password(password)
+        .application_name(APPLICATION_NAME);
+    if !database.is_empty() {
+        options = options.database(database);
+    }
+    if connection.read_only {
+        options = options.options([("default_transaction_read_only", "on")]);
+    }
+
+    for (key, value) in &connection.params {
+        match key.as_str() {
+            "sslmode" => {
+                let mode = PgSslMode::from_str(value)
+                    .map_err(|_| format!("Invalid sslmode \"{value}\""))?;
+                options = options.ssl_mode(mode);
+            }
+            "sslrootcert" => options = options.ssl_root_cert(value),
+            "sslcert" => options = options.ssl_client_cert(value),
+            "sslkey" => options = options.ssl_client_key(value),
+            "options" => options = options.options(parse_options(value)?),
+            "application_name" => options = options.application_name(value),
+            // Used when connecting, and anything else is reported on import.
+            _ => {}
+        }
+    }
+    let application_name = connection.session.application_name.trim();
+    if !application_name.is_empty() {
+        options = options.application_name(application_name);
+    }
+
+    Ok(options)
+}
+
+fn connect_timeout(connection: &SavedConnection) -> Result<Option<Duration>, String> {
+    match connection.params.get("connect_timeout") {
+        Some(value) => {
+            let seconds = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid connect_timeout \"{value}\""))?;
+            // Like libpq, zero or a negative value means wait indefinitely and
+            // anything below two seconds is rounded up.
+            Ok((seconds > 0).then(|| Duration::from_secs(seconds.max(2) as u64)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Describes a connection error by the stage that failed: name resolution,
+/// TCP, TLS, authentication or the database lookup.
+pub fn describe_connect_error(error: &sqlx::Error) -> String {
+    match error {
+        sqlx::Error::Io(e) if e.kind() == io::ErrorKind::ConnectionRefused => String::from(
+            "Connection refused: is the server running and accepting TCP connections on this port?",
+        ),
+        sqlx::Error::Io(e) if e.kind() == io::ErrorKind::TimedOut => {
+            String::from("Timed out connecting to the server")
+        }
+        sqlx::Error::Io(e) if e.to_string().contains("could not resolve address") => {
+            format!("DNS lookup failed: {e}")
+        }
+        sqlx::Error::Io(e) => format!("Network error: {e}"),
+        sqlx::Error::Tls(e) => format!("TLS error: {e}"),
+        sqlx::Error::Database(e) => match e.code().as_deref() {
+            Some("28P01") | Some("28000") => format!("Authentication failed: {}", e.message()),
+            Some("3D000") => format!("Database not found: {}", e.message()),
+            _ => format!("Server error: {}", e.message()),
+        },
+        sqlx::Error::PoolTimedOut => String::from("Timed out waiting for a connection"),
+        e => e.to_string(),
+    }
+}
+
+async fn open_session(
+    options: &PgConnectOptions,
+    timeout: Option<Duration>,
+) -> Result<PgConnection, String> {
+    let connecting = PgConnection::connect_with(options);
+    let result = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connecting)
+            .await
+            .map_err(|_| format!("Timed out after {} seconds", timeout.as_secs()))?,
+        None => connecting.await,
+    };
+    result.map_err(|e| describe_connect_error(&e))
+}
+
+async fn session_matches(
+    conn: &mut PgConnection,
+    target: TargetSessionAttrs,
+) -> Result<bool, String> {
+    if target == TargetSessionAttrs::Any {
+        return Ok(true);
+    }
+    let (in_recovery, read_only): (bool, bool) = sqlx::query_as(
+        "SELECT pg_is_in_recovery(), current_setting('transaction_read_only') = 'on'",
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(match target {
+        TargetSessionAttrs::Any => true,
+        TargetSessionAttrs::ReadWrite => !in_recovery && !read_only,
+        TargetSessionAttrs::ReadOnly => in_recovery || read_only,
+        TargetSessionAttrs::Primary => !in_recovery,
+        TargetSessionAttrs::Standby | TargetSessionAttrs::PreferStandby => in_recovery,
+    })
+}
+
+/// Opens a single session on the first of the connection's hosts that accepts
+/// it and matches `target_session_attrs`, trying them in order. Connecting
+/// directly rather than through a pool reports why a host failed instead of a
+/// pool timeout.
+async fn find_host(
+    connection: &SavedConnection,
+    database: &str,
+    password: &str,
+) -> Result<(PgConnectOptions, PgConnection), String> {
+    let hosts = host_port_pairs(&connection.db_host, &connection.db_port)?;
+    let target: TargetSessionAttrs = connection
+        .params
+        .get("target_session_attrs")
+        .map_or(Ok(TargetSessionAttrs::Any), |value| value.parse())?;
+    let timeout = connect_timeout(connection)?;
+
+    let mut errors = Vec::new();
+    // prefer-standby falls back to any host when no standby is available.
+    let mut passes = vec![target];
+    if target == TargetSessionAttrs::PreferStandby {
+        passes.push(TargetSessionAttrs::Any);
+    }
+
+    for pass in passes {
+        for (host, port) in &hosts {
+            let options = connect_options(connection, host, *port, database, password)?;
+            match open_session(&options, timeout).await {
+                Ok(mut conn) => match session_matches(&mut conn, pass).await {
+                    Ok(true) => {
+                        // Checked here so a bad setting is reported rather than
+                        // failing every pooled connection.
+                        return match apply_session_settings(&mut conn, &connection.session).await {
+                            Ok(()) => Ok((options, conn)),
+                            Err(e) => {
+                                let _ = conn.close().await;
+                                Err(format!(
+                                    "Failed to apply the session settings: {}",
+                                    describe_connect_error(&e)
+                                ))
+                            }
+                        };
+                    }
+                    Ok(false) => {
+                        errors.push(format!(
+                            "{host}:{port}: server does not match target_session_attrs"
+                        ));
+                        let _ = conn.close().await;
+                    }
+                    Err(e) => errors.push(format!("{host}:{port}: {e}")),
+                },
+                Err(e) => errors.push(format!("{host}:{port}: {e}")),
+            }
+        }
+    }
+
+    Err(errors.join("\n"))
+}
+
+pub async fn connect(
+    connection: &SavedConnection,
+    database: &str,
+    password: &str,
+) -> Result<Pool<Postgres>, String> {
+    let (options, conn) = find_host(connection, database, password).await?;
+    let _ = conn.close().await;
+
+    let settings = connection.session.clone();
+    let mut pool_options = PgPoolOptions::new().after_connect(move |conn, _| {
+        let settings = settings.clone();
+        Box::pin(async move { apply_session_settings(conn, &settings).await })
+    });
+    if let Some(timeout) = connect_timeout(connection)? {
+        pool_options = pool_options.acquire_timeout(timeout);
+    }
+    pool_options
+        .connect_with(options)
+        .await
+        .map_err(|e| describe_connect_error(&e))
+}
+
+/// Connects without keeping the session and describes the server.
+pub async fn test_connection(
+    connection: &SavedConnection,
+    database: &str,
+    password: &str,
+) -> Result<String, String> {
+    let (options, mut conn) = find_host(connection, database, password).await?;
+    let (version, encrypted): (String, Option<bool>) = sqlx::query_as(
+        "SELECT version(), (SELECT ssl FROM pg_stat_ssl WHERE pid = pg_backend_pid())",
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    let _ = conn.close().await;
+
+    let encryption = if encrypted == Some(true) {
+        "encrypted"
+    } else {
+        "not encrypted"
+    };
+    Ok(format!(
+        "Connected to {}:{} ({encryption})\n{version}",
+        options.get_host(),
+        options.get_port()
+    ))
+}
@@ -1,7 +1,8 @@
-use std::net::Ipv4Addr;
-
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::types::Oid, PgConnection, Pool, Postgres};
+use sqlx::{postgres::types::Oid, PgConnection, Pool, Postgres, Row};
+
+use crate::sql::qualified_name;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PublicTable {
@@ -109,3 +110,362 @@ pub async fn get_running_queries_data(
     .fetch_all(db)
     .await
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SslStatus {
+    pub ssl: Option<bool>,
+    pub version: Option<String>,
+    pub cipher: Option<String>,
+    pub bits: Option<i32>,
+}
+
+pub async fn get_ssl_status(db: &Pool<Postgres>) -> Result<SslStatus, sqlx::Error> {
+    sqlx::query_as!(
+        SslStatus,
+        "
+        SELECT
+          ssl,
+          version,
+          cipher,
+          bits
+        FROM
+          pg_stat_ssl
+        WHERE
+          pid = pg_backend_pid();
+        "
+    )
+    .fetch_one(db)
+    .await
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableSize {
+    pub row_count: i64,
+    /// Table, indexes and TOAST, formatted by `pg_size_pretty`.
+    pub total_size: String,
+}
+
+/// Counts the rows of a table, which reads all of it.
+pub async fn get_table_size(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<TableSize, sqlx::Error> {
+    let count = format!("SELECT count(*) FROM {}", qualified_name(schema, table));
+    let row_count: i64 = sqlx::query_scalar(&count).fetch_one(db).await?;
+    let total_size = sqlx::query_scalar!(
+        r#"
+        SELECT
+          pg_size_pretty(pg_total_relation_size(format('%I.%I', $1::text, $2::text)::regclass)) AS "size!"
+        "#,
+        schema,
+        table
+    )
+    .fetch_one(db)
+    .await?;
+    Ok(TableSize {
+        row_count,
+        total_size,
+    })
+}
+
+/// Sizes from `pg_class` and activity from `pg_stat_user_tables`, which has
+/// nothing for views.
+#[derive(Debug, Clone)]
+pub struct TableStats {
+    pub total_size: String,
+    /// The table itself, without its indexes and TOAST.
+    pub table_size: String,
+    pub index_size: String,
+    pub toast_size: String,
+    /// `pg_class.reltuples`, unknown until the table is first vacuumed or analyzed.
+    pub estimated_rows: Option<i64>,
+    pub live_tuples: Option<i64>,
+    pub dead_tuples: Option<i64>,
+    pub modified_since_analyze: Option<i64>,
+    pub seq_scans: Option<i64>,
+    pub seq_tuples_read: Option<i64>,
+    pub index_scans: Option<i64>,
+    pub index_tuples_fetched: Option<i64>,
+    pub last_vacuum: Option<DateTime<Utc>>,
+    pub last_autovacuum: Option<DateTime<Utc>>,
+    pub last_analyze: Option<DateTime<Utc>>,
+    pub last_autoanalyze: Option<DateTime<Utc>>,
+}
+
+pub async fn get_table_stats(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<TableStats, sqlx::Error> {
+    sqlx::query_as!(
+        TableStats,
+        r#"
+        SELECT
+          pg_size_pretty(pg_total_relation_size(c.oid)) AS "total_size!",
+          pg_size_pretty(
+            pg_table_size(c.oid) - COALESCE(pg_total_relation_size(NULLIF(c.reltoastrelid, 0)), 0)
+          ) AS "table_size!",
+          pg_size_pretty(pg_indexes_size(c.oid)) AS "index_size!",
+          pg_size_pretty(COALESCE(pg_total_relation_size(NULLIF(c.reltoastrelid, 0)), 0)) AS "toast_size!",
+          CASE WHEN c.reltuples < 0 THEN NULL ELSE c.reltuples::int8 END AS estimated_rows,
+          s.n_live_tup AS "live_tuples?",
+          s.n_dead_tup AS "dead_tuples?",
+          s.n_mod_since_analyze AS "modified_since_analyze?",
+          s.seq_scan AS "seq_scans?",
+          s.seq_tup_read AS "seq_tuples_read?",
+          s.idx_scan AS "index_scans?",
+          s.idx_tup_fetch AS "index_tuples_fetched?",
+          s.last_vacuum AS "last_vacuum?",
+          s.last_autovacuum AS "last_autovacuum?",
+          s.last_analyze AS "last_analyze?",
+          s.last_autoanalyze AS "last_autoanalyze?"
+        FROM
+          pg_class c
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          LEFT JOIN pg_stat_user_tables s ON s.relid = c.oid
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+        "#,
+        schema,
+        table
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// An index of a table with its size and `pg_stat_user_indexes` usage.
+#[derive(Debug, Clone)]
+pub struct IndexStats {
+    pub name: String,
+    pub size: String,
+    pub is_primary: bool,
+    pub is_unique: bool,
+    pub scans: Option<i64>,
+    pub tuples_read: Option<i64>,
+    pub tuples_fetched: Option<i64>,
+}
+
+pub async fn get_index_stats(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<IndexStats>, sqlx::Error> {
+    sqlx::query_as!(
+        IndexStats,
+        r#"
+        SELECT
+          ic.relname::text AS "name!",
+          pg_size_pretty(pg_relation_size(i.indexrelid)) AS "size!",
+          i.indisprimary AS "is_primary!",
+          i.indisunique AS "is_unique!",
+          s.idx_scan AS "scans?",
+          s.idx_tup_read AS "tuples_read?",
+          s.idx_tup_fetch AS "tuples_fetched?"
+        FROM
+          pg_index i
+          JOIN pg_class ic ON ic.oid = i.indexrelid
+          JOIN pg_class c ON c.oid = i.indrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          LEFT JOIN pg_stat_user_indexes s ON s.indexrelid = i.indexrelid
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+        ORDER BY
+          ic.relname
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// The planner's row estimate and total size of a table, for the table list.
+#[derive(Debug, Clone)]
+pub struct TableEstimate {
+    pub schema: String,
+    pub table: String,
+    pub estimated_rows: Option<i64>,
+    pub total_size: String,
+}
+
+/// Estimates for every table and materialized view outside the system schemas,
+/// without reading any of them.
+pub async fn get_table_estimates(db: &Pool<Postgres>) -> Result<Vec<TableEstimate>, sqlx::Error> {
+    sqlx::query_as!(
+        TableEstimate,
+        r#"
+        SELECT
+          n.nspname::text AS "schema!",
+          c.relname::text AS "table!",
+          CASE WHEN c.reltuples < 0 THEN NULL ELSE c.reltuples::int8 END AS estimated_rows,
+          pg_size_pretty(pg_total_relation_size(c.oid)) AS "total_size!"
+        FROM
+          pg_class c
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          c.relkind IN ('r', 'p', 'm')
+          AND n.nspname NOT LIKE 'pg\_%'
+          AND n.nspname <> 'information_schema'
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Runs a query whose columns are all cast to text.
+pub async fn fetch_text_rows(
+    db: &Pool<Postgres>,
+    sql: &str,
+) -> Result<Vec<Vec<Option<String>>>, sqlx::Error> {
+    let rows = sqlx::query(sql).fetch_all(db).await?;
+    rows.iter()
+        .map(|row| (0..row.len()).map(|idx| row.try_get(idx)).collect())
+        .collect()
+}
+
+/// Like `fetch_text_rows`, with parameters bound as text.
+pub async fn fetch_text_rows_with_params(
+    db: &Pool<Postgres>,
+    sql: &str,
+    params: &[Option<String>],
+) -> Result<Vec<Vec<Option<String>>>, sqlx::Error> {
+    let mut query = sqlx::query(sql);
+    for param in params {
+        query = query.bind(param);
+    }
+    let rows = query.fetch_all(db).await?;
+    rows.iter()
+        .map(|row| (0..row.len()).map(|idx| row.try_get(idx)).collect())
+        .collect()
+}
+
+/// Runs row changes in one transaction. Each statement has to change exactly
+/// one row, otherwise everything is rolled back.
+pub async fn apply_row_changes(
+    db: &Pool<Postgres>,
+    changes: Vec<(String, Vec<Option<String>>)>,
+) -> Result<usize, String> {
+    let mut transaction = db.begin().await.map_err(|e| e.to_string())?;
+    for (sql, params) in &changes {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+        let result = query
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("{e}\n{sql}"))?;
+        if result.rows_affected() != 1 {
+            return Err(format!(
+                "{} rows would have been changed by\n{sql}\nNothing was saved.",
+                result.rows_affected()
+            ));
+        }
+    }
+    transaction.commit().await.map_err(|e| e.to_string())?;
+    Ok(changes.len())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiagramColumn {
+    pub table_name: String,
+    pub column_name: String,
+    pub data_type: String,
+    pub primary_key: bool,
+}
+
+/// Columns of the tables in a schema, in table and column order.
+pub async fn get_diagram_columns(
+    db: &Pool<Postgres>,
+    schema: &str,
+) -> Result<Vec<DiagramColumn>, sqlx::Error> {
+    sqlx::query_as!(
+        DiagramColumn,
+        r#"
+        SELECT
+          c.relname AS "table_name!",
+          a.attname AS "column_name!",
+          format_type(a.atttypid, a.atttypmod) AS "data_type!",
+          EXISTS (
+            SELECT 1
+            FROM pg_index i
+            WHERE i.indrelid = c.oid AND i.indisprimary AND a.attnum = ANY(i.indkey)
+          ) AS "primary_key!"
+        FROM
+          pg_class c
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          JOIN pg_attribute a ON a.attrelid = c.oid
+        WHERE
+          n.nspname = $1
+          AND c.relkind IN ('r', 'p')
+          AND NOT c.relispartition
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+        ORDER BY
+          c.relname,
+          a.attnum;
+        "#,
+        schema
+    )
+    .fetch_all(db)
+    .await
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiagramForeignKey {
+    pub name: String,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub foreign_schema: String,
+    pub foreign_table: String,
+    pub foreign_columns: Vec<String>,
+}
+
+/// Foreign keys of the tables in a schema, including ones that reference
+/// tables in other schemas.
+pub async fn get_diagram_foreign_keys(
+    db: &Pool<Postgres>,
+    schema: &str,
+) -> Result<Vec<DiagramForeignKey>, sqlx::Error> {
+    sqlx::query_as!(
+        DiagramForeignKey,
+        r#"
+        SELECT
+          con.conname AS "name!",
+          c.relname AS "table_name!",
+          ARRAY(
+            SELECT a.attname
+            FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+            ORDER BY k.ord
+          )::text[] AS "columns!",
+          fn.nspname AS "foreign_schema!",
+          fc.relname AS "foreign_table!",
+          ARRAY(
+            SELECT a.attname
+            FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+            ORDER BY k.ord
+          )::text[] AS "foreign_columns!"
+        FROM
+          pg_constraint con
+          JOIN pg_class c ON c.oid = con.conrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          JOIN pg_class fc ON fc.oid = con.confrelid
+          JOIN pg_namespace fn ON fn.oid = fc.relnamespace
+        WHERE
+          con.contype = 'f'
+          AND n.nspname = $1
+          AND con.conparentid = 0
+        ORDER BY
+          c.relname,
+          con.conname;
+        "#,
+        schema
+    )
+    .fetch_all(db)
+    .await
+}
@@ -0,0 +1,670 @@
+use sqlx::{postgres::types::Oid, Pool, Postgres};
+
+use super::{pg_query_handlers::format_sql, pg_schema::get_object_definition};
+
+/// Where an object kind's comment, owner and privileges are stored.
+struct CatalogEntry {
+    /// The keyword used in `COMMENT ON`, `ALTER ... OWNER TO` and `GRANT ... ON`.
+    keyword: &'static str,
+    catalog: &'static str,
+    /// Expression giving the object's name as it is written in those statements.
+    name: &'static str,
+    owner: Option<&'static str>,
+    /// The ACL column and the object type passed to `acldefault`.
+    acl: Option<(&'static str, char)>,
+}
+
+fn catalog_entry(kind: &str) -> Option<CatalogEntry> {
+    let relation = |keyword, acl_type| CatalogEntry {
+        keyword,
+        catalog: "pg_class",
+        name: "oid::regclass::text",
+        owner: Some("relowner"),
+        acl: Some(("relacl", acl_type)),
+    };
+    let routine = |keyword| CatalogEntry {
+        keyword,
+        catalog: "pg_proc",
+        name: "oid::regprocedure::text",
+        owner: Some("proowner"),
+        acl: Some(("proacl", 'f')),
+    };
+    let data_type = |keyword| CatalogEntry {
+        keyword,
+        catalog: "pg_type",
+        name: "oid::regtype::text",
+        owner: Some("typowner"),
+        acl: Some(("typacl", 'T')),
+    };
+
+    let entry = match kind {
+        "table" => relation("TABLE", 'r'),
+        "view" => relation("VIEW", 'r'),
+        "materialized view" => relation("MATERIALIZED VIEW", 'r'),
+        "foreign table" => relation("FOREIGN TABLE", 'r'),
+        "sequence" => relation("SEQUENCE", 's'),
+        "index" => CatalogEntry {
+            owner: None,
+            acl: None,
+            ..relation("INDEX", 'r')
+        },
+        "function" => routine("FUNCTION"),
+        "procedure" => routine("PROCEDURE"),
+        "aggregate" => routine("AGGREGATE"),
+        "enum" | "composite" | "range" => data_type("TYPE"),
+        "domain" => data_type("DOMAIN"),
+        "trigger" => CatalogEntry {
+            keyword: "TRIGGER",
+            catalog: "pg_trigger",
+            name: "quote_ident(tgname) || ' ON ' || tgrelid::regclass::text",
+            owner: None,
+            acl: None,
+        },
+        "extension" => CatalogEntry {
+            keyword: "EXTENSION",
+            catalog: "pg_extension",
+            name: "quote_ident(extname)",
+            owner: None,
+            acl: None,
+        },
+        _ => return None,
+    };
+    Some(entry)
+}
+
+/// Builds the full `CREATE` script for an object: its definition followed by
+/// its comments, owner and privileges. Each statement is run through
+/// `format_sql`, except function bodies which are kept as the server wrote them.
+pub async fn get_object_ddl(
+    db: &Pool<Postgres>,
+    kind: &str,
+    oid: Oid,
+) -> Result<String, sqlx::Error> {
+    let mut statements = match kind {
+        "table" => get_table_ddl(db, oid).await?,
+        "index" => get_index_ddl(db, oid).await?,
+        "trigger" => get_trigger_ddl(db, oid).await?,
+        "function" | "procedure" => {
+            let definition = get_object_definition(db, kind, oid).await?;
+            vec![format!("{};", definition.trim_end())]
+        }
+        _ => vec![format_sql(&get_object_definition(db, kind, oid).await?)],
+    };
+    // Indexes and triggers of a table add their own comments above.
+    if kind != "index" && kind != "trigger" {
+        statements.extend(get_comment_ddl(db, kind, oid).await?);
+    }
+    statements.extend(get_ownership_ddl(db, kind, oid).await?);
+    Ok(statements.join("\n\n"))
+}
+
+async fn get_table_ddl(db: &Pool<Postgres>, oid: Oid) -> Result<Vec<String>, sqlx::Error> {
+    let table = sqlx::query!(
+        r#"
+        SELECT
+          c.oid::regclass::text AS "name!",
+          c.relpersistence::text AS "persistence!",
+          (
+            SELECT i.inhparent::regclass::text
+            FROM pg_inherits i
+            WHERE i.inhrelid = c.oid AND c.relispartition
+          ) AS partition_parent,
+          pg_get_expr(c.relpartbound, c.oid) AS partition_bound,
+          pg_get_partkeydef(c.oid) AS partition_key,
+          (
+            SELECT string_agg(i.inhparent::regclass::text, ', ' ORDER BY i.inhseqno)
+            FROM pg_inherits i
+            WHERE i.inhrelid = c.oid AND NOT c.relispartition
+          ) AS inherits,
+          array_to_string(c.reloptions, ', ') AS options,
+          quote_ident(t.spcname) AS tablespace,
+          c.relrowsecurity AS "row_security!",
+          c.relforcerowsecurity AS "force_row_security!"
+        FROM
+          pg_class c
+          LEFT JOIN pg_tablespace t ON t.oid = c.reltablespace
+        WHERE
+          c.oid = $1;
+        "#,
+        oid
+    )
+    .fetch_one(db)
+    .await?;
+
+    // Columns of a partition come from its parent, and inherited columns
+    // from the parents listed under INHERITS.
+    let mut elements = sqlx::query_scalar!(
+        r#"
+        SELECT
+          quote_ident(a.attname) || ' ' || format_type(a.atttypid, a.atttypmod)
+            || CASE
+                 WHEN a.attcollation <> t.typcollation THEN ' COLLATE ' || a.attcollation::regcollation::text
+                 ELSE ''
+               END
+            || CASE a.attidentity
+                 WHEN 'a' THEN ' GENERATED ALWAYS AS IDENTITY'
+                 WHEN 'd' THEN ' GENERATED BY DEFAULT AS IDENTITY'
+                 ELSE ''
+               END
+            || CASE
+                 WHEN a.attgenerated = 's' THEN ' GENERATED ALWAYS AS (' || pg_get_expr(d.adbin, d.adrelid) || ') STORED'
+                 WHEN d.adbin IS NOT NULL THEN ' DEFAULT ' || pg_get_expr(d.adbin, d.adrelid)
+                 ELSE ''
+               END
+            || CASE WHEN a.attnotnull THEN ' NOT NULL' ELSE '' END AS "definition!"
+        FROM
+          pg_attribute a
+          JOIN pg_class c ON c.oid = a.attrelid
+          JOIN pg_type t ON t.oid = a.atttypid
+          LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE
+          a.attrelid = $1
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+          AND a.attislocal
+          AND NOT c.relispartition
+        ORDER BY
+          a.attnum;
+        "#,
+        oid
+    )
+    .fetch_all(db)
+    .await?;
+
+    elements.extend(
+        sqlx::query_scalar!(
+            r#"
+            SELECT
+              'CONSTRAINT ' || quote_ident(con.conname) || ' ' || pg_get_constraintdef(con.oid) AS "definition!"
+            FROM
+              pg_constraint con
+            WHERE
+              con.conrelid = $1
+              AND con.conislocal
+              AND con.contype IN ('p', 'u', 'f', 'c', 'x')
+            ORDER BY
+              array_position(ARRAY['p', 'u', 'f', 'c', 'x'], con.contype::text),
+              con.conname;
+            "#,
+            oid
+        )
+        .fetch_all(db)
+        .await?,
+    );
+
+    let mut create = String::from("CREATE ");
+    match table.persistence.as_str() {
+        "u" => create += "UNLOGGED ",
+        "t" => create += "TEMPORARY ",
+        _ => {}
+    }
+    create += &format!("TABLE {}", table.name);
+    if let Some(parent) = &table.partition_parent {
+        create += &format!(" PARTITION OF {parent}");
+    }
+    if !elements.is_empty() || table.partition_parent.is_none() {
+        create += &format!(" ({})", elements.join(", "));
+    }
+    if let Some(bound) = &table.partition_bound {
+        create += &format!(" {bound}");
+    }
+    if let Some(inherits) = &table.inherits {
+        create += &format!(" INHERITS ({inherits})");
+    }
+    if let Some(partition_key) = &table.partition_key {
+        create += &format!(" PARTITION BY {partition_key}");
+    }
+    if let Some(options) = table
+        .options
+        .as_deref()
+        .filter(|options| !options.is_empty())
+    {
+        create += &format!(" WITH ({options})");
+    }
+    if let Some(tablespace) = &table.tablespace {
+        create += &format!(" TABLESPACE {tablespace}");
+    }
+    create += ";";
+
+    // Sequences behind serial columns are dropped with the table, so they are
+    // created before it and attached to their columns afterwards.
+    let owned_sequences = sqlx::query!(
+        r#"
+        SELECT
+          d.objid AS "oid!",
+          format('ALTER SEQUENCE %s OWNED BY %s.%I;', d.objid::regclass, d.refobjid::regclass, a.attname)
+            AS "owned_by!"
+        FROM
+          pg_depend d
+          JOIN pg_class s ON s.oid = d.objid
+          JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+        WHERE
+          d.classid = 'pg_class'::regclass
+          AND d.refclassid = 'pg_class'::regclass
+          AND d.refobjid = $1
+          AND d.deptype = 'a'
+          AND s.relkind = 'S'
+        ORDER BY
+          a.attnum;
+        "#,
+        oid
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut statements = Vec::new();
+    for sequence in &owned_sequences {
+        statements.push(format_sql(
+            &get_object_definition(db, "sequence", sequence.oid).await?,
+        ));
+    }
+    // The formatter splits partition bounds apart, so partitions stay on one line.
+    if table.partition_parent.is_some() {
+        statements.push(create);
+    } else {
+        statements.push(format_sql(&create));
+    }
+    statements.extend(
+        owned_sequences
+            .into_iter()
+            .map(|sequence| sequence.owned_by),
+    );
+
+    // A partition takes its columns from the parent, so defaults and NOT NULL
+    // that differ from the parent's are set afterwards.
+    statements.extend(
+        sqlx::query_scalar!(
+            r#"
+            SELECT
+              o.statement AS "statement!"
+            FROM
+              (
+                SELECT
+                  a.attnum,
+                  1 AS position,
+                  CASE
+                    WHEN d.adbin IS NULL THEN format('ALTER TABLE %s ALTER COLUMN %I DROP DEFAULT;', a.attrelid::regclass, a.attname)
+                    ELSE format('ALTER TABLE %s ALTER COLUMN %I SET DEFAULT %s;', a.attrelid::regclass, a.attname, pg_get_expr(d.adbin, d.adrelid))
+                  END AS statement
+                FROM
+                  pg_attribute a
+                  JOIN pg_inherits i ON i.inhrelid = a.attrelid
+                  JOIN pg_attribute pa ON pa.attrelid = i.inhparent AND pa.attname = a.attname
+                  LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                  LEFT JOIN pg_attrdef pd ON pd.adrelid = pa.attrelid AND pd.adnum = pa.attnum
+                WHERE
+                  a.attrelid = $1
+                  AND a.attnum > 0
+                  AND NOT a.attisdropped
+                  AND a.attgenerated = ''
+                  AND pg_get_expr(d.adbin, d.adrelid) IS DISTINCT FROM pg_get_expr(pd.adbin, pd.adrelid)
+                UNION ALL
+                SELECT
+                  a.attnum,
+                  2,
+                  format('ALTER TABLE %s ALTER COLUMN %I SET NOT NULL;', a.attrelid::regclass, a.attname)
+                FROM
+                  pg_attribute a
+                  JOIN pg_inherits i ON i.inhrelid = a.attrelid
+                  JOIN pg_attribute pa ON pa.attrelid = i.inhparent AND pa.attname = a.attname
+                WHERE
+                  a.attrelid = $1
+                  AND a.attnum > 0
+                  AND NOT a.attisdropped
+                  AND a.attnotnull
+                  AND NOT pa.attnotnull
+              ) o
+              JOIN pg_class c ON c.oid = $1
+            WHERE
+              c.relispartition
+            ORDER BY
+              o.attnum,
+              o.position;
+            "#,
+            oid
+        )
+        .fetch_all(db)
+        .await?,
+    );
+    if table.row_security {
+        statements.push(format!(
+            "ALTER TABLE {} ENABLE ROW LEVEL SECURITY;",
+            table.name
+        ));
+    }
+    if table.force_row_security {
+        statements.push(format!(
+            "ALTER TABLE {} FORCE ROW LEVEL SECURITY;",
+            table.name
+        ));
+    }
+
+    // Indexes behind constraints are created by the constraints, and those
+    // of partitions by the parent's index.
+    let indexes = sqlx::query_scalar!(
+        r#"
+        SELECT
+          x.indexrelid AS "oid!"
+        FROM
+          pg_index x
+          JOIN pg_class i ON i.oid = x.indexrelid
+        WHERE
+          x.indrelid = $1
+          AND NOT i.relispartition
+          AND NOT EXISTS (
+            SELECT 1
+            FROM pg_constraint con
+            WHERE con.conrelid = x.indrelid AND con.conindid = x.indexrelid AND con.contype IN ('p', 'u', 'x')
+          )
+        ORDER BY
+          i.relname;
+        "#,
+        oid
+    )
+    .fetch_all(db)
+    .await?;
+    for index in indexes {
+        statements.extend(get_index_ddl(db, index).await?);
+    }
+
+    let triggers = sqlx::query_scalar!(
+        r#"
+        SELECT
+          t.oid AS "oid!"
+        FROM
+          pg_trigger t
+        WHERE
+          t.tgrelid = $1
+          AND NOT t.tgisinternal
+          AND t.tgparentid = 0
+        ORDER BY
+          t.tgname;
+        "#,
+        oid
+    )
+    .fetch_all(db)
+    .await?;
+    for trigger in triggers {
+        statements.extend(get_trigger_ddl(db, trigger).await?);
+    }
+
+    // Kept on one line, the formatter splits `FOR SELECT` apart.
+    statements.extend(
+        sqlx::query_scalar!(
+            r#"
+            SELECT
+              format(
+                'CREATE POLICY %I ON %s AS %s FOR %s TO %s%s%s;',
+                p.polname,
+                p.polrelid::regclass,
+                CASE WHEN p.polpermissive THEN 'PERMISSIVE' ELSE 'RESTRICTIVE' END,
+                CASE p.polcmd
+                  WHEN 'r' THEN 'SELECT'
+                  WHEN 'a' THEN 'INSERT'
+                  WHEN 'w' THEN 'UPDATE'
+                  WHEN 'd' THEN 'DELETE'
+                  ELSE 'ALL'
+                END,
+                CASE
+                  WHEN p.polroles = '{0}' THEN 'PUBLIC'
+                  ELSE (
+                    SELECT string_agg(quote_ident(r.rolname), ', ' ORDER BY r.rolname)
+                    FROM pg_roles r
+                    WHERE r.oid = ANY (p.polroles)
+                  )
+                END,
+                ' USING (' || pg_get_expr(p.polqual, p.polrelid) || ')',
+                ' WITH CHECK (' || pg_get_expr(p.polwithcheck, p.polrelid) || ')'
+              ) AS "definition!"
+            FROM
+              pg_policy p
+            WHERE
+              p.polrelid = $1
+            ORDER BY
+              p.polname;
+            "#,
+            oid
+        )
+        .fetch_all(db)
+        .await?,
+    );
+
+    statements.extend(
+        sqlx::query_scalar!(
+            r#"
+            SELECT
+              format('COMMENT ON CONSTRAINT %I ON %s IS %L;', con.conname, con.conrelid::regclass, d.description)
+                AS "definition!"
+            FROM
+              pg_constraint con
+              JOIN pg_description d ON d.objoid = con.oid AND d.classoid = 'pg_constraint'::regclass
+            WHERE
+              con.conrelid = $1
+              AND con.conislocal
+            ORDER BY
+              con.conname;
+            "#,
+            oid
+        )
+        .fetch_all(db)
+        .await?,
+    );
+
+    Ok(statements)
+}
+
+async fn get_index_ddl(db: &Pool<Postgres>, oid: Oid) -> Result<Vec<String>, sqlx::Error> {
+    let index = sqlx::query!(
+        r#"
+        SELECT
+          pg_get_indexdef($1) || ';' AS "definition!",
+          'COMMENT ON INDEX ' || $1::oid::regclass::text || ' IS '
+            || quote_literal(obj_description($1, 'pg_class')) || ';' AS comment
+        "#,
+        oid
+    )
+    .fetch_one(db)
+    .await?;
+    Ok([Some(format_sql(&index.definition)), index.comment]
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+async fn get_trigger_ddl(db: &Pool<Postgres>, oid: Oid) -> Result<Vec<String>, sqlx::Error> {
+    let trigger = sqlx::query!(
+        r#"
+        SELECT
+          pg_get_triggerdef(t.oid, true) || ';' AS "definition!",
+          format(
+            'ALTER TABLE %s %s TRIGGER %I;',
+            t.tgrelid::regclass,
+            CASE t.tgenabled WHEN 'D' THEN 'DISABLE' WHEN 'R' THEN 'ENABLE REPLICA' ELSE 'ENABLE ALWAYS' END,
+            t.tgname
+          ) AS "state!",
+          t.tgenabled <> 'O' AS "changed_state!",
+          'COMMENT ON TRIGGER ' || quote_ident(t.tgname) || ' ON ' || t.tgrelid::regclass::text || ' IS '
+            || quote_literal(obj_description(t.oid, 'pg_trigger')) || ';' AS comment
+        FROM
+          pg_trigger t
+        WHERE
+          t.oid = $1;
+        "#,
+        oid
+    )
+    .fetch_one(db)
+    .await?;
+    // The formatter breaks trigger definitions up, so they stay on one line.
+    let mut statements = vec![trigger.definition];
+    if trigger.changed_state {
+        statements.push(trigger.state);
+    }
+    statements.extend(trigger.comment);
+    Ok(statements)
+}
+
+/// The object's own comment and, for relations, the comments on its columns.
+async fn get_comment_ddl(
+    db: &Pool<Postgres>,
+    kind: &str,
+    oid: Oid,
+) -> Result<Vec<String>, sqlx::Error> {
+    let Some(entry) = catalog_entry(kind) else {
+        return Ok(Vec::new());
+    };
+
+    let query = format!(
+        "SELECT format('COMMENT ON {} %s IS %L;', {}, d.description)
+         FROM {} o
+         JOIN pg_description d ON d.objoid = o.oid AND d.classoid = '{}'::regclass AND d.objsubid = 0
+         WHERE o.oid = $1",
+        entry.keyword, entry.name, entry.catalog, entry.catalog
+    );
+    let mut statements: Vec<String> = sqlx::query_scalar(&query).bind(oid).fetch_all(db).await?;
+
+    if entry.catalog == "pg_class" {
+        statements.extend(
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format('COMMENT ON COLUMN %s.%I IS %L;', a.attrelid::regclass, a.attname, d.description)
+                    AS "definition!"
+                FROM
+                  pg_attribute a
+                  JOIN pg_description d ON d.objoid = a.attrelid
+                    AND d.classoid = 'pg_class'::regclass
+                    AND d.objsubid = a.attnum
+                WHERE
+                  a.attrelid = $1
+                  AND NOT a.attisdropped
+                ORDER BY
+                  a.attnum;
+                "#,
+                oid
+            )
+            .fetch_all(db)
+            .await?,
+        );
+    }
+    Ok(statements)
+}
+
+/// `ALTER ... OWNER TO` plus the `GRANT` and `REVOKE` statements that turn the
+/// default privileges into the object's current ones.
+async fn get_ownership_ddl(
+    db: &Pool<Postgres>,
+    kind: &str,
+    oid: Oid,
+) -> Result<Vec<String>, sqlx::Error> {
+    let Some(entry) = catalog_entry(kind) else {
+        return Ok(Vec::new());
+    };
+    let mut statements = Vec::new();
+
+    if let Some(owner) = entry.owner {
+        let query = format!(
+            "SELECT format('ALTER {} %s OWNER TO %I;', {}, pg_get_userbyid({owner}))
+             FROM {} WHERE oid = $1",
+            entry.keyword, entry.name, entry.catalog
+        );
+        statements.extend(
+            sqlx::query_scalar::<_, String>(&query)
+                .bind(oid)
+                .fetch_optional(db)
+                .await?,
+        );
+    }
+
+    let (Some(owner), Some((acl, acl_type))) = (entry.owner, entry.acl) else {
+        return Ok(statements);
+    };
+    // Aggregates and views are granted to like functions and tables.
+    let keyword = match entry.keyword {
+        "AGGREGATE" => "FUNCTION",
+        "VIEW" | "MATERIALIZED VIEW" | "FOREIGN TABLE" => "TABLE",
+        keyword => keyword,
+    };
+    let query = format!(
+        "WITH object AS (
+           SELECT {name} AS name, {owner} AS owner, {acl} AS acl FROM {catalog} WHERE oid = $1
+         ),
+         actual AS (
+           SELECT a.grantee, a.privilege_type, a.is_grantable
+           FROM object o, aclexplode(coalesce(o.acl, acldefault('{acl_type}', o.owner))) a
+         ),
+         defaults AS (
+           SELECT a.grantee, a.privilege_type, a.is_grantable
+           FROM object o, aclexplode(acldefault('{acl_type}', o.owner)) a
+         ),
+         changes AS (
+           (SELECT *, false AS revoked FROM actual EXCEPT SELECT *, false FROM defaults)
+           UNION ALL
+           (SELECT *, true FROM defaults EXCEPT SELECT *, true FROM actual)
+         )
+         SELECT format(
+           CASE WHEN c.revoked THEN 'REVOKE %s ON {keyword} %s FROM %s;' ELSE 'GRANT %s ON {keyword} %s TO %s%s;' END,
+           string_agg(c.privilege_type, ', ' ORDER BY c.privilege_type),
+           o.name,
+           CASE WHEN c.grantee = 0 THEN 'PUBLIC' ELSE quote_ident(pg_get_userbyid(c.grantee)) END,
+           CASE WHEN c.is_grantable THEN ' WITH GRANT OPTION' ELSE '' END
+         )
+         FROM changes c, object o
+         GROUP BY o.name, c.grantee, c.is_grantable, c.revoked
+         ORDER BY c.revoked DESC, c.grantee = 0 DESC, pg_get_userbyid(c.grantee)",
+        name = entry.name,
+        catalog = entry.catalog,
+    );
+    statements.extend(
+        sqlx::query_scalar::<_, String>(&query)
+            .bind(oid)
+            .fetch_all(db)
+            .await?,
+    );
+
+    if entry.catalog == "pg_class" {
+        statements.extend(
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    'GRANT %s (%s) ON TABLE %s TO %s%s;',
+                    g.privilege_type,
+                    string_agg(quote_ident(g.attname), ', ' ORDER BY g.attnum),
+                    $1::oid::regclass,
+                    CASE WHEN g.grantee = 0 THEN 'PUBLIC' ELSE quote_ident(pg_get_userbyid(g.grantee)) END,
+                    CASE WHEN g.is_grantable THEN ' WITH GRANT OPTION' ELSE '' END
+                  ) AS "definition!"
+                FROM
+                  (
+                    SELECT a.attname, a.attnum, x.grantee, x.privilege_type, x.is_grantable
+                    FROM pg_attribute a, aclexplode(a.attacl) x
+                    WHERE a.attrelid = $1 AND NOT a.attisdropped
+                  ) g
+                GROUP BY
+                  g.grantee,
+                  g.privilege_type,
+                  g.is_grantable
+                ORDER BY
+                  pg_get_userbyid(g.grantee),
+                  g.privilege_type;
+                "#,
+                oid
+            )
+            .fetch_all(db)
+            .await?,
+        );
+    }
+    Ok(statements)
+}
+
+/// Runs a script in one transaction, e.g. one built by the table designer.
+pub async fn apply_ddl(db: &Pool<Postgres>, statements: Vec<String>) -> Result<(), String> {
+    let mut transaction = db.begin().await.map_err(|e| e.to_string())?;
+    for statement in &statements {
+        sqlx::query(statement)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("{e}\n{statement}"))?;
+    }
+    transaction.commit().await.map_err(|e| e.to_string())
+}
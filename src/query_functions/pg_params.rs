@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::PgArguments,
+    query::Query,
+    types::{BigDecimal, Uuid},
+    Postgres,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParamType {
+    #[default]
+    Text,
+    Integer,
+    BigInt,
+    Float,
+    Numeric,
+    Boolean,
+    Date,
+    Timestamp,
+    Timestamptz,
+    Uuid,
+}
+
+impl ParamType {
+    pub const ALL: [ParamType; 10] = [
+        ParamType::Text,
+        ParamType::Integer,
+        ParamType::BigInt,
+        ParamType::Float,
+        ParamType::Numeric,
+        ParamType::Boolean,
+        ParamType::Date,
+        ParamType::Timestamp,
+        ParamType::Timestamptz,
+        ParamType::Uuid,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ParamType::Text => "text",
+            ParamType::Integer => "integer",
+            ParamType::BigInt => "bigint",
+            ParamType::Float => "double precision",
+            ParamType::Numeric => "numeric",
+            ParamType::Boolean => "boolean",
+            ParamType::Date => "date",
+            ParamType::Timestamp => "timestamp",
+            ParamType::Timestamptz => "timestamptz",
+            ParamType::Uuid => "uuid",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct QueryParam {
+    pub name: String,
+    pub param_type: ParamType,
+    pub value: String,
+    pub is_null: bool,
+}
+
+fn parse<T: FromStr>(param: &QueryParam) -> Result<Option<T>, String> {
+    if param.is_null {
+        return Ok(None);
+    }
+    // Accept the space separated timestamps Postgres itself prints.
+    let value = match param.param_type {
+        ParamType::Text => param.value.clone(),
+        ParamType::Timestamp | ParamType::Timestamptz => param.value.trim().replacen(' ', "T", 1),
+        _ => param.value.trim().to_string(),
+    };
+    value.parse::<T>().map(Some).map_err(|_| {
+        format!(
+            "Invalid {} value for {}: {}",
+            param.param_type.label(),
+            param.name,
+            param.value
+        )
+    })
+}
+
+/// Binds every parameter to the query with the type the user picked, so
+/// values never end up interpolated into the SQL text.
+pub fn bind_params<'q>(
+    mut query: Query<'q, Postgres, PgArguments>,
+    params: &[QueryParam],
+) -> Result<Query<'q, Postgres, PgArguments>, String> {
+    for param in params {
+        query = match param.param_type {
+            ParamType::Text => query.bind(parse::<String>(param)?),
+            ParamType::Integer => query.bind(parse::<i32>(param)?),
+            ParamType::BigInt => query.bind(parse::<i64>(param)?),
+            ParamType::Float => query.bind(parse::<f64>(param)?),
+            ParamType::Numeric => query.bind(parse::<BigDecimal>(param)?),
+            ParamType::Boolean => query.bind(parse::<bool>(param)?),
+            ParamType::Date => query.bind(parse::<chrono::NaiveDate>(param)?),
+            ParamType::Timestamp => query.bind(parse::<chrono::NaiveDateTime>(param)?),
+            ParamType::Timestamptz => {
+                query.bind(parse::<chrono::DateTime<chrono::FixedOffset>>(param)?)
+            }
+            ParamType::Uuid => query.bind(parse::<Uuid>(param)?),
+        };
+    }
+    Ok(query)
+}
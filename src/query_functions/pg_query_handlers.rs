@@ -1,15 +1,22 @@
 use std::{sync::mpsc::Sender, time::Instant};
 
 use sqlformat::{format, FormatOptions, QueryParams};
-use sqlx::{postgres::PgRow, Pool, Postgres};
+use sqlx::{postgres::PgRow, Connection, Pool, Postgres};
 
 use super::pg_data::get_query_pid;
+use super::pg_params::{bind_params, QueryParam};
+use super::pg_session::{EditorSession, PinnedSession, TransactionStatus};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_query(
     db_pool: &Option<Pool<Postgres>>,
+    session: EditorSession,
+    auto_commit: bool,
     query_str: String,
+    params: Vec<QueryParam>,
     tx: Sender<(Vec<PgRow>, String, u128, f64)>,
     pid_tx: Sender<i32>,
+    status_tx: Sender<TransactionStatus>,
 ) {
     let mut query_execution_time_ms: u128 = 0;
     let mut query_execution_time_sec = 0.0;
@@ -17,8 +24,16 @@ pub async fn execute_query(
     let mut error_message: String = String::new();
 
     if let Some(pool) = db_pool {
-        if let Ok(mut conn) = pool.acquire().await {
-            match get_query_pid(&mut conn).await {
+        let mut session = session.lock().await;
+        if session.is_none() {
+            *session = pool.acquire().await.ok().map(|conn| PinnedSession {
+                conn,
+                status: TransactionStatus::Idle,
+            });
+        }
+
+        if let Some(pinned) = session.as_mut() {
+            match get_query_pid(&mut pinned.conn).await {
                 Ok(row) => {
                     if let Some(pid) = row.pg_backend_pid {
                         let _ = pid_tx.send(pid);
@@ -27,19 +42,46 @@ pub async fn execute_query(
                 Err(err) => eprintln!("Failed to get PID {err}"),
             };
 
+            if !auto_commit && pinned.status == TransactionStatus::Idle {
+                let result = sqlx::query("BEGIN").execute(&mut *pinned.conn).await;
+                if let Err(err) = &result {
+                    eprintln!("Failed to begin transaction: {err}");
+                }
+                pinned.status = pinned.status.after("BEGIN", result.is_ok());
+            }
+
             let query_start_time = Instant::now();
 
-            match sqlx::query(&query_str).fetch_all(&mut *conn).await {
-                Ok(rows) => {
-                    let elapsed_time = query_start_time.elapsed();
-                    query_execution_time_ms = elapsed_time.as_millis();
-                    query_execution_time_sec = (elapsed_time.as_secs_f64() * 100.0).round() / 100.0;
-                    res_rows = rows;
+            match bind_params(sqlx::query(&query_str), &params) {
+                Ok(query) => {
+                    let result = query.fetch_all(&mut *pinned.conn).await;
+                    pinned.status = pinned.status.after(&query_str, result.is_ok());
+                    match result {
+                        Ok(rows) => {
+                            let elapsed_time = query_start_time.elapsed();
+                            query_execution_time_ms = elapsed_time.as_millis();
+                            query_execution_time_sec =
+                                (elapsed_time.as_secs_f64() * 100.0).round() / 100.0;
+                            res_rows = rows;
+                        }
+                        Err(e) => {
+                            error_message = format!("{e}");
+                        }
+                    }
                 }
                 Err(e) => {
-                    error_message = format!("{e}");
+                    error_message = e;
                 }
             }
+
+            // The backend may have been terminated (e.g. by cancelling the query),
+            // in which case the next run should start on a fresh connection.
+            if pinned.conn.ping().await.is_ok() {
+                let _ = status_tx.send(pinned.status);
+            } else {
+                *session = None;
+                let _ = status_tx.send(TransactionStatus::Idle);
+            }
         } else {
             error_message = String::from("Failed to acquire a database connection");
         }
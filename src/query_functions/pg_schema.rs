@@ -0,0 +1,936 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::types::Oid, Pool, Postgres};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableColumn {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    /// `a` or `d` for identity columns, see `pg_attribute.attidentity`.
+    pub identity: String,
+    /// `s` for stored generated columns.
+    pub generated: String,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableIndex {
+    pub oid: Oid,
+    pub name: String,
+    pub definition: String,
+    pub is_primary: bool,
+    pub is_unique: bool,
+    /// Key columns in index order. Expression columns are left out.
+    pub columns: Vec<String>,
+    /// Access method, e.g. `btree`.
+    pub method: String,
+    /// Every key column or expression, as written in `CREATE INDEX`.
+    pub keys: Vec<String>,
+    pub predicate: Option<String>,
+    /// Set for indexes that back a primary key, unique or exclusion constraint.
+    pub is_constraint: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableConstraint {
+    pub name: String,
+    /// `pg_constraint.contype`: `p`, `f`, `u`, `c`, `x` or `t`.
+    pub kind: String,
+    pub definition: String,
+    pub columns: Vec<String>,
+    pub foreign_schema: Option<String>,
+    pub foreign_table: Option<String>,
+    pub foreign_columns: Vec<String>,
+    /// `pg_constraint.confupdtype` and `confdeltype`: `a`, `r`, `c`, `n` or `d`.
+    pub on_update: String,
+    pub on_delete: String,
+    /// The expression of a check constraint.
+    pub expression: Option<String>,
+}
+
+impl TableConstraint {
+    pub fn kind_label(&self) -> &'static str {
+        match self.kind.as_str() {
+            "p" => "primary key",
+            "f" => "foreign key",
+            "u" => "unique",
+            "c" => "check",
+            "x" => "exclusion",
+            "t" => "trigger",
+            "n" => "not null",
+            _ => "constraint",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableTrigger {
+    pub oid: Oid,
+    pub name: String,
+    pub definition: String,
+    /// `D` when disabled, see `pg_trigger.tgenabled`.
+    pub enabled: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TablePolicy {
+    pub name: String,
+    pub command: String,
+    pub permissive: String,
+    pub roles: Vec<String>,
+    pub using_expression: Option<String>,
+    pub check_expression: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TablePartition {
+    pub schema: String,
+    pub name: String,
+    pub bound: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TableDetails {
+    pub columns: Vec<TableColumn>,
+    pub indexes: Vec<TableIndex>,
+    pub constraints: Vec<TableConstraint>,
+    pub triggers: Vec<TableTrigger>,
+    pub policies: Vec<TablePolicy>,
+    pub partitions: Vec<TablePartition>,
+    pub partition_key: Option<String>,
+    pub row_security: bool,
+}
+
+impl TableDetails {
+    pub fn primary_key(&self) -> Option<&[String]> {
+        self.indexes
+            .iter()
+            .find(|index| index.is_primary)
+            .map(|index| index.columns.as_slice())
+    }
+}
+
+async fn get_columns(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TableColumn>, sqlx::Error> {
+    sqlx::query_as!(
+        TableColumn,
+        r#"
+        SELECT
+          a.attname AS "name!",
+          format_type(a.atttypid, a.atttypmod) AS "data_type!",
+          a.attnotnull AS "not_null!",
+          pg_get_expr(d.adbin, d.adrelid) AS default_value,
+          a.attidentity::text AS "identity!",
+          a.attgenerated::text AS "generated!",
+          col_description(a.attrelid, a.attnum) AS comment
+        FROM
+          pg_attribute a
+          JOIN pg_class c ON c.oid = a.attrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+        ORDER BY
+          a.attnum;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_indexes(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TableIndex>, sqlx::Error> {
+    sqlx::query_as!(
+        TableIndex,
+        r#"
+        SELECT
+          x.indexrelid AS "oid!",
+          i.relname AS "name!",
+          pg_get_indexdef(x.indexrelid) AS "definition!",
+          x.indisprimary AS "is_primary!",
+          x.indisunique AS "is_unique!",
+          ARRAY(
+            SELECT
+              a.attname::text
+            FROM
+              unnest(x.indkey[:x.indnkeyatts - 1]) WITH ORDINALITY k (attnum, position)
+              JOIN pg_attribute a ON a.attrelid = x.indrelid AND a.attnum = k.attnum
+            ORDER BY
+              k.position
+          ) AS "columns!",
+          am.amname::text AS "method!",
+          ARRAY(
+            SELECT
+              pg_get_indexdef(x.indexrelid, k, TRUE)
+            FROM
+              generate_series(1, x.indnkeyatts) k
+          ) AS "keys!",
+          pg_get_expr(x.indpred, x.indrelid, TRUE) AS predicate,
+          EXISTS (
+            SELECT
+            FROM
+              pg_constraint con
+            WHERE
+              con.conindid = x.indexrelid
+              AND con.conrelid = x.indrelid
+          ) AS "is_constraint!"
+        FROM
+          pg_index x
+          JOIN pg_class i ON i.oid = x.indexrelid
+          JOIN pg_am am ON am.oid = i.relam
+          JOIN pg_class c ON c.oid = x.indrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+        ORDER BY
+          NOT x.indisprimary,
+          i.relname;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_constraints(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TableConstraint>, sqlx::Error> {
+    sqlx::query_as!(
+        TableConstraint,
+        r#"
+        SELECT
+          con.conname AS "name!",
+          con.contype::text AS "kind!",
+          pg_get_constraintdef(con.oid) AS "definition!",
+          ARRAY(
+            SELECT
+              a.attname::text
+            FROM
+              unnest(con.conkey) WITH ORDINALITY k (attnum, position)
+              JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+            ORDER BY
+              k.position
+          ) AS "columns!",
+          fn.nspname::text AS foreign_schema,
+          f.relname::text AS foreign_table,
+          ARRAY(
+            SELECT
+              a.attname::text
+            FROM
+              unnest(con.confkey) WITH ORDINALITY k (attnum, position)
+              JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+            ORDER BY
+              k.position
+          ) AS "foreign_columns!",
+          con.confupdtype::text AS "on_update!",
+          con.confdeltype::text AS "on_delete!",
+          pg_get_expr(con.conbin, con.conrelid) AS expression
+        FROM
+          pg_constraint con
+          JOIN pg_class c ON c.oid = con.conrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+          LEFT JOIN pg_class f ON f.oid = con.confrelid
+          LEFT JOIN pg_namespace fn ON fn.oid = f.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+        ORDER BY
+          array_position(ARRAY['p', 'u', 'f', 'c', 'x'], con.contype::text),
+          con.conname;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_triggers(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TableTrigger>, sqlx::Error> {
+    sqlx::query_as!(
+        TableTrigger,
+        r#"
+        SELECT
+          t.oid AS "oid!",
+          t.tgname AS "name!",
+          pg_get_triggerdef(t.oid) AS "definition!",
+          t.tgenabled::text AS "enabled!"
+        FROM
+          pg_trigger t
+          JOIN pg_class c ON c.oid = t.tgrelid
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2
+          AND NOT t.tgisinternal
+        ORDER BY
+          t.tgname;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_policies(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TablePolicy>, sqlx::Error> {
+    sqlx::query_as!(
+        TablePolicy,
+        r#"
+        SELECT
+          policyname AS "name!",
+          cmd AS "command!",
+          permissive AS "permissive!",
+          roles::text[] AS "roles!",
+          qual AS using_expression,
+          with_check AS check_expression
+        FROM
+          pg_policies
+        WHERE
+          schemaname = $1
+          AND tablename = $2
+        ORDER BY
+          policyname;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+async fn get_partitions(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TablePartition>, sqlx::Error> {
+    sqlx::query_as!(
+        TablePartition,
+        r#"
+        SELECT
+          cn.nspname AS "schema!",
+          c.relname AS "name!",
+          pg_get_expr(c.relpartbound, c.oid) AS bound
+        FROM
+          pg_inherits i
+          JOIN pg_class c ON c.oid = i.inhrelid
+          JOIN pg_namespace cn ON cn.oid = c.relnamespace
+          JOIN pg_class p ON p.oid = i.inhparent
+          JOIN pg_namespace pn ON pn.oid = p.relnamespace
+        WHERE
+          pn.nspname = $1
+          AND p.relname = $2
+          AND c.relispartition
+        ORDER BY
+          c.relname;
+        "#,
+        schema,
+        table
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Loads everything shown under a table in the schema browser.
+pub async fn get_table_details(
+    db: &Pool<Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<TableDetails, sqlx::Error> {
+    let (columns, indexes, constraints, triggers, policies, partitions) = tokio::try_join!(
+        get_columns(db, schema, table),
+        get_indexes(db, schema, table),
+        get_constraints(db, schema, table),
+        get_triggers(db, schema, table),
+        get_policies(db, schema, table),
+        get_partitions(db, schema, table),
+    )?;
+
+    let table_info = sqlx::query!(
+        r#"
+        SELECT
+          pg_get_partkeydef(c.oid) AS partition_key,
+          c.relrowsecurity AS "row_security!"
+        FROM
+          pg_class c
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2;
+        "#,
+        schema,
+        table
+    )
+    .fetch_optional(db)
+    .await?;
+    let (partition_key, row_security) = table_info.map_or((None, false), |info| {
+        (info.partition_key, info.row_security)
+    });
+
+    Ok(TableDetails {
+        columns,
+        indexes,
+        constraints,
+        triggers,
+        policies,
+        partitions,
+        partition_key,
+        row_security,
+    })
+}
+
+/// A function, sequence, type or materialized view in a schema, or an extension.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SchemaObject {
+    /// e.g. `function`, `procedure`, `sequence`, `enum` or `materialized view`.
+    pub kind: String,
+    pub oid: Oid,
+    pub name: String,
+    /// A function's signature, a sequence's current value or an enum's labels.
+    pub detail: Option<String>,
+}
+
+pub async fn get_schemas(db: &Pool<Postgres>) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT
+          nspname AS "nspname!"
+        FROM
+          pg_namespace
+        WHERE
+          nspname <> 'pg_toast'
+          AND nspname NOT LIKE 'pg_temp_%'
+          AND nspname NOT LIKE 'pg_toast_temp_%'
+        ORDER BY
+          nspname;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_schema_objects(
+    db: &Pool<Postgres>,
+    schema: &str,
+) -> Result<Vec<SchemaObject>, sqlx::Error> {
+    sqlx::query_as!(
+        SchemaObject,
+        r#"
+        SELECT
+          kind AS "kind!",
+          oid AS "oid!",
+          name AS "name!",
+          detail
+        FROM (
+          SELECT
+            CASE p.prokind
+              WHEN 'p' THEN 'procedure'
+              WHEN 'a' THEN 'aggregate'
+              ELSE 'function'
+            END AS kind,
+            p.oid,
+            p.proname::text AS name,
+            '(' || pg_get_function_arguments(p.oid) || ')'
+              || CASE WHEN p.prokind = 'p' THEN '' ELSE ' → ' || pg_get_function_result(p.oid) END
+              AS detail
+          FROM
+            pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+          WHERE
+            n.nspname = $1
+          UNION ALL
+          SELECT
+            'sequence',
+            c.oid,
+            c.relname::text,
+            s.last_value::text
+          FROM
+            pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_sequences s ON s.schemaname = n.nspname AND s.sequencename = c.relname
+          WHERE
+            n.nspname = $1
+            AND c.relkind = 'S'
+          UNION ALL
+          SELECT
+            'materialized view',
+            c.oid,
+            c.relname::text,
+            CASE WHEN c.relispopulated THEN NULL ELSE 'not populated' END
+          FROM
+            pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+          WHERE
+            n.nspname = $1
+            AND c.relkind = 'm'
+          UNION ALL
+          SELECT
+            CASE t.typtype
+              WHEN 'e' THEN 'enum'
+              WHEN 'd' THEN 'domain'
+              WHEN 'r' THEN 'range'
+              ELSE 'composite'
+            END,
+            t.oid,
+            t.typname::text,
+            CASE t.typtype
+              WHEN 'e' THEN (
+                SELECT string_agg(e.enumlabel, ', ' ORDER BY e.enumsortorder)
+                FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+              )
+              WHEN 'd' THEN format_type(t.typbasetype, t.typtypmod)
+            END
+          FROM
+            pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            LEFT JOIN pg_class c ON c.oid = t.typrelid
+          WHERE
+            n.nspname = $1
+            AND (t.typtype IN ('e', 'd', 'r') OR (t.typtype = 'c' AND c.relkind = 'c'))
+        ) objects
+        ORDER BY
+          kind,
+          name;
+        "#,
+        schema
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// A table, view, column, index, function, sequence or type, as found by the
+/// object search.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SearchObject {
+    /// e.g. `table`, `column`, `index`, `function` or `enum`.
+    pub kind: String,
+    pub schema: String,
+    /// The table of a column or index.
+    pub table: Option<String>,
+    pub name: String,
+    /// A column's type or a function's arguments.
+    pub detail: Option<String>,
+}
+
+/// Every object the search can find, leaving out the system schemas.
+pub async fn get_search_objects(db: &Pool<Postgres>) -> Result<Vec<SearchObject>, sqlx::Error> {
+    sqlx::query_as!(
+        SearchObject,
+        r#"
+        WITH schemas AS (
+          SELECT oid, nspname::text AS nspname
+          FROM pg_namespace
+          WHERE nspname NOT LIKE 'pg\_%' AND nspname <> 'information_schema'
+        )
+        SELECT
+          kind AS "kind!",
+          schema AS "schema!",
+          "table",
+          name AS "name!",
+          detail
+        FROM (
+          SELECT
+            CASE c.relkind
+              WHEN 'v' THEN 'view'
+              WHEN 'm' THEN 'materialized view'
+              WHEN 'f' THEN 'foreign table'
+              WHEN 'S' THEN 'sequence'
+              ELSE 'table'
+            END AS kind,
+            s.nspname AS schema,
+            NULL::text AS "table",
+            c.relname::text AS name,
+            NULL::text AS detail
+          FROM
+            pg_class c
+            JOIN schemas s ON s.oid = c.relnamespace
+          WHERE
+            c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+          UNION ALL
+          SELECT
+            'column',
+            s.nspname,
+            c.relname::text,
+            a.attname::text,
+            format_type(a.atttypid, a.atttypmod)
+          FROM
+            pg_attribute a
+            JOIN pg_class c ON c.oid = a.attrelid
+            JOIN schemas s ON s.oid = c.relnamespace
+          WHERE
+            c.relkind IN ('r', 'p', 'v', 'm', 'f')
+            AND a.attnum > 0
+            AND NOT a.attisdropped
+          UNION ALL
+          SELECT
+            'index',
+            s.nspname,
+            t.relname::text,
+            c.relname::text,
+            NULL
+          FROM
+            pg_index i
+            JOIN pg_class c ON c.oid = i.indexrelid
+            JOIN pg_class t ON t.oid = i.indrelid
+            JOIN schemas s ON s.oid = c.relnamespace
+          UNION ALL
+          SELECT
+            CASE p.prokind
+              WHEN 'p' THEN 'procedure'
+              WHEN 'a' THEN 'aggregate'
+              ELSE 'function'
+            END,
+            s.nspname,
+            NULL,
+            p.proname::text,
+            '(' || pg_get_function_identity_arguments(p.oid) || ')'
+          FROM
+            pg_proc p
+            JOIN schemas s ON s.oid = p.pronamespace
+          UNION ALL
+          SELECT
+            CASE t.typtype
+              WHEN 'e' THEN 'enum'
+              WHEN 'd' THEN 'domain'
+              WHEN 'r' THEN 'range'
+              ELSE 'composite'
+            END,
+            s.nspname,
+            NULL,
+            t.typname::text,
+            NULL
+          FROM
+            pg_type t
+            JOIN schemas s ON s.oid = t.typnamespace
+            LEFT JOIN pg_class c ON c.oid = t.typrelid
+          WHERE
+            t.typtype IN ('e', 'd', 'r') OR (t.typtype = 'c' AND c.relkind = 'c')
+        ) objects
+        ORDER BY
+          schema,
+          "table" NULLS FIRST,
+          name;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_extensions(db: &Pool<Postgres>) -> Result<Vec<SchemaObject>, sqlx::Error> {
+    sqlx::query_as!(
+        SchemaObject,
+        r#"
+        SELECT
+          'extension' AS "kind!",
+          oid AS "oid!",
+          extname::text AS "name!",
+          extversion AS detail
+        FROM
+          pg_extension
+        ORDER BY
+          extname;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_relation_oid(
+    db: &Pool<Postgres>,
+    schema: &str,
+    name: &str,
+) -> Result<Oid, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT
+          c.oid AS "oid!"
+        FROM
+          pg_class c
+          JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE
+          n.nspname = $1
+          AND c.relname = $2;
+        "#,
+        schema,
+        name
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Reconstructs the `CREATE` statement for an object listed by
+/// `get_schema_objects` or `get_extensions`, or for a view or foreign table.
+pub async fn get_object_definition(
+    db: &Pool<Postgres>,
+    kind: &str,
+    oid: Oid,
+) -> Result<String, sqlx::Error> {
+    let definition = match kind {
+        "function" | "procedure" => {
+            sqlx::query_scalar!(r#"SELECT pg_get_functiondef($1) AS "definition!""#, oid)
+                .fetch_one(db)
+                .await?
+        }
+        "aggregate" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    E'CREATE AGGREGATE %s (%s) (\n    SFUNC = %s,\n    STYPE = %s%s%s\n);',
+                    a.aggfnoid::regproc,
+                    pg_get_function_identity_arguments(a.aggfnoid),
+                    a.aggtransfn::regproc,
+                    format_type(a.aggtranstype, NULL),
+                    CASE WHEN a.aggfinalfn <> 0 THEN E',\n    FINALFUNC = ' || a.aggfinalfn::regproc END,
+                    E',\n    INITCOND = ' || quote_literal(a.agginitval)
+                  ) AS "definition!"
+                FROM
+                  pg_aggregate a
+                WHERE
+                  a.aggfnoid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "sequence" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    E'CREATE SEQUENCE %s\n    AS %s\n    INCREMENT BY %s\n    MINVALUE %s\n    MAXVALUE %s\n    START WITH %s\n    CACHE %s%s;',
+                    s.seqrelid::regclass,
+                    format_type(s.seqtypid, NULL),
+                    s.seqincrement,
+                    s.seqmin,
+                    s.seqmax,
+                    s.seqstart,
+                    s.seqcache,
+                    CASE WHEN s.seqcycle THEN E'\n    CYCLE' ELSE '' END
+                  ) AS "definition!"
+                FROM
+                  pg_sequence s
+                WHERE
+                  s.seqrelid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "view" | "materialized view" => {
+            let view = if kind == "view" {
+                "VIEW"
+            } else {
+                "MATERIALIZED VIEW"
+            };
+            let (name, query) = sqlx::query_as::<_, (String, String)>(
+                "SELECT $1::regclass::text, pg_get_viewdef($1, true)",
+            )
+            .bind(oid)
+            .fetch_one(db)
+            .await?;
+            format!("CREATE {view} {name} AS\n{query}")
+        }
+        "enum" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    E'CREATE TYPE %s AS ENUM (\n    %s\n);',
+                    $1::oid::regtype,
+                    string_agg(quote_literal(e.enumlabel), E',\n    ' ORDER BY e.enumsortorder)
+                  ) AS "definition!"
+                FROM
+                  pg_enum e
+                WHERE
+                  e.enumtypid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "domain" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    'CREATE DOMAIN %s AS %s%s%s%s;',
+                    t.oid::regtype,
+                    format_type(t.typbasetype, t.typtypmod),
+                    ' DEFAULT ' || t.typdefault,
+                    CASE WHEN t.typnotnull THEN ' NOT NULL' ELSE '' END,
+                    (
+                      SELECT string_agg(
+                        E'\n    CONSTRAINT ' || quote_ident(con.conname) || ' ' || pg_get_constraintdef(con.oid),
+                        '' ORDER BY con.conname
+                      )
+                      FROM pg_constraint con
+                      WHERE con.contypid = t.oid
+                    )
+                  ) AS "definition!"
+                FROM
+                  pg_type t
+                WHERE
+                  t.oid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "composite" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    E'CREATE TYPE %s AS (\n    %s\n);',
+                    t.oid::regtype,
+                    string_agg(
+                      quote_ident(a.attname) || ' ' || format_type(a.atttypid, a.atttypmod),
+                      E',\n    ' ORDER BY a.attnum
+                    )
+                  ) AS "definition!"
+                FROM
+                  pg_type t
+                  JOIN pg_attribute a ON a.attrelid = t.typrelid
+                WHERE
+                  t.oid = $1
+                  AND a.attnum > 0
+                  AND NOT a.attisdropped
+                GROUP BY
+                  t.oid;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "range" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    'CREATE TYPE %s AS RANGE (SUBTYPE = %s);',
+                    r.rngtypid::regtype,
+                    format_type(r.rngsubtype, NULL)
+                  ) AS "definition!"
+                FROM
+                  pg_range r
+                WHERE
+                  r.rngtypid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "foreign table" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    E'CREATE FOREIGN TABLE %s (\n    %s\n)\nSERVER %I%s;',
+                    c.oid::regclass,
+                    (
+                      SELECT string_agg(
+                        quote_ident(a.attname) || ' ' || format_type(a.atttypid, a.atttypmod)
+                          || CASE WHEN a.attnotnull THEN ' NOT NULL' ELSE '' END,
+                        E',\n    ' ORDER BY a.attnum
+                      )
+                      FROM pg_attribute a
+                      WHERE a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+                    ),
+                    s.srvname,
+                    (
+                      SELECT E'\nOPTIONS (' || string_agg(quote_ident(o.option_name) || ' ' || quote_literal(o.option_value), ', ') || ')'
+                      FROM pg_options_to_table(f.ftoptions) o
+                    )
+                  ) AS "definition!"
+                FROM
+                  pg_foreign_table f
+                  JOIN pg_class c ON c.oid = f.ftrelid
+                  JOIN pg_foreign_server s ON s.oid = f.ftserver
+                WHERE
+                  c.oid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        "extension" => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT
+                  format(
+                    'CREATE EXTENSION IF NOT EXISTS %I WITH SCHEMA %I VERSION %L;',
+                    e.extname,
+                    n.nspname,
+                    e.extversion
+                  ) AS "definition!"
+                FROM
+                  pg_extension e
+                  JOIN pg_namespace n ON n.oid = e.extnamespace
+                WHERE
+                  e.oid = $1;
+                "#,
+                oid
+            )
+            .fetch_one(db)
+            .await?
+        }
+        _ => format!("-- No definition is available for a {kind}"),
+    };
+    Ok(definition)
+}
+
+/// Types a column can be given, for the table designer. Array types are
+/// left out, they are written with `[]`.
+pub async fn get_data_types(db: &Pool<Postgres>) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT
+          format_type(t.oid, NULL) AS "name!"
+        FROM
+          pg_type t
+          JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE
+          t.typtype IN ('b', 'd', 'e', 'r', 'm')
+          AND t.typcategory <> 'A'
+          AND t.typisdefined
+          AND n.nspname NOT IN ('pg_toast', 'information_schema')
+        ORDER BY
+          n.nspname <> 'pg_catalog',
+          1;
+        "#
+    )
+    .fetch_all(db)
+    .await
+}
@@ -0,0 +1,142 @@
+use std::sync::{mpsc::Sender, Arc};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{pool::PoolConnection, Connection, Executor, PgConnection, Postgres};
+use tokio::sync::Mutex;
+
+use crate::sql::{transaction_control, TransactionControl};
+
+/// A pooled connection pinned to the editor so consecutive runs share one session.
+pub type EditorSession = Arc<Mutex<Option<PinnedSession>>>;
+
+pub struct PinnedSession {
+    pub conn: PoolConnection<Postgres>,
+    /// Kept up to date from the outcome of every statement run on `conn`.
+    pub status: TransactionStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionStatus {
+    #[default]
+    Idle,
+    InTransaction,
+    Failed,
+}
+
+impl TransactionStatus {
+    pub fn is_open(&self) -> bool {
+        *self != TransactionStatus::Idle
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransactionStatus::Idle => "Idle",
+            TransactionStatus::InTransaction => "In transaction",
+            TransactionStatus::Failed => "Failed transaction",
+        }
+    }
+
+    /// The status after running `sql`, following the server's rules: any error
+    /// inside a transaction block aborts it, and ending the block always leaves
+    /// the session idle, even when `COMMIT` fails.
+    pub fn after(self, sql: &str, succeeded: bool) -> TransactionStatus {
+        match (transaction_control(sql), succeeded) {
+            (Some(TransactionControl::End { chain: true }), true) => {
+                TransactionStatus::InTransaction
+            }
+            (Some(TransactionControl::End { .. }), _) => TransactionStatus::Idle,
+            (Some(TransactionControl::Begin), true) => TransactionStatus::InTransaction,
+            (Some(TransactionControl::RollbackToSavepoint), true) => {
+                TransactionStatus::InTransaction
+            }
+            (_, true) => self,
+            (_, false) if self.is_open() => TransactionStatus::Failed,
+            (_, false) => TransactionStatus::Idle,
+        }
+    }
+}
+
+/// Commits or rolls back the editor's transaction.
+pub async fn end_transaction(
+    session: EditorSession,
+    commit: bool,
+    status_tx: Sender<TransactionStatus>,
+) -> Result<(), String> {
+    let mut session = session.lock().await;
+    let Some(pinned) = session.as_mut() else {
+        return Ok(());
+    };
+
+    let statement = if commit { "COMMIT" } else { "ROLLBACK" };
+    let result = sqlx::query(statement).execute(&mut *pinned.conn).await;
+    pinned.status = pinned.status.after(statement, result.is_ok());
+    let _ = status_tx.send(pinned.status);
+    if result.is_err() && pinned.conn.ping().await.is_err() {
+        *session = None;
+    }
+    result
+        .map(|_| ())
+        .map_err(|err| format!("{statement} failed: {err}"))
+}
+
+/// Settings applied to every session a connection opens. Empty values are left
+/// at the server's default.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// Defaults to "Rosemary" when empty.
+    pub application_name: String,
+    pub search_path: String,
+    pub role: String,
+    pub statement_timeout: String,
+    pub lock_timeout: String,
+    pub idle_in_transaction_session_timeout: String,
+    pub time_zone: String,
+    /// Run after the settings above, e.g. `SET work_mem = '64MB';`.
+    pub extra_statements: String,
+}
+
+impl SessionSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn settings(&self) -> Vec<(&'static str, String)> {
+        [
+            ("role", &self.role),
+            ("search_path", &self.search_path),
+            ("statement_timeout", &self.statement_timeout),
+            ("lock_timeout", &self.lock_timeout),
+            (
+                "idle_in_transaction_session_timeout",
+                &self.idle_in_transaction_session_timeout,
+            ),
+            ("TimeZone", &self.time_zone),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name, value.trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+}
+
+/// Applies the settings to a new session. `set_config` is used rather than
+/// `SET` so values don't need quoting; setting `role` is the same as `SET ROLE`.
+pub async fn apply_session_settings(
+    conn: &mut PgConnection,
+    settings: &SessionSettings,
+) -> Result<(), sqlx::Error> {
+    for (name, value) in settings.settings() {
+        sqlx::query("SELECT set_config($1, $2, false)")
+            .bind(name)
+            .bind(value)
+            .execute(&mut *conn)
+            .await?;
+    }
+    if !settings.extra_statements.trim().is_empty() {
+        // A plain string runs through the simple query protocol, which allows
+        // several statements.
+        conn.execute(settings.extra_statements.as_str()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,896 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    app::new_connection_id,
+    query_functions::pg_compare::{SchemaSnapshot, SnapshotColumn, SnapshotObject},
+    sql::{qualified_name, quote_identifier},
+};
+
+/// The groups of the compare tree, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiffKind {
+    Schema,
+    Type,
+    Sequence,
+    Function,
+    Table,
+    Column,
+    Constraint,
+    Index,
+    View,
+    Grant,
+}
+
+impl DiffKind {
+    pub fn of(kind: &str) -> Self {
+        match kind {
+            "schema" => Self::Schema,
+            "enum" | "domain" | "composite" | "range" => Self::Type,
+            "sequence" => Self::Sequence,
+            "function" | "procedure" => Self::Function,
+            "column" => Self::Column,
+            "constraint" => Self::Constraint,
+            "index" => Self::Index,
+            "view" | "materialized view" => Self::View,
+            "grant" => Self::Grant,
+            _ => Self::Table,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Schema => "Schemas",
+            Self::Type => "Types",
+            Self::Sequence => "Sequences",
+            Self::Function => "Functions",
+            Self::Table => "Tables",
+            Self::Column => "Columns",
+            Self::Constraint => "Constraints",
+            Self::Index => "Indexes",
+            Self::View => "Views",
+            Self::Grant => "Grants",
+        }
+    }
+
+    /// Columns, constraints and indexes are shown under their table.
+    pub fn is_table_part(self) -> bool {
+        matches!(self, Self::Column | Self::Constraint | Self::Index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStatus {
+    /// Only in the source, so it is created in the target.
+    Missing,
+    /// Only in the target, so it is dropped.
+    Extra,
+    Changed,
+    Same,
+}
+
+impl DiffStatus {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Missing => "＋",
+            Self::Extra => "－",
+            Self::Changed => "≠",
+            Self::Same => "＝",
+        }
+    }
+}
+
+/// An object in the source, the target or both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub kind: DiffKind,
+    pub schema: String,
+    pub table: String,
+    pub name: String,
+    pub source: Option<SnapshotObject>,
+    pub target: Option<SnapshotObject>,
+    /// Whether the migration script brings this object in line.
+    pub included: bool,
+}
+
+impl DiffEntry {
+    pub fn status(&self) -> DiffStatus {
+        match (&self.source, &self.target) {
+            (Some(_), None) => DiffStatus::Missing,
+            (None, Some(_)) => DiffStatus::Extra,
+            (Some(source), Some(target)) if source != target => DiffStatus::Changed,
+            _ => DiffStatus::Same,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.kind {
+            DiffKind::Schema => quote_identifier(&self.name),
+            DiffKind::Function => self.object_name(),
+            DiffKind::Grant => format!("{} to {}", self.table, self.name),
+            kind if kind.is_table_part() => quote_identifier(&self.name),
+            _ => qualified_name(&self.schema, &self.name),
+        }
+    }
+
+    fn object_kind(&self) -> &str {
+        self.source
+            .as_ref()
+            .or(self.target.as_ref())
+            .map_or("", |object| object.kind.as_str())
+    }
+
+    fn relation(&self) -> String {
+        qualified_name(&self.schema, &self.table)
+    }
+
+    /// The name as written in statements.
+    fn object_name(&self) -> String {
+        match self.kind {
+            // Functions are named with their arguments, already quoted.
+            DiffKind::Function => format!("{}.{}", quote_identifier(&self.schema), self.name),
+            DiffKind::Schema => quote_identifier(&self.name),
+            _ => qualified_name(&self.schema, &self.name),
+        }
+    }
+}
+
+/// How a column is written in `CREATE TABLE` and `ADD COLUMN`.
+pub fn column_definition(column: &SnapshotColumn) -> String {
+    let mut definition = column.data_type.clone();
+    if let Some(collation) = &column.collation {
+        definition += &format!(" COLLATE {collation}");
+    }
+    match column.identity.as_str() {
+        "a" => definition += " GENERATED ALWAYS AS IDENTITY",
+        "d" => definition += " GENERATED BY DEFAULT AS IDENTITY",
+        _ => {}
+    }
+    if !column.generated.is_empty() {
+        definition += &format!(" GENERATED ALWAYS AS ({}) STORED", column.generated);
+    } else if let Some(default_value) = &column.default_value {
+        definition += &format!(" DEFAULT {default_value}");
+    }
+    if column.not_null {
+        definition += " NOT NULL";
+    }
+    definition
+}
+
+fn column_objects(snapshot: &SchemaSnapshot) -> impl Iterator<Item = SnapshotObject> + '_ {
+    snapshot.columns.iter().map(|column| SnapshotObject {
+        kind: String::from("column"),
+        schema: column.schema.clone(),
+        table: column.table.clone(),
+        name: column.name.clone(),
+        definition: column_definition(column),
+    })
+}
+
+type EntryKey = (DiffKind, String, String, String);
+
+/// Matches the objects of two snapshots by kind and name. Entries are sorted
+/// by kind, schema, table and name, and all differences are included in the
+/// migration script to begin with.
+pub fn compare_schemas(source: &SchemaSnapshot, target: &SchemaSnapshot) -> Vec<DiffEntry> {
+    let mut entries: BTreeMap<EntryKey, DiffEntry> = BTreeMap::new();
+    let mut add = |object: SnapshotObject, is_source: bool| {
+        let kind = DiffKind::of(&object.kind);
+        let key = (
+            kind,
+            object.schema.clone(),
+            object.table.clone(),
+            object.name.clone(),
+        );
+        let entry = entries.entry(key).or_insert_with(|| DiffEntry {
+            kind,
+            schema: object.schema.clone(),
+            table: object.table.clone(),
+            name: object.name.clone(),
+            source: None,
+            target: None,
+            included: false,
+        });
+        if is_source {
+            entry.source = Some(object);
+        } else {
+            entry.target = Some(object);
+        }
+    };
+    for object in source.objects.iter().cloned().chain(column_objects(source)) {
+        add(object, true);
+    }
+    for object in target.objects.iter().cloned().chain(column_objects(target)) {
+        add(object, false);
+    }
+    entries
+        .into_values()
+        .map(|mut entry| {
+            entry.included = entry.status() != DiffStatus::Same;
+            entry
+        })
+        .collect()
+}
+
+/// Labels of an enum, from the definition written by `get_object_definition`.
+fn enum_labels(definition: &str) -> Vec<&str> {
+    definition
+        .lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| line.starts_with('\''))
+        .collect()
+}
+
+/// Comments out a definition that can't be changed automatically.
+fn manual_change(entry: &DiffEntry, definition: &str) -> String {
+    let mut comment = format!(
+        "-- {} differs and has to be changed by hand. In the source it is:",
+        entry.label()
+    );
+    for line in definition.lines() {
+        comment += &format!("\n-- {line}");
+    }
+    comment
+}
+
+fn find_column<'a>(snapshot: &'a SchemaSnapshot, entry: &DiffEntry) -> Option<&'a SnapshotColumn> {
+    snapshot.columns.iter().find(|column| {
+        column.schema == entry.schema && column.table == entry.table && column.name == entry.name
+    })
+}
+
+fn alter_column(
+    entry: &DiffEntry,
+    source: &SnapshotColumn,
+    target: &SnapshotColumn,
+) -> Vec<String> {
+    let table = entry.relation();
+    let name = quote_identifier(&source.name);
+    let alter = |change: String| format!("ALTER TABLE {table} ALTER COLUMN {name} {change};");
+    let mut statements = Vec::new();
+
+    if source.generated != target.generated {
+        statements.push(manual_change(entry, &column_definition(source)));
+        return statements;
+    }
+    if source.data_type != target.data_type || source.collation != target.collation {
+        let collation = source
+            .collation
+            .as_ref()
+            .map(|collation| format!(" COLLATE {collation}"))
+            .unwrap_or_default();
+        statements.push(alter(format!(
+            "TYPE {}{collation} USING {name}::{}",
+            source.data_type, source.data_type
+        )));
+    }
+    if source.identity != target.identity && !target.identity.is_empty() {
+        if source.identity.is_empty() {
+            statements.push(alter(String::from("DROP IDENTITY")));
+        } else {
+            let generated = if source.identity == "a" {
+                "ALWAYS"
+            } else {
+                "BY DEFAULT"
+            };
+            statements.push(alter(format!("SET GENERATED {generated}")));
+        }
+    }
+    if source.default_value != target.default_value {
+        statements.push(match &source.default_value {
+            Some(default_value) => alter(format!("SET DEFAULT {default_value}")),
+            None => alter(String::from("DROP DEFAULT")),
+        });
+    }
+    if source.not_null != target.not_null {
+        statements.push(alter(String::from(if source.not_null {
+            "SET NOT NULL"
+        } else {
+            "DROP NOT NULL"
+        })));
+    }
+    // Identity columns have to be NOT NULL first.
+    if source.identity != target.identity && target.identity.is_empty() {
+        let generated = if source.identity == "a" {
+            "ALWAYS"
+        } else {
+            "BY DEFAULT"
+        };
+        statements.push(alter(format!("ADD GENERATED {generated} AS IDENTITY")));
+    }
+    statements
+}
+
+/// The `REVOKE` statements for privileges the target has and the source
+/// doesn't, or the `GRANT` statements for the other way round. When the
+/// object is recreated all of the source's privileges are granted.
+fn grant_statements(entry: &DiffEntry, revoke: bool, recreated: bool) -> Vec<String> {
+    let privileges = |object: &Option<SnapshotObject>| -> BTreeSet<String> {
+        object
+            .iter()
+            .flat_map(|object| object.definition.split(", "))
+            .filter(|privilege| !privilege.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    let source = privileges(&entry.source);
+    let target = if recreated {
+        BTreeSet::new()
+    } else {
+        privileges(&entry.target)
+    };
+    if revoke {
+        target
+            .difference(&source)
+            .map(|privilege| {
+                let privilege = privilege.trim_end_matches(" WITH GRANT OPTION");
+                format!("REVOKE {privilege} ON {} FROM {};", entry.table, entry.name)
+            })
+            .collect()
+    } else {
+        source
+            .difference(&target)
+            .map(
+                |privilege| match privilege.strip_suffix(" WITH GRANT OPTION") {
+                    Some(privilege) => format!(
+                        "GRANT {privilege} ON {} TO {} WITH GRANT OPTION;",
+                        entry.table, entry.name
+                    ),
+                    None => format!("GRANT {privilege} ON {} TO {};", entry.table, entry.name),
+                },
+            )
+            .collect()
+    }
+}
+
+/// The statements that bring the target in line with the source, for the
+/// included entries. Things that are dropped go first, in reverse order of
+/// dependency, then things that are created or changed.
+pub fn migration_script(
+    entries: &[DiffEntry],
+    source: &SchemaSnapshot,
+    target: &SchemaSnapshot,
+) -> Vec<String> {
+    let included: Vec<&DiffEntry> = entries.iter().filter(|entry| entry.included).collect();
+    let of_kind = |kind: DiffKind| {
+        included
+            .iter()
+            .copied()
+            .filter(move |entry| entry.kind == kind)
+    };
+    let with_status = |kind: DiffKind, statuses: &'static [DiffStatus]| {
+        of_kind(kind).filter(move |entry| statuses.contains(&entry.status()))
+    };
+    const EXTRA: &[DiffStatus] = &[DiffStatus::Extra];
+    const MISSING: &[DiffStatus] = &[DiffStatus::Missing];
+    const CHANGED: &[DiffStatus] = &[DiffStatus::Changed];
+    const EXTRA_OR_CHANGED: &[DiffStatus] = &[DiffStatus::Extra, DiffStatus::Changed];
+    const MISSING_OR_CHANGED: &[DiffStatus] = &[DiffStatus::Missing, DiffStatus::Changed];
+
+    // Parts of tables that are dropped or created go with the table.
+    let dropped_tables: BTreeSet<(&str, &str)> = with_status(DiffKind::Table, EXTRA)
+        .map(|entry| (entry.schema.as_str(), entry.name.as_str()))
+        .collect();
+    let created_tables: BTreeSet<(&str, &str)> = with_status(DiffKind::Table, MISSING)
+        .map(|entry| (entry.schema.as_str(), entry.name.as_str()))
+        .collect();
+    let on_dropped_table = |entry: &&DiffEntry| {
+        dropped_tables.contains(&(entry.schema.as_str(), entry.table.as_str()))
+    };
+    let is_foreign_key = |object: &Option<SnapshotObject>| {
+        object
+            .as_ref()
+            .is_some_and(|object| object.definition.starts_with("FOREIGN KEY"))
+    };
+
+    let mut statements = Vec::new();
+
+    for entry in of_kind(DiffKind::Grant) {
+        statements.extend(grant_statements(entry, true, false));
+    }
+
+    // Changed views are dropped and created again, as they would otherwise
+    // stop columns they use from being changed.
+    let recreated_views: BTreeSet<String> = with_status(DiffKind::View, CHANGED)
+        .map(|entry| format!("TABLE {}", entry.object_name()))
+        .collect();
+    for entry in with_status(DiffKind::View, EXTRA_OR_CHANGED) {
+        let keyword = match entry.target.as_ref().map(|target| target.kind.as_str()) {
+            Some("view") => "VIEW",
+            _ => "MATERIALIZED VIEW",
+        };
+        statements.push(format!("DROP {keyword} {};", entry.object_name()));
+    }
+    for entry in of_kind(DiffKind::Function) {
+        let kind_changed = entry.status() == DiffStatus::Changed
+            && entry.source.as_ref().map(|o| &o.kind) != entry.target.as_ref().map(|o| &o.kind);
+        if entry.status() == DiffStatus::Extra || kind_changed {
+            let target_kind = entry
+                .target
+                .as_ref()
+                .map_or("", |target| target.kind.as_str());
+            statements.push(format!(
+                "DROP {} {};",
+                target_kind.to_uppercase(),
+                entry.object_name()
+            ));
+        }
+    }
+    let constraints: Vec<&DiffEntry> = with_status(DiffKind::Constraint, EXTRA_OR_CHANGED)
+        .filter(|entry| !on_dropped_table(entry))
+        .collect();
+    for entry in constraints
+        .iter()
+        .filter(|entry| is_foreign_key(&entry.target))
+        .chain(
+            constraints
+                .iter()
+                .filter(|entry| !is_foreign_key(&entry.target)),
+        )
+    {
+        statements.push(format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};",
+            entry.relation(),
+            quote_identifier(&entry.name)
+        ));
+    }
+    for entry in with_status(DiffKind::Index, EXTRA_OR_CHANGED).filter(|e| !on_dropped_table(e)) {
+        statements.push(format!("DROP INDEX {};", entry.object_name()));
+    }
+    for entry in with_status(DiffKind::Column, EXTRA).filter(|e| !on_dropped_table(e)) {
+        statements.push(format!(
+            "ALTER TABLE {} DROP COLUMN {};",
+            entry.relation(),
+            quote_identifier(&entry.name)
+        ));
+    }
+    for entry in with_status(DiffKind::Table, EXTRA) {
+        statements.push(format!("DROP TABLE {};", entry.object_name()));
+    }
+    for entry in with_status(DiffKind::Sequence, EXTRA) {
+        statements.push(format!("DROP SEQUENCE {};", entry.object_name()));
+    }
+    for entry in with_status(DiffKind::Type, EXTRA) {
+        let keyword = if entry.object_kind() == "domain" {
+            "DOMAIN"
+        } else {
+            "TYPE"
+        };
+        statements.push(format!("DROP {keyword} {};", entry.object_name()));
+    }
+    for entry in with_status(DiffKind::Schema, EXTRA) {
+        statements.push(format!("DROP SCHEMA {};", entry.object_name()));
+    }
+
+    for entry in with_status(DiffKind::Schema, MISSING) {
+        statements.push(format!("CREATE SCHEMA {};", entry.object_name()));
+    }
+    for entry in with_status(DiffKind::Type, MISSING_OR_CHANGED) {
+        let (Some(source), target) = (&entry.source, &entry.target) else {
+            continue;
+        };
+        let Some(target) = target else {
+            statements.push(source.definition.clone());
+            continue;
+        };
+        let (new_labels, old_labels) = (
+            enum_labels(&source.definition),
+            enum_labels(&target.definition),
+        );
+        // Labels can be added to an enum, anything else is left to the user.
+        let only_added = source.kind == "enum"
+            && target.kind == "enum"
+            && new_labels
+                .iter()
+                .filter(|label| old_labels.contains(label))
+                .eq(old_labels.iter());
+        if only_added {
+            let mut previous: Option<&str> = None;
+            for label in &new_labels {
+                if !old_labels.contains(label) {
+                    let position = match previous {
+                        Some(previous) => format!(" AFTER {previous}"),
+                        None => old_labels
+                            .first()
+                            .map(|first| format!(" BEFORE {first}"))
+                            .unwrap_or_default(),
+                    };
+                    statements.push(format!(
+                        "ALTER TYPE {} ADD VALUE {label}{position};",
+                        entry.object_name()
+                    ));
+                }
+                previous = Some(label);
+            }
+        } else {
+            statements.push(manual_change(entry, &source.definition));
+        }
+    }
+    for entry in with_status(DiffKind::Sequence, MISSING_OR_CHANGED) {
+        let verb = if entry.target.is_some() {
+            "ALTER"
+        } else {
+            "CREATE"
+        };
+        let definition = entry.source.as_ref().map_or("", |o| o.definition.as_str());
+        statements.push(format!(
+            "{verb} SEQUENCE {} {definition};",
+            entry.object_name()
+        ));
+    }
+
+    let functions: Vec<&DiffEntry> = with_status(DiffKind::Function, MISSING_OR_CHANGED).collect();
+    if !functions.is_empty() {
+        // Functions can refer to tables that are created further down.
+        statements.push(String::from("SET check_function_bodies = false;"));
+    }
+    for entry in functions {
+        if let Some(source) = &entry.source {
+            statements.push(format!("{};", source.definition.trim_end()));
+        }
+    }
+
+    for entry in with_status(DiffKind::Table, MISSING_OR_CHANGED) {
+        let Some(definition) = entry.source.as_ref().map(|o| o.definition.as_str()) else {
+            continue;
+        };
+        if entry.target.is_some() {
+            statements.push(manual_change(
+                entry,
+                &format!("CREATE TABLE {} (...) {definition}", entry.object_name()),
+            ));
+        } else if definition.starts_with("PARTITION OF") {
+            statements.push(format!(
+                "CREATE TABLE {} {definition};",
+                entry.object_name()
+            ));
+        } else {
+            let columns: Vec<String> = source
+                .columns
+                .iter()
+                .filter(|c| c.schema == entry.schema && c.table == entry.name)
+                .map(|c| format!("    {} {}", quote_identifier(&c.name), column_definition(c)))
+                .collect();
+            let suffix = if definition.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", definition.trim())
+            };
+            statements.push(format!(
+                "CREATE TABLE {} (\n{}\n){suffix};",
+                entry.object_name(),
+                columns.join(",\n")
+            ));
+        }
+    }
+    for entry in of_kind(DiffKind::Column) {
+        match entry.status() {
+            DiffStatus::Missing
+                if !created_tables.contains(&(entry.schema.as_str(), entry.table.as_str())) =>
+            {
+                if let Some(column) = find_column(source, entry) {
+                    statements.push(format!(
+                        "ALTER TABLE {} ADD COLUMN {} {};",
+                        entry.relation(),
+                        quote_identifier(&column.name),
+                        column_definition(column)
+                    ));
+                }
+            }
+            DiffStatus::Changed => {
+                if let (Some(source), Some(target)) =
+                    (find_column(source, entry), find_column(target, entry))
+                {
+                    statements.extend(alter_column(entry, source, target));
+                }
+            }
+            _ => {}
+        }
+    }
+    let constraints: Vec<&DiffEntry> =
+        with_status(DiffKind::Constraint, MISSING_OR_CHANGED).collect();
+    for entry in constraints
+        .iter()
+        .filter(|entry| !is_foreign_key(&entry.source))
+        .chain(
+            constraints
+                .iter()
+                .filter(|entry| is_foreign_key(&entry.source)),
+        )
+    {
+        if let Some(source) = &entry.source {
+            statements.push(format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} {};",
+                entry.relation(),
+                quote_identifier(&entry.name),
+                source.definition
+            ));
+        }
+    }
+    for entry in with_status(DiffKind::Index, MISSING_OR_CHANGED) {
+        if let Some(source) = &entry.source {
+            statements.push(format!("{};", source.definition));
+        }
+    }
+    for entry in with_status(DiffKind::View, MISSING_OR_CHANGED) {
+        let Some(source) = &entry.source else {
+            continue;
+        };
+        let query = source.definition.trim_end().trim_end_matches(';');
+        let create = if source.kind == "view" {
+            "CREATE VIEW"
+        } else {
+            "CREATE MATERIALIZED VIEW"
+        };
+        statements.push(format!("{create} {} AS\n{query};", entry.object_name()));
+    }
+    for entry in &included {
+        if entry.kind == DiffKind::Grant {
+            let recreated = recreated_views.contains(&entry.table);
+            statements.extend(grant_statements(entry, false, recreated));
+        }
+    }
+    // Grants on recreated views are lost with the old view, so they are made
+    // again even when nothing about them changed.
+    for entry in entries {
+        if entry.kind == DiffKind::Grant
+            && !entry.included
+            && recreated_views.contains(&entry.table)
+        {
+            statements.extend(grant_statements(entry, false, true));
+        }
+    }
+    statements
+}
+
+/// Comparing the schemas of two live connections.
+#[derive(Debug)]
+pub struct SchemaCompare {
+    /// Tells results for this compare apart from ones for an earlier one.
+    pub id: String,
+    pub source_key: String,
+    pub target_key: String,
+    pub loading: bool,
+    pub snapshots: Option<(SchemaSnapshot, SchemaSnapshot)>,
+    pub entries: Vec<DiffEntry>,
+    pub show_identical: bool,
+    /// Index of the entry whose definitions are shown side by side.
+    pub selected: Option<usize>,
+    pub error: Option<String>,
+    /// The migration script, with the `included` flags it was written for.
+    script_cache: Option<(Vec<bool>, String)>,
+}
+
+impl SchemaCompare {
+    pub fn new(source_key: &str, target_key: &str) -> Self {
+        Self {
+            id: new_connection_id(),
+            source_key: source_key.to_string(),
+            target_key: target_key.to_string(),
+            loading: false,
+            snapshots: None,
+            entries: Vec::new(),
+            show_identical: false,
+            selected: None,
+            error: None,
+            script_cache: None,
+        }
+    }
+
+    pub fn set_snapshots(&mut self, source: SchemaSnapshot, target: SchemaSnapshot) {
+        self.entries = compare_schemas(&source, &target);
+        self.snapshots = Some((source, target));
+        self.selected = None;
+        self.script_cache = None;
+    }
+
+    pub fn script(&self) -> Vec<String> {
+        match &self.snapshots {
+            Some((source, target)) => migration_script(&self.entries, source, target),
+            None => Vec::new(),
+        }
+    }
+
+    /// The script as one string, written again only when the entries it
+    /// includes change.
+    pub fn script_text(&mut self) -> &str {
+        let included: Vec<bool> = self.entries.iter().map(|entry| entry.included).collect();
+        if self
+            .script_cache
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != included)
+        {
+            self.script_cache = Some((included, self.script().join("\n")));
+        }
+        self.script_cache
+            .as_ref()
+            .map_or("", |(_, script)| script.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(kind: &str, table: &str, name: &str, definition: &str) -> SnapshotObject {
+        SnapshotObject {
+            kind: kind.to_string(),
+            schema: String::from("public"),
+            table: table.to_string(),
+            name: name.to_string(),
+            definition: definition.to_string(),
+        }
+    }
+
+    fn column(table: &str, name: &str, data_type: &str, not_null: bool) -> SnapshotColumn {
+        SnapshotColumn {
+            schema: String::from("public"),
+            table: table.to_string(),
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            collation: None,
+            default_value: None,
+            not_null,
+            identity: String::new(),
+            generated: String::new(),
+        }
+    }
+
+    fn snapshots() -> (SchemaSnapshot, SchemaSnapshot) {
+        let source = SchemaSnapshot {
+            objects: vec![
+                object("schema", "", "public", ""),
+                object("table", "", "customers", ""),
+                object("table", "", "orders", ""),
+                object("constraint", "orders", "orders_pkey", "PRIMARY KEY (id)"),
+                object(
+                    "constraint",
+                    "orders",
+                    "orders_customer_id_fkey",
+                    "FOREIGN KEY (customer_id) REFERENCES customers(id)",
+                ),
+                object(
+                    "enum",
+                    "",
+                    "status",
+                    "CREATE TYPE public.status AS ENUM (\n    'new',\n    'paid',\n    'sent'\n);",
+                ),
+                object(
+                    "grant",
+                    "TABLE public.customers",
+                    "reader",
+                    "INSERT, SELECT",
+                ),
+            ],
+            columns: vec![
+                column("customers", "id", "bigint", true),
+                column("customers", "name", "text", true),
+                column("orders", "id", "bigint", true),
+                column("orders", "customer_id", "bigint", false),
+            ],
+        };
+        let target = SchemaSnapshot {
+            objects: vec![
+                object("schema", "", "public", ""),
+                object("table", "", "customers", ""),
+                object(
+                    "index",
+                    "customers",
+                    "customers_name",
+                    "CREATE INDEX customers_name ON public.customers USING btree (name)",
+                ),
+                object(
+                    "enum",
+                    "",
+                    "status",
+                    "CREATE TYPE public.status AS ENUM (\n    'new',\n    'sent'\n);",
+                ),
+                object(
+                    "grant",
+                    "TABLE public.customers",
+                    "reader",
+                    "SELECT, UPDATE",
+                ),
+                object("table", "", "old", ""),
+                object("constraint", "old", "old_pkey", "PRIMARY KEY (id)"),
+            ],
+            columns: vec![
+                column("customers", "id", "bigint", true),
+                column("customers", "name", "character varying(100)", false),
+                column("customers", "legacy", "text", false),
+                column("old", "id", "integer", true),
+            ],
+        };
+        (source, target)
+    }
+
+    #[test]
+    fn matches_objects_by_kind_and_name() {
+        let (source, target) = snapshots();
+        let entries = compare_schemas(&source, &target);
+        let status = |kind: DiffKind, table: &str, name: &str| {
+            entries
+                .iter()
+                .find(|e| e.kind == kind && e.table == table && e.name == name)
+                .map(|e| (e.status(), e.included))
+        };
+        assert_eq!(
+            status(DiffKind::Schema, "", "public"),
+            Some((DiffStatus::Same, false))
+        );
+        assert_eq!(
+            status(DiffKind::Table, "", "orders"),
+            Some((DiffStatus::Missing, true))
+        );
+        assert_eq!(
+            status(DiffKind::Table, "", "old"),
+            Some((DiffStatus::Extra, true))
+        );
+        assert_eq!(
+            status(DiffKind::Column, "customers", "name"),
+            Some((DiffStatus::Changed, true))
+        );
+        assert_eq!(
+            status(DiffKind::Type, "", "status"),
+            Some((DiffStatus::Changed, true))
+        );
+        // Entries come grouped by kind.
+        assert!(entries.windows(2).all(|pair| pair[0].kind <= pair[1].kind));
+    }
+
+    #[test]
+    fn builds_a_migration_script() {
+        let (source, target) = snapshots();
+        let entries = compare_schemas(&source, &target);
+        assert_eq!(
+            migration_script(&entries, &source, &target),
+            [
+                "REVOKE UPDATE ON TABLE public.customers FROM reader;",
+                "DROP INDEX public.customers_name;",
+                "ALTER TABLE public.customers DROP COLUMN legacy;",
+                "DROP TABLE public.old;",
+                "ALTER TYPE public.status ADD VALUE 'paid' AFTER 'new';",
+                "CREATE TABLE public.orders (\n    id bigint NOT NULL,\n    customer_id bigint\n);",
+                "ALTER TABLE public.customers ALTER COLUMN name TYPE text USING name::text;",
+                "ALTER TABLE public.customers ALTER COLUMN name SET NOT NULL;",
+                "ALTER TABLE public.orders ADD CONSTRAINT orders_pkey PRIMARY KEY (id);",
+                "ALTER TABLE public.orders ADD CONSTRAINT orders_customer_id_fkey \
+                 FOREIGN KEY (customer_id) REFERENCES customers(id);",
+                "GRANT INSERT ON TABLE public.customers TO reader;",
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_out_excluded_entries_and_reordered_enums() {
+        let (mut source, target) = snapshots();
+        source.objects[5].definition =
+            String::from("CREATE TYPE public.status AS ENUM (\n    'sent',\n    'new'\n);");
+        let mut entries = compare_schemas(&source, &target);
+        for entry in &mut entries {
+            entry.included = entry.kind == DiffKind::Type;
+        }
+        let script = migration_script(&entries, &source, &target);
+        assert_eq!(script.len(), 1);
+        assert!(script[0].starts_with("-- public.status differs and has to be changed by hand."));
+        assert!(script[0].ends_with("\n--     'new'\n-- );"));
+    }
+
+    #[test]
+    fn rewrites_the_cached_script_when_entries_are_excluded() {
+        let (source, target) = snapshots();
+        let mut compare = SchemaCompare::new("a", "b");
+        compare.set_snapshots(source, target);
+        let full = compare.script_text().to_string();
+        assert_eq!(full, compare.script().join("\n"));
+
+        let idx = compare
+            .entries
+            .iter()
+            .position(|entry| entry.status() != DiffStatus::Same)
+            .unwrap();
+        compare.entries[idx].included = false;
+        let partial = compare.script_text().to_string();
+        assert_ne!(partial, full);
+        assert_eq!(partial, compare.script().join("\n"));
+    }
+}
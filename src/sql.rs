@@ -0,0 +1,673 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    QuotedIdentifier,
+    String,
+    Number,
+    Comment,
+    Whitespace,
+    PositionalParam,
+    NamedParam,
+    Symbol,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn is_word_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+/// Splits SQL into tokens, keeping strings, quoted identifiers, dollar quotes
+/// and comments intact so callers can safely look for keywords and placeholders.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // Open `[`s and the last byte of code before the current token, so array
+    // slices such as `arr[1:n]` aren't read as named parameters.
+    let mut brackets = 0usize;
+    let mut previous = None;
+
+    while i < bytes.len() {
+        let start = i;
+        let b = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        let kind = if b.is_ascii_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            TokenKind::Whitespace
+        } else if b == b'-' && next == Some(b'-') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            TokenKind::Comment
+        } else if b == b'/' && next == Some(b'*') {
+            let mut depth = 0;
+            while i < bytes.len() {
+                if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+                    depth += 1;
+                    i += 2;
+                } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            TokenKind::Comment
+        } else if b == b'\'' {
+            i = scan_quoted(bytes, i, b'\'', false);
+            TokenKind::String
+        } else if b == b'"' {
+            i = scan_quoted(bytes, i, b'"', false);
+            TokenKind::QuotedIdentifier
+        } else if b == b'$' && next.is_some_and(|n| n.is_ascii_digit()) {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            TokenKind::PositionalParam
+        } else if b == b'$' {
+            match dollar_quote_end(sql, i) {
+                Some(end) => {
+                    i = end;
+                    TokenKind::String
+                }
+                None => {
+                    i += 1;
+                    TokenKind::Symbol
+                }
+            }
+        } else if b == b':' && next == Some(b':') {
+            i += 2;
+            TokenKind::Symbol
+        } else if b == b':'
+            && next.is_some_and(is_word_start)
+            && brackets == 0
+            && !previous.is_some_and(|p: u8| p.is_ascii_digit() || p == b'[')
+        {
+            i += 1;
+            while i < bytes.len() && is_word_byte(bytes[i]) && bytes[i] != b'$' {
+                i += 1;
+            }
+            TokenKind::NamedParam
+        } else if b.is_ascii_digit() || (b == b'.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                i += 1;
+            }
+            TokenKind::Number
+        } else if is_word_start(b) {
+            while i < bytes.len() && is_word_byte(bytes[i]) {
+                i += 1;
+            }
+            // String constants with a prefix, e.g. E'\n', B'101' or X'1F'.
+            let prefix = &sql[start..i];
+            if bytes.get(i) == Some(&b'\'')
+                && matches!(prefix, "E" | "e" | "B" | "b" | "X" | "x" | "N" | "n")
+            {
+                i = scan_quoted(bytes, i, b'\'', prefix.eq_ignore_ascii_case("e"));
+                TokenKind::String
+            } else {
+                TokenKind::Word
+            }
+        } else {
+            match b {
+                b'[' => brackets += 1,
+                b']' => brackets = brackets.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+            TokenKind::Symbol
+        };
+
+        if !matches!(kind, TokenKind::Whitespace | TokenKind::Comment) {
+            previous = Some(bytes[i - 1]);
+        }
+        tokens.push(Token {
+            kind,
+            text: &sql[start..i],
+            start,
+        });
+    }
+
+    tokens
+}
+
+fn scan_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if backslash_escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn dollar_quote_end(sql: &str, start: usize) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let mut i = start + 1;
+    if i < bytes.len() && is_word_start(bytes[i]) {
+        while i < bytes.len() && is_word_byte(bytes[i]) && bytes[i] != b'$' {
+            i += 1;
+        }
+    }
+    if bytes.get(i) != Some(&b'$') {
+        return None;
+    }
+    let tag = &sql[start..=i];
+    let body_start = i + 1;
+    Some(
+        sql[body_start..]
+            .find(tag)
+            .map_or(sql.len(), |pos| body_start + pos + tag.len()),
+    )
+}
+
+/// Rewrites `:name` placeholders to positional ones and returns the placeholder
+/// names in bind order. Positional `$n` placeholders keep their numbers and
+/// named ones are numbered after them.
+pub fn prepare_placeholders(sql: &str) -> (String, Vec<String>) {
+    let tokens = tokenize(sql);
+
+    let max_positional = tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::PositionalParam)
+        .filter_map(|t| t.text[1..].parse::<usize>().ok())
+        .max()
+        .unwrap_or(0);
+
+    let mut names: Vec<String> = (1..=max_positional).map(|n| format!("${n}")).collect();
+    let mut rewritten = String::with_capacity(sql.len());
+
+    for token in &tokens {
+        if token.kind == TokenKind::NamedParam {
+            let position = match names.iter().position(|name| name == token.text) {
+                Some(position) => position,
+                None => {
+                    names.push(token.text.to_string());
+                    names.len() - 1
+                }
+            };
+            rewritten.push_str(&format!("${}", position + 1));
+        } else {
+            rewritten.push_str(token.text);
+        }
+    }
+
+    (rewritten, names)
+}
+
+/// One statement of a script, trimmed, with its byte offset in the script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    pub text: &'a str,
+    pub start: usize,
+}
+
+/// Splits a script on semicolons outside strings, comments, dollar quotes and
+/// `BEGIN ATOMIC ... END` function bodies. Statements that are only whitespace
+/// or comments are dropped.
+pub fn split_statements(sql: &str) -> Vec<Statement<'_>> {
+    let tokens = tokenize(sql);
+    let mut statements = Vec::new();
+    let mut statement_start = 0;
+    let mut has_code = false;
+    let mut previous_word = "";
+    // Depth of CASE ... END blocks inside a BEGIN ATOMIC body, plus one for the body.
+    let mut atomic_depth = 0;
+
+    let mut push = |start: usize, end: usize, has_code: bool| {
+        let text = &sql[start..end];
+        let trimmed = text.trim_start();
+        if has_code {
+            statements.push(Statement {
+                text: trimmed.trim_end(),
+                start: start + (text.len() - trimmed.len()),
+            });
+        }
+    };
+
+    for token in &tokens {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            TokenKind::Symbol if token.text == ";" && atomic_depth == 0 => {
+                push(statement_start, token.start, has_code);
+                statement_start = token.start + 1;
+                has_code = false;
+                previous_word = "";
+                continue;
+            }
+            TokenKind::Word => {
+                if token.text.eq_ignore_ascii_case("atomic")
+                    && previous_word.eq_ignore_ascii_case("begin")
+                {
+                    atomic_depth = 1;
+                } else if atomic_depth > 0 && token.text.eq_ignore_ascii_case("case") {
+                    atomic_depth += 1;
+                } else if atomic_depth > 0 && token.text.eq_ignore_ascii_case("end") {
+                    atomic_depth -= 1;
+                }
+                previous_word = token.text;
+            }
+            _ => previous_word = "",
+        }
+        has_code = true;
+    }
+    push(statement_start, sql.len(), has_code);
+
+    statements
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    Read,
+    Write,
+    Ddl,
+    /// Transaction control and session commands such as `BEGIN` or `SET`.
+    Session,
+}
+
+impl StatementKind {
+    pub fn modifies_data(&self) -> bool {
+        matches!(self, StatementKind::Write | StatementKind::Ddl)
+    }
+}
+
+/// The statement's words and symbols, upper cased, with their parenthesis depth.
+fn significant_tokens(sql: &str) -> Vec<(String, usize)> {
+    let mut depth: usize = 0;
+    let mut significant = Vec::new();
+    for token in tokenize(sql) {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => {}
+            TokenKind::Symbol if token.text == "(" => {
+                significant.push((String::from("("), depth));
+                depth += 1;
+            }
+            TokenKind::Symbol if token.text == ")" => {
+                depth = depth.saturating_sub(1);
+                significant.push((String::from(")"), depth));
+            }
+            TokenKind::Word => significant.push((token.text.to_ascii_uppercase(), depth)),
+            _ => significant.push((token.text.to_string(), depth)),
+        }
+    }
+    significant
+}
+
+/// Whether an `UPDATE` or `DELETE` keyword starts a statement rather than being
+/// part of `FOR UPDATE`, `ON DELETE`, `DO UPDATE` and the like.
+fn starts_modification(tokens: &[(String, usize)], idx: usize) -> bool {
+    let (word, _) = &tokens[idx];
+    if word != "UPDATE" && word != "DELETE" {
+        return false;
+    }
+    match idx.checked_sub(1).map(|prev| tokens[prev].0.as_str()) {
+        None | Some("(") | Some(")") | Some("AS") | Some(",") => true,
+        // EXPLAIN ANALYZE [VERBOSE] runs the statement.
+        Some("ANALYZE") | Some("ANALYSE") | Some("VERBOSE") => true,
+        Some(_) => false,
+    }
+}
+
+/// Classifies a single statement by what it can change. Statements that aren't
+/// recognised count as writes.
+pub fn classify_statement(sql: &str) -> StatementKind {
+    let tokens = significant_tokens(sql);
+    let Some(first) = tokens.iter().position(|(word, _)| word != "(") else {
+        return StatementKind::Session;
+    };
+
+    match tokens[first].0.as_str() {
+        "SELECT" => {
+            // SELECT ... INTO creates a table.
+            if tokens
+                .iter()
+                .any(|(word, depth)| word == "INTO" && *depth == tokens[first].1)
+            {
+                StatementKind::Ddl
+            } else {
+                StatementKind::Read
+            }
+        }
+        "WITH" => {
+            let writes = tokens.iter().enumerate().any(|(idx, (word, _))| {
+                matches!(word.as_str(), "INSERT" | "MERGE") || starts_modification(&tokens, idx)
+            });
+            if writes {
+                StatementKind::Write
+            } else {
+                StatementKind::Read
+            }
+        }
+        "EXPLAIN" => {
+            let analyze = tokens.iter().any(|(word, _)| word == "ANALYZE");
+            let inner = tokens.iter().skip(first + 1).position(|(word, _)| {
+                matches!(
+                    word.as_str(),
+                    "SELECT"
+                        | "WITH"
+                        | "INSERT"
+                        | "UPDATE"
+                        | "DELETE"
+                        | "MERGE"
+                        | "VALUES"
+                        | "TABLE"
+                        | "EXECUTE"
+                        | "CREATE"
+                        | "DECLARE"
+                )
+            });
+            match inner {
+                Some(inner) if analyze => {
+                    let keyword = &tokens[first + 1 + inner].0;
+                    let offset = tokenize(sql)
+                        .into_iter()
+                        .filter(|t| t.kind == TokenKind::Word)
+                        .find(|t| t.text.eq_ignore_ascii_case(keyword))
+                        .map_or(0, |t| t.start);
+                    classify_statement(&sql[offset..])
+                }
+                _ => StatementKind::Read,
+            }
+        }
+        "COPY" => {
+            if tokens
+                .iter()
+                .any(|(word, depth)| word == "FROM" && *depth == 0)
+            {
+                StatementKind::Write
+            } else {
+                StatementKind::Read
+            }
+        }
+        "VALUES" | "TABLE" | "SHOW" | "FETCH" | "MOVE" | "DECLARE" | "CLOSE" => StatementKind::Read,
+        "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "TRUNCATE" | "CALL" | "DO" | "LOCK"
+        | "EXECUTE" => StatementKind::Write,
+        "CREATE" | "ALTER" | "DROP" | "COMMENT" | "GRANT" | "REVOKE" | "REINDEX" | "CLUSTER"
+        | "VACUUM" | "ANALYZE" | "ANALYSE" | "REFRESH" | "SECURITY" | "IMPORT" | "REASSIGN" => {
+            StatementKind::Ddl
+        }
+        "BEGIN" | "START" | "COMMIT" | "END" | "ROLLBACK" | "ABORT" | "SAVEPOINT" | "RELEASE"
+        | "SET" | "RESET" | "DISCARD" | "PREPARE" | "DEALLOCATE" | "LISTEN" | "UNLISTEN"
+        | "NOTIFY" | "LOAD" | "CHECKPOINT" => StatementKind::Session,
+        _ => StatementKind::Write,
+    }
+}
+
+/// Returns `UPDATE` or `DELETE` if the statement (or one of its CTEs) updates
+/// or deletes without a `WHERE` clause.
+pub fn unfiltered_modification(sql: &str) -> Option<&'static str> {
+    if classify_statement(sql) != StatementKind::Write {
+        return None;
+    }
+    let tokens = significant_tokens(sql);
+    for (idx, (word, depth)) in tokens.iter().enumerate() {
+        if !starts_modification(&tokens, idx) {
+            continue;
+        }
+        let has_where = tokens[idx + 1..]
+            .iter()
+            .take_while(|(_, d)| d >= depth)
+            .any(|(w, d)| w == "WHERE" && d == depth);
+        if !has_where {
+            return Some(if word == "UPDATE" { "UPDATE" } else { "DELETE" });
+        }
+    }
+    None
+}
+
+/// How a statement opens or closes a transaction block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionControl {
+    /// `BEGIN` or `START TRANSACTION`.
+    Begin,
+    /// `COMMIT`, `END`, `ROLLBACK`, `ABORT` or `PREPARE TRANSACTION`. With
+    /// `AND CHAIN` a new transaction starts straight away.
+    End { chain: bool },
+    /// `ROLLBACK TO SAVEPOINT`, which recovers a failed transaction.
+    RollbackToSavepoint,
+}
+
+pub fn transaction_control(sql: &str) -> Option<TransactionControl> {
+    let tokens = significant_tokens(sql);
+    let words: Vec<&str> = tokens.iter().map(|(word, _)| word.as_str()).collect();
+    let chain = words.windows(2).any(|pair| pair == ["AND", "CHAIN"]);
+
+    match words.as_slice() {
+        ["BEGIN", ..] | ["START", "TRANSACTION", ..] => Some(TransactionControl::Begin),
+        // COMMIT PREPARED and ROLLBACK PREPARED act on an already prepared
+        // transaction, not the session's.
+        ["COMMIT" | "ROLLBACK" | "END" | "ABORT", "PREPARED", ..] => None,
+        ["ROLLBACK" | "ABORT", rest @ ..] if rest.contains(&"TO") => {
+            Some(TransactionControl::RollbackToSavepoint)
+        }
+        ["COMMIT" | "ROLLBACK" | "END" | "ABORT", ..] => Some(TransactionControl::End { chain }),
+        ["PREPARE", "TRANSACTION", ..] => Some(TransactionControl::End { chain: false }),
+        _ => None,
+    }
+}
+
+/// Keywords Postgres reserves, which have to be quoted when used as names.
+#[rustfmt::skip]
+const RESERVED_KEYWORDS: &[&str] = &[
+    "all", "analyse", "analyze", "and", "any", "array", "as", "asc", "asymmetric",
+    "authorization", "binary", "both", "case", "cast", "check", "collate", "collation",
+    "column", "concurrently", "constraint", "create", "cross", "current_catalog",
+    "current_date", "current_role", "current_schema", "current_time", "current_timestamp",
+    "current_user", "default", "deferrable", "desc", "distinct", "do", "else", "end",
+    "except", "false", "fetch", "for", "foreign", "freeze", "from", "full", "grant", "group",
+    "having", "ilike", "in", "initially", "inner", "intersect", "into", "is", "isnull",
+    "join", "lateral", "leading", "left", "like", "limit", "localtime", "localtimestamp",
+    "natural", "not", "notnull", "null", "offset", "on", "only", "or", "order", "outer",
+    "overlaps", "placing", "primary", "references", "returning", "right", "select",
+    "session_user", "similar", "some", "symmetric", "system_user", "table", "tablesample",
+    "then", "to", "trailing", "true", "union", "unique", "user", "using", "variadic",
+    "verbose", "when", "where", "window", "with",
+];
+
+/// Quotes an identifier the way `quote_ident` does: only when it isn't a
+/// plain lower case name or is a reserved keyword.
+pub fn quote_identifier(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$');
+    if plain && !RESERVED_KEYWORDS.contains(&name) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// A schema qualified name, quoted where needed.
+pub fn qualified_name(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_identifier(schema), quote_identifier(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(sql: &str) -> Vec<&str> {
+        split_statements(sql).iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn splits_outside_quotes_and_comments() {
+        assert_eq!(
+            split("select $$;$$; select $tag$ ; $$ ; $tag$"),
+            ["select $$;$$", "select $tag$ ; $$ ; $tag$"]
+        );
+        assert_eq!(
+            split(r"select E'\''; select 'it''s;'"),
+            [r"select E'\''", "select 'it''s;'"]
+        );
+        assert_eq!(
+            split("select 1 -- ;\n; /* ; /* ; */ */ select 2"),
+            ["select 1 -- ;", "/* ; /* ; */ */ select 2"]
+        );
+        assert_eq!(split(r#"select "a;b" from t"#), [r#"select "a;b" from t"#]);
+    }
+
+    #[test]
+    fn keeps_begin_atomic_bodies_together() {
+        let sql = "create function f() returns int begin atomic \
+                   select case when true then 1 end; select 2; end; select 3";
+        let statements = split(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("select 2; end"));
+        assert_eq!(statements[1], "select 3");
+    }
+
+    #[test]
+    fn drops_empty_statements_and_keeps_offsets() {
+        let sql = ";; -- only a comment\n;  select 1 ;";
+        assert_eq!(
+            split_statements(sql),
+            [Statement {
+                text: "select 1",
+                start: sql.find("select").unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn numbers_named_placeholders_after_positional_ones() {
+        let (sql, names) =
+            prepare_placeholders("select :a, $2, ':b', :a::text, $$:c$$ -- :d\n, :e");
+        assert_eq!(sql, "select $3, $2, ':b', $3::text, $$:c$$ -- :d\n, $4");
+        assert_eq!(names, ["$1", "$2", ":a", ":e"]);
+    }
+
+    #[test]
+    fn leaves_array_slices_alone() {
+        let (sql, names) =
+            prepare_placeholders("select arr[1:n], arr[:hi], arr[lo:hi] from t where id = :id");
+        assert_eq!(
+            sql,
+            "select arr[1:n], arr[:hi], arr[lo:hi] from t where id = $1"
+        );
+        assert_eq!(names, [":id"]);
+
+        let (sql, names) = prepare_placeholders("select 1 :n from t where arr[ 2 ] = :x");
+        assert_eq!(sql, "select 1 :n from t where arr[ 2 ] = $1");
+        assert_eq!(names, [":x"]);
+    }
+
+    #[test]
+    fn classifies_statements() {
+        assert_eq!(
+            classify_statement("select 1 for update"),
+            StatementKind::Read
+        );
+        assert_eq!(
+            classify_statement("select * into t2 from t"),
+            StatementKind::Ddl
+        );
+        assert_eq!(
+            classify_statement("with d as (delete from t returning *) select * from d"),
+            StatementKind::Write
+        );
+        assert_eq!(
+            classify_statement("explain delete from t"),
+            StatementKind::Read
+        );
+        assert_eq!(
+            classify_statement("explain (analyze) delete from t"),
+            StatementKind::Write
+        );
+        assert_eq!(classify_statement("copy t to stdout"), StatementKind::Read);
+        assert_eq!(
+            classify_statement("copy t from stdin"),
+            StatementKind::Write
+        );
+        assert_eq!(classify_statement("-- nothing"), StatementKind::Session);
+        assert_eq!(classify_statement("vacuum t"), StatementKind::Ddl);
+        assert_eq!(classify_statement("frobnicate"), StatementKind::Write);
+    }
+
+    #[test]
+    fn finds_updates_and_deletes_without_where() {
+        assert_eq!(unfiltered_modification("delete from t"), Some("DELETE"));
+        assert_eq!(unfiltered_modification("delete from t where id = 1"), None);
+        assert_eq!(
+            unfiltered_modification("update t set a = (select b from u where u.id = 1)"),
+            Some("UPDATE")
+        );
+        assert_eq!(
+            unfiltered_modification(
+                "with d as (delete from t) insert into u select * from v where true"
+            ),
+            Some("DELETE")
+        );
+        assert_eq!(
+            unfiltered_modification(
+                "insert into t values (1) on conflict (id) do update set a = 1"
+            ),
+            None
+        );
+        assert_eq!(unfiltered_modification("select 'delete from t'"), None);
+    }
+
+    #[test]
+    fn recognises_transaction_control() {
+        use TransactionControl::*;
+        assert_eq!(transaction_control("begin"), Some(Begin));
+        assert_eq!(
+            transaction_control("start transaction read only"),
+            Some(Begin)
+        );
+        assert_eq!(transaction_control("commit"), Some(End { chain: false }));
+        assert_eq!(
+            transaction_control("rollback and chain"),
+            Some(End { chain: true })
+        );
+        assert_eq!(
+            transaction_control("rollback to savepoint s"),
+            Some(RollbackToSavepoint)
+        );
+        assert_eq!(
+            transaction_control("prepare transaction 'x'"),
+            Some(End { chain: false })
+        );
+        assert_eq!(transaction_control("commit prepared 'x'"), None);
+        assert_eq!(transaction_control("select 'begin'"), None);
+    }
+
+    #[test]
+    fn quotes_identifiers_like_quote_ident() {
+        assert_eq!(quote_identifier("orders"), "orders");
+        assert_eq!(quote_identifier("_t$1"), "_t$1");
+        assert_eq!(quote_identifier("Orders"), "\"Orders\"");
+        assert_eq!(quote_identifier("user"), "\"user\"");
+        assert_eq!(quote_identifier("1st"), "\"1st\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(qualified_name("public", "my table"), "public.\"my table\"");
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::SavedConnection;
+
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// How often to pick new local ports when another process took one first.
+const PORT_ATTEMPTS: usize = 3;
+
+/// The tunnel connects to 127.0.0.1, so the server's certificate can't match
+/// the host name that verify-full checks it against.
+pub const VERIFY_FULL_TUNNEL_WARNING: &str = "sslmode=verify-full can't be used through an SSH \
+    tunnel, the certificate would be checked against the tunnel's local address. \
+    Use verify-ca instead.";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct SshTunnelConfig {
+    pub host: String,
+    pub port: String,
+    pub user: String,
+    /// Private key to use. When empty the SSH agent and default keys are tried.
+    pub identity_file: String,
+    /// When empty the user's `~/.ssh/known_hosts` is used.
+    pub known_hosts_file: String,
+}
+
+impl SshTunnelConfig {
+    /// Rejects a host or user that ssh would read as an option, e.g.
+    /// `-oProxyCommand=…`, which runs a local command.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err(String::from("The SSH host is empty"));
+        }
+        for (label, value) in [("host", &self.host), ("user", &self.user)] {
+            if value.trim().starts_with('-') {
+                return Err(format!("The SSH {label} can't start with '-'"));
+            }
+        }
+        Ok(())
+    }
+
+    fn command(&self, forwards: &[(u16, String, u16)]) -> Command {
+        let mut command = Command::new("ssh");
+        command.args([
+            "-N",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "StrictHostKeyChecking=yes",
+            "-o",
+            "ServerAliveInterval=15",
+            "-o",
+            "ServerAliveCountMax=3",
+        ]);
+        if !self.port.trim().is_empty() {
+            command.args(["-p", self.port.trim()]);
+        }
+        if !self.identity_file.trim().is_empty() {
+            command.args(["-o", "IdentitiesOnly=yes", "-i", self.identity_file.trim()]);
+        }
+        if !self.known_hosts_file.trim().is_empty() {
+            command.arg("-o").arg(format!(
+                "UserKnownHostsFile={}",
+                self.known_hosts_file.trim()
+            ));
+        }
+        for (local_port, host, port) in forwards {
+            // IPv6 addresses have to be bracketed in a forward specification.
+            let host = if host.contains(':') {
+                format!("[{host}]")
+            } else {
+                host.clone()
+            };
+            command
+                .arg("-L")
+                .arg(format!("127.0.0.1:{local_port}:{host}:{port}"));
+        }
+        // Nothing after this is read as an option.
+        command.arg("--");
+        if self.user.trim().is_empty() {
+            command.arg(self.host.trim());
+        } else {
+            command.arg(format!("{}@{}", self.user.trim(), self.host.trim()));
+        }
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        command
+    }
+}
+
+fn stderr_output(child: &mut Child) -> String {
+    let mut output = String::new();
+    if let Some(stderr) = child.stderr.as_mut() {
+        let _ = stderr.read_to_string(&mut output);
+    }
+    output.trim().to_string()
+}
+
+/// Keeps reading a running ssh's stderr so banners and warnings can't fill the
+/// pipe and block it. Lines are logged as they arrive.
+fn drain_stderr(child: &mut Child, host: &str) {
+    let Some(stderr) = child.stderr.take() else {
+        return;
+    };
+    let host = host.to_string();
+    thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            eprintln!("ssh {host}: {line}");
+        }
+    });
+}
+
+/// Local ports picked by the OS. They are free when this returns, but ssh only
+/// binds them later, so a failed forward has to be retried with new ports.
+fn free_local_ports(count: usize) -> Result<Vec<u16>, String> {
+    let listeners = (0..count)
+        .map(|_| TcpListener::bind((Ipv4Addr::LOCALHOST, 0)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to find a free local port: {e}"))?;
+    listeners
+        .iter()
+        .map(|listener| listener.local_addr().map(|address| address.port()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to find a free local port: {e}"))
+}
+
+/// Whether ssh gave up because a local port it was told to listen on was taken.
+fn is_forward_failure(error: &str) -> bool {
+    error.contains("Address already in use") || error.contains("cannot listen to port")
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Waits until ssh accepts connections on the first forwarded port, or until
+/// the tunnel is closed.
+fn wait_until_ready(child: &mut Child, local_port: u16, stop: &AtomicBool) -> Result<(), String> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, local_port));
+    let started = Instant::now();
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            kill(child);
+            return Err(String::from("The SSH tunnel was closed"));
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let output = stderr_output(child);
+                return Err(if output.is_empty() {
+                    format!("ssh exited with {status}")
+                } else {
+                    output
+                });
+            }
+            Ok(None) => {}
+            Err(e) => return Err(format!("Failed to check on ssh: {e}")),
+        }
+        if TcpStream::connect_timeout(&address, Duration::from_millis(200)).is_ok() {
+            // Whatever answered might be another process that took the port,
+            // in which case ssh exits with a forward failure shortly after.
+            thread::sleep(Duration::from_millis(300));
+            if let Ok(None) = child.try_wait() {
+                return Ok(());
+            }
+            continue;
+        }
+        if started.elapsed() > READY_TIMEOUT {
+            kill(child);
+            return Err(String::from("Timed out waiting for the SSH tunnel"));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// A local port forward through `ssh`, restarted if it drops and closed when dropped.
+pub struct SshTunnel {
+    local_ports: Vec<u16>,
+    stop: Arc<AtomicBool>,
+    /// The running ssh, shared with the thread that restarts it. `None` while
+    /// it is being restarted.
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl SshTunnel {
+    /// Forwards a local port to each of the targets, as seen from the SSH host.
+    pub fn open(config: &SshTunnelConfig, targets: &[(String, u16)]) -> Result<Self, String> {
+        config.validate()?;
+
+        // Another process can take a port between picking it and ssh binding
+        // it. ExitOnForwardFailure makes ssh exit then, so try other ports.
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut attempt = 1;
+        let (mut child, local_ports, forwards) = loop {
+            let local_ports = free_local_ports(targets.len())?;
+            let forwards: Vec<(u16, String, u16)> = local_ports
+                .iter()
+                .zip(targets)
+                .map(|(local_port, (host, port))| (*local_port, host.clone(), *port))
+                .collect();
+
+            let mut child = config
+                .command(&forwards)
+                .spawn()
+                .map_err(|e| format!("Failed to start ssh: {e}"))?;
+            match wait_until_ready(&mut child, local_ports[0], &stop) {
+                Ok(()) => break (child, local_ports, forwards),
+                Err(e) if attempt < PORT_ATTEMPTS && is_forward_failure(&e) => {
+                    eprintln!("Retrying the SSH tunnel on other ports: {e}");
+                    attempt += 1;
+                }
+                Err(e) => return Err(format!("Failed to open the SSH tunnel: {e}")),
+            }
+        };
+        drain_stderr(&mut child, &config.host);
+
+        let child = Arc::new(Mutex::new(Some(child)));
+        {
+            let stop = stop.clone();
+            let child = child.clone();
+            let config = config.clone();
+            thread::spawn(move || supervise(&child, &config, &forwards, &stop));
+        }
+
+        Ok(Self {
+            local_ports,
+            stop,
+            child,
+        })
+    }
+
+    pub fn local_ports(&self) -> &[u16] {
+        &self.local_ports
+    }
+
+    /// Points a copy of the connection at the tunnel's local ports.
+    pub fn tunnelled(&self, connection: &SavedConnection) -> SavedConnection {
+        let hosts = vec!["127.0.0.1"; self.local_ports.len()];
+        let ports: Vec<String> = self.local_ports.iter().map(u16::to_string).collect();
+        SavedConnection {
+            db_host: hosts.join(","),
+            db_port: ports.join(","),
+            ..connection.clone()
+        }
+    }
+}
+
+/// Runs on the UI thread, so it doesn't wait for the supervisor. A restart
+/// in progress notices the stop flag and kills its ssh.
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().ok().and_then(|mut child| child.take()) {
+            kill(&mut child);
+        }
+    }
+}
+
+/// Keeps ssh running until the tunnel is dropped, restarting it with a growing delay.
+fn supervise(
+    child: &Mutex<Option<Child>>,
+    config: &SshTunnelConfig,
+    forwards: &[(u16, String, u16)],
+    stop: &AtomicBool,
+) {
+    let mut delay = Duration::from_secs(1);
+    let mut restart_at: Option<Instant> = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(200));
+
+        if restart_at.is_none() {
+            let Ok(mut running) = child.lock() else {
+                return;
+            };
+            let Some(status) = running
+                .as_mut()
+                .and_then(|running| running.try_wait().ok().flatten())
+            else {
+                continue;
+            };
+            *running = None;
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            eprintln!(
+                "SSH tunnel to {} exited with {status}, reconnecting",
+                config.host
+            );
+            restart_at = Some(Instant::now() + delay);
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        } else if restart_at.is_some_and(|at| Instant::now() >= at) {
+            match config.command(forwards).spawn() {
+                Ok(mut new_child) => match wait_until_ready(&mut new_child, forwards[0].0, stop) {
+                    Ok(()) => {
+                        drain_stderr(&mut new_child, &config.host);
+                        let Ok(mut running) = child.lock() else {
+                            kill(&mut new_child);
+                            return;
+                        };
+                        // The tunnel may have been dropped while waiting.
+                        if stop.load(Ordering::Relaxed) {
+                            kill(&mut new_child);
+                            return;
+                        }
+                        *running = Some(new_child);
+                        restart_at = None;
+                        delay = Duration::from_secs(1);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to reopen the SSH tunnel: {e}");
+                        restart_at = Some(Instant::now() + delay);
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("Failed to start ssh: {e}");
+                    restart_at = Some(Instant::now() + delay);
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,820 @@
+use std::collections::HashSet;
+
+use crate::{
+    app::new_connection_id,
+    query_functions::pg_schema::TableDetails,
+    sql::{qualified_name, quote_identifier},
+};
+
+/// Foreign key actions, keyed by their `pg_constraint` code.
+pub const REFERENTIAL_ACTIONS: [(&str, &str); 5] = [
+    ("a", "NO ACTION"),
+    ("r", "RESTRICT"),
+    ("c", "CASCADE"),
+    ("n", "SET NULL"),
+    ("d", "SET DEFAULT"),
+];
+
+pub fn referential_action(code: &str) -> &'static str {
+    REFERENTIAL_ACTIONS
+        .iter()
+        .find(|(action_code, _)| *action_code == code)
+        .map_or("NO ACTION", |(_, action)| action)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DesignColumn {
+    pub name: String,
+    pub data_type: String,
+    pub default_value: String,
+    pub not_null: bool,
+    pub primary_key: bool,
+    /// `a` or `d` for identity columns, see `pg_attribute.attidentity`.
+    pub identity: String,
+    /// Expression of a stored generated column. It can't be changed here.
+    pub generated: Option<String>,
+    /// Name in the catalog, `None` for columns that don't exist yet.
+    pub original: Option<String>,
+}
+
+/// A constraint or index, with the name it has in the catalog so renames and
+/// changes can be told apart.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Named<T> {
+    pub name: String,
+    pub original: Option<String>,
+    pub spec: T,
+}
+
+/// Column lists are plain names separated by commas.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UniqueSpec {
+    pub columns: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CheckSpec {
+    pub expression: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeySpec {
+    pub columns: String,
+    /// The referenced table, as SQL, e.g. `public.customers`.
+    pub references: String,
+    pub foreign_columns: String,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+impl Default for ForeignKeySpec {
+    fn default() -> Self {
+        Self {
+            columns: String::new(),
+            references: String::new(),
+            foreign_columns: String::new(),
+            on_update: String::from("a"),
+            on_delete: String::from("a"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSpec {
+    pub unique: bool,
+    pub method: String,
+    /// Key columns or expressions, as SQL.
+    pub keys: String,
+    pub predicate: String,
+}
+
+impl Default for IndexSpec {
+    fn default() -> Self {
+        Self {
+            unique: false,
+            method: String::from("btree"),
+            keys: String::new(),
+            predicate: String::new(),
+        }
+    }
+}
+
+/// A table as edited in the designer. Exclusion constraints, triggers and
+/// anything else it doesn't show are left alone.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TableDesign {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<DesignColumn>,
+    /// Left to Postgres to name when empty.
+    pub primary_key_name: String,
+    pub uniques: Vec<Named<UniqueSpec>>,
+    pub checks: Vec<Named<CheckSpec>>,
+    pub foreign_keys: Vec<Named<ForeignKeySpec>>,
+    pub indexes: Vec<Named<IndexSpec>>,
+}
+
+fn existing<T>(name: &str, spec: T) -> Named<T> {
+    Named {
+        name: name.to_string(),
+        original: Some(name.to_string()),
+        spec,
+    }
+}
+
+fn column_list(columns: &str) -> String {
+    columns
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `CONSTRAINT name ` when the constraint has a name.
+fn constraint_prefix(name: &str) -> String {
+    if name.trim().is_empty() {
+        String::new()
+    } else {
+        format!("CONSTRAINT {} ", quote_identifier(name.trim()))
+    }
+}
+
+impl UniqueSpec {
+    fn definition(&self) -> String {
+        format!("UNIQUE ({})", column_list(&self.columns))
+    }
+}
+
+impl CheckSpec {
+    fn definition(&self) -> String {
+        format!("CHECK ({})", self.expression.trim())
+    }
+}
+
+impl ForeignKeySpec {
+    fn definition(&self) -> String {
+        let mut definition = format!(
+            "FOREIGN KEY ({}) REFERENCES {}",
+            column_list(&self.columns),
+            self.references.trim()
+        );
+        if !self.foreign_columns.trim().is_empty() {
+            definition += &format!(" ({})", column_list(&self.foreign_columns));
+        }
+        for (clause, code) in [
+            ("ON UPDATE", &self.on_update),
+            ("ON DELETE", &self.on_delete),
+        ] {
+            if code != "a" {
+                definition += &format!(" {clause} {}", referential_action(code));
+            }
+        }
+        definition
+    }
+}
+
+impl DesignColumn {
+    fn definition(&self) -> String {
+        let mut definition = format!("{} {}", quote_identifier(&self.name), self.data_type.trim());
+        if let Some(expression) = &self.generated {
+            definition += &format!(" GENERATED ALWAYS AS ({expression}) STORED");
+        }
+        if let Some(identity) = identity_clause(&self.identity) {
+            definition += &format!(" GENERATED {identity} AS IDENTITY");
+        }
+        if !self.default_value.trim().is_empty() {
+            definition += &format!(" DEFAULT {}", self.default_value.trim());
+        }
+        if self.not_null {
+            definition += " NOT NULL";
+        }
+        definition
+    }
+}
+
+fn identity_clause(identity: &str) -> Option<&'static str> {
+    match identity {
+        "a" => Some("ALWAYS"),
+        "d" => Some("BY DEFAULT"),
+        _ => None,
+    }
+}
+
+fn create_index(table: &str, index: &Named<IndexSpec>) -> String {
+    let mut sql = String::from("CREATE ");
+    if index.spec.unique {
+        sql += "UNIQUE ";
+    }
+    sql += "INDEX ";
+    if !index.name.trim().is_empty() {
+        sql += &format!("{} ", quote_identifier(index.name.trim()));
+    }
+    sql += &format!(
+        "ON {table} USING {} ({})",
+        index.spec.method.trim(),
+        index.spec.keys.trim()
+    );
+    if !index.spec.predicate.trim().is_empty() {
+        sql += &format!(" WHERE {}", index.spec.predicate.trim());
+    }
+    sql + ";"
+}
+
+/// Matches edited constraints or indexes with the catalog ones by their
+/// original name. Changed ones are dropped and added again.
+struct Changes<'a, T> {
+    dropped: Vec<&'a str>,
+    renamed: Vec<(&'a str, &'a str)>,
+    added: Vec<&'a Named<T>>,
+}
+
+fn changes<'a, T: PartialEq>(original: &'a [Named<T>], current: &'a [Named<T>]) -> Changes<'a, T> {
+    let mut changes = Changes {
+        dropped: Vec::new(),
+        renamed: Vec::new(),
+        added: Vec::new(),
+    };
+    for old in original {
+        match current
+            .iter()
+            .find(|new| new.original.as_deref() == Some(&old.name))
+        {
+            None => changes.dropped.push(&old.name),
+            Some(new) if new.spec == old.spec => {
+                if new.name.trim() != old.name && !new.name.trim().is_empty() {
+                    changes.renamed.push((&old.name, new.name.trim()));
+                }
+            }
+            Some(new) => {
+                changes.dropped.push(&old.name);
+                changes.added.push(new);
+            }
+        }
+    }
+    changes.added.extend(current.iter().filter(|new| {
+        !new.original
+            .as_deref()
+            .is_some_and(|name| original.iter().any(|old| old.name == name))
+    }));
+    changes
+}
+
+impl TableDesign {
+    pub fn new(schema: &str) -> Self {
+        Self {
+            schema: schema.to_string(),
+            columns: vec![DesignColumn {
+                name: String::from("id"),
+                data_type: String::from("bigint"),
+                not_null: true,
+                primary_key: true,
+                identity: String::from("a"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// The design of an existing table, from its catalog details.
+    pub fn from_details(schema: &str, table: &str, details: &TableDetails) -> Self {
+        let primary_key = details.primary_key().unwrap_or_default();
+        let columns = details
+            .columns
+            .iter()
+            .map(|column| {
+                let generated = (column.generated == "s")
+                    .then(|| column.default_value.clone().unwrap_or_default());
+                DesignColumn {
+                    name: column.name.clone(),
+                    data_type: column.data_type.clone(),
+                    default_value: if generated.is_some() {
+                        String::new()
+                    } else {
+                        column.default_value.clone().unwrap_or_default()
+                    },
+                    not_null: column.not_null,
+                    primary_key: primary_key.contains(&column.name),
+                    identity: column.identity.trim().to_string(),
+                    generated,
+                    original: Some(column.name.clone()),
+                }
+            })
+            .collect();
+
+        let mut design = Self {
+            schema: schema.to_string(),
+            name: table.to_string(),
+            columns,
+            ..Default::default()
+        };
+        for constraint in &details.constraints {
+            let columns = constraint.columns.join(", ");
+            match constraint.kind.as_str() {
+                "p" => design.primary_key_name = constraint.name.clone(),
+                "u" => design
+                    .uniques
+                    .push(existing(&constraint.name, UniqueSpec { columns })),
+                "c" => design.checks.push(existing(
+                    &constraint.name,
+                    CheckSpec {
+                        expression: constraint.expression.clone().unwrap_or_default(),
+                    },
+                )),
+                "f" => design.foreign_keys.push(existing(
+                    &constraint.name,
+                    ForeignKeySpec {
+                        columns,
+                        references: qualified_name(
+                            constraint.foreign_schema.as_deref().unwrap_or_default(),
+                            constraint.foreign_table.as_deref().unwrap_or_default(),
+                        ),
+                        foreign_columns: constraint.foreign_columns.join(", "),
+                        on_update: constraint.on_update.clone(),
+                        on_delete: constraint.on_delete.clone(),
+                    },
+                )),
+                _ => {}
+            }
+        }
+        design.indexes = details
+            .indexes
+            .iter()
+            .filter(|index| !index.is_constraint)
+            .map(|index| {
+                existing(
+                    &index.name,
+                    IndexSpec {
+                        unique: index.is_unique,
+                        method: index.method.clone(),
+                        keys: index.keys.join(", "),
+                        predicate: index.predicate.clone().unwrap_or_default(),
+                    },
+                )
+            })
+            .collect();
+        design
+    }
+
+    pub fn add_column(&mut self) {
+        self.columns.push(DesignColumn {
+            data_type: String::from("text"),
+            ..Default::default()
+        });
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(String::from("The table needs a name."));
+        }
+        if self.columns.is_empty() {
+            return Err(String::from("The table needs at least one column."));
+        }
+        let mut names = HashSet::new();
+        for column in &self.columns {
+            if column.name.trim().is_empty() {
+                return Err(String::from("Every column needs a name."));
+            }
+            if !names.insert(column.name.as_str()) {
+                return Err(format!("There are two columns called {}.", column.name));
+            }
+            if column.data_type.trim().is_empty() {
+                return Err(format!("{} needs a type.", column.name));
+            }
+            if !column.identity.is_empty() && !column.default_value.trim().is_empty() {
+                return Err(format!(
+                    "{} is an identity column, so it can't have a default.",
+                    column.name
+                ));
+            }
+        }
+        for foreign_key in &self.foreign_keys {
+            if foreign_key.spec.columns.trim().is_empty()
+                || foreign_key.spec.references.trim().is_empty()
+            {
+                return Err(String::from(
+                    "Foreign keys need columns and a referenced table.",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn primary_key_columns(&self) -> Vec<&DesignColumn> {
+        self.columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .collect()
+    }
+
+    fn constraint_definitions(&self) -> Vec<(&str, String)> {
+        let uniques = self
+            .uniques
+            .iter()
+            .map(|unique| (unique.name.as_str(), unique.spec.definition()));
+        let checks = self
+            .checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.spec.definition()));
+        let foreign_keys = self
+            .foreign_keys
+            .iter()
+            .map(|foreign_key| (foreign_key.name.as_str(), foreign_key.spec.definition()));
+        uniques.chain(checks).chain(foreign_keys).collect()
+    }
+
+    fn create_script(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.columns.iter().map(DesignColumn::definition).collect();
+        let primary_key = self.primary_key_columns();
+        if !primary_key.is_empty() {
+            let names: Vec<String> = primary_key
+                .iter()
+                .map(|column| quote_identifier(&column.name))
+                .collect();
+            lines.push(format!(
+                "{}PRIMARY KEY ({})",
+                constraint_prefix(&self.primary_key_name),
+                names.join(", ")
+            ));
+        }
+        for (name, definition) in self.constraint_definitions() {
+            lines.push(format!("{}{definition}", constraint_prefix(name)));
+        }
+        let table = qualified_name(&self.schema, &self.name);
+        let mut statements = vec![format!(
+            "CREATE TABLE {table} (\n  {}\n);",
+            lines.join(",\n  ")
+        )];
+        statements.extend(self.indexes.iter().map(|index| create_index(&table, index)));
+        statements
+    }
+
+    /// The statements that turn `original` into this design, touching only
+    /// what changed.
+    fn alter_script(&self, original: &TableDesign) -> Vec<String> {
+        let table = qualified_name(&original.schema, &original.name);
+        let alter = |action: String| format!("ALTER TABLE {table} {action};");
+        let mut drops = Vec::new();
+        let mut columns = Vec::new();
+        let mut adds = Vec::new();
+
+        let old_key: Vec<&str> = original
+            .primary_key_columns()
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect();
+        let new_key = self.primary_key_columns();
+        let key_changed = old_key.len() != new_key.len()
+            || new_key.iter().any(|column| {
+                !column
+                    .original
+                    .as_deref()
+                    .is_some_and(|name| old_key.contains(&name))
+            });
+        if key_changed {
+            if !old_key.is_empty() {
+                drops.push(alter(format!(
+                    "DROP CONSTRAINT {}",
+                    quote_identifier(&original.primary_key_name)
+                )));
+            }
+            if !new_key.is_empty() {
+                let names: Vec<String> = new_key
+                    .iter()
+                    .map(|column| quote_identifier(&column.name))
+                    .collect();
+                adds.push(alter(format!(
+                    "ADD {}PRIMARY KEY ({})",
+                    constraint_prefix(&self.primary_key_name),
+                    names.join(", ")
+                )));
+            }
+        } else if !old_key.is_empty()
+            && !self.primary_key_name.trim().is_empty()
+            && self.primary_key_name.trim() != original.primary_key_name
+        {
+            adds.push(alter(format!(
+                "RENAME CONSTRAINT {} TO {}",
+                quote_identifier(&original.primary_key_name),
+                quote_identifier(self.primary_key_name.trim())
+            )));
+        }
+
+        let unique_changes = changes(&original.uniques, &self.uniques);
+        let check_changes = changes(&original.checks, &self.checks);
+        let foreign_key_changes = changes(&original.foreign_keys, &self.foreign_keys);
+        // Foreign keys go first so the constraints they depend on can be dropped.
+        let dropped = foreign_key_changes
+            .dropped
+            .iter()
+            .chain(&unique_changes.dropped)
+            .chain(&check_changes.dropped);
+        for name in dropped {
+            drops.push(alter(format!("DROP CONSTRAINT {}", quote_identifier(name))));
+        }
+        let index_changes = changes(&original.indexes, &self.indexes);
+        for name in &index_changes.dropped {
+            drops.push(format!(
+                "DROP INDEX {};",
+                qualified_name(&original.schema, name)
+            ));
+        }
+
+        for old in &original.columns {
+            if !self
+                .columns
+                .iter()
+                .any(|column| column.original.as_deref() == Some(&old.name))
+            {
+                columns.push(alter(format!(
+                    "DROP COLUMN {}",
+                    quote_identifier(&old.name)
+                )));
+            }
+        }
+        for column in &self.columns {
+            let old = column
+                .original
+                .as_deref()
+                .and_then(|name| original.columns.iter().find(|old| old.name == name));
+            let Some(old) = old else {
+                columns.push(alter(format!("ADD COLUMN {}", column.definition())));
+                continue;
+            };
+            let name = quote_identifier(&column.name);
+            if column.name != old.name {
+                columns.push(alter(format!(
+                    "RENAME COLUMN {} TO {name}",
+                    quote_identifier(&old.name)
+                )));
+            }
+            let data_type = column.data_type.trim();
+            if column.generated.is_none() && data_type != old.data_type {
+                columns.push(alter(format!(
+                    "ALTER COLUMN {name} TYPE {data_type} USING {name}::{data_type}"
+                )));
+            }
+            let default_value = column.default_value.trim();
+            if default_value != old.default_value {
+                columns.push(alter(if default_value.is_empty() {
+                    format!("ALTER COLUMN {name} DROP DEFAULT")
+                } else {
+                    format!("ALTER COLUMN {name} SET DEFAULT {default_value}")
+                }));
+            }
+            if column.not_null != old.not_null {
+                columns.push(alter(format!(
+                    "ALTER COLUMN {name} {} NOT NULL",
+                    if column.not_null { "SET" } else { "DROP" }
+                )));
+            }
+            if column.identity != old.identity {
+                let action = match (
+                    identity_clause(&old.identity),
+                    identity_clause(&column.identity),
+                ) {
+                    (_, None) => String::from("DROP IDENTITY"),
+                    (None, Some(identity)) => format!("ADD GENERATED {identity} AS IDENTITY"),
+                    (Some(_), Some(identity)) => format!("SET GENERATED {identity}"),
+                };
+                columns.push(alter(format!("ALTER COLUMN {name} {action}")));
+            }
+        }
+
+        let mut renames = Vec::new();
+        let added = unique_changes
+            .added
+            .iter()
+            .map(|unique| (&unique.name, unique.spec.definition()))
+            .chain(
+                check_changes
+                    .added
+                    .iter()
+                    .map(|check| (&check.name, check.spec.definition())),
+            )
+            .chain(
+                foreign_key_changes
+                    .added
+                    .iter()
+                    .map(|foreign_key| (&foreign_key.name, foreign_key.spec.definition())),
+            );
+        for (name, definition) in added {
+            adds.push(alter(format!(
+                "ADD {}{definition}",
+                constraint_prefix(name)
+            )));
+        }
+        for (old, new) in unique_changes
+            .renamed
+            .iter()
+            .chain(&check_changes.renamed)
+            .chain(&foreign_key_changes.renamed)
+        {
+            renames.push(alter(format!(
+                "RENAME CONSTRAINT {} TO {}",
+                quote_identifier(old),
+                quote_identifier(new)
+            )));
+        }
+
+        for index in &index_changes.added {
+            adds.push(create_index(&table, index));
+        }
+        for (old, new) in &index_changes.renamed {
+            renames.push(format!(
+                "ALTER INDEX {} RENAME TO {};",
+                qualified_name(&original.schema, old),
+                quote_identifier(new)
+            ));
+        }
+
+        if self.name.trim() != original.name {
+            renames.push(alter(format!(
+                "RENAME TO {}",
+                quote_identifier(self.name.trim())
+            )));
+        }
+
+        [drops, columns, adds, renames].concat()
+    }
+
+    /// The script that creates the table, or alters `original` to match.
+    pub fn script(&self, original: Option<&TableDesign>) -> Result<Vec<String>, String> {
+        self.validate()?;
+        Ok(match original {
+            Some(original) => self.alter_script(original),
+            None => self.create_script(),
+        })
+    }
+
+    /// Names of catalog columns that the design drops.
+    pub fn dropped_columns<'a>(&self, original: &'a TableDesign) -> Vec<&'a str> {
+        original
+            .columns
+            .iter()
+            .filter(|old| {
+                !self
+                    .columns
+                    .iter()
+                    .any(|column| column.original.as_deref() == Some(&old.name))
+            })
+            .map(|old| old.name.as_str())
+            .collect()
+    }
+}
+
+/// A table being created or changed in the designer window.
+#[derive(Debug)]
+pub struct TableDesigner {
+    /// Tells results for this designer apart from ones for a designer that was closed.
+    pub id: String,
+    pub key: String,
+    /// The table as it is in the catalog. `None` while creating a table.
+    pub original: Option<TableDesign>,
+    pub design: TableDesign,
+    pub read_only: Option<String>,
+    /// Name of the production connection, whose changes are confirmed before applying.
+    pub production: Option<String>,
+    pub applying: bool,
+    pub confirm_apply: bool,
+    pub error: Option<String>,
+    pub status: String,
+}
+
+impl TableDesigner {
+    pub fn new(key: &str, original: Option<TableDesign>, design: TableDesign) -> Self {
+        Self {
+            id: new_connection_id(),
+            key: key.to_string(),
+            original,
+            design,
+            read_only: None,
+            production: None,
+            applying: false,
+            confirm_apply: false,
+            error: None,
+            status: String::new(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.original {
+            Some(original) => format!(
+                "Design {}",
+                qualified_name(&original.schema, &original.name)
+            ),
+            None => String::from("New table"),
+        }
+    }
+
+    pub fn script(&self) -> Result<Vec<String>, String> {
+        self.design.script(self.original.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn design() -> TableDesign {
+        let mut design = TableDesign::new("public");
+        design.name = String::from("Orders");
+        design.add_column();
+        design.columns[1].name = String::from("customer_id");
+        design.columns[1].data_type = String::from("bigint");
+        design.foreign_keys.push(Named {
+            name: String::from("orders_customer"),
+            original: None,
+            spec: ForeignKeySpec {
+                columns: String::from("customer_id"),
+                references: String::from("public.customers"),
+                on_delete: String::from("c"),
+                ..Default::default()
+            },
+        });
+        design.indexes.push(Named {
+            name: String::new(),
+            original: None,
+            spec: IndexSpec {
+                keys: String::from("customer_id"),
+                ..Default::default()
+            },
+        });
+        design
+    }
+
+    /// The design as it would be loaded back from the catalog.
+    fn saved(design: &TableDesign) -> TableDesign {
+        let mut saved = design.clone();
+        saved.primary_key_name = String::from("Orders_pkey");
+        for column in &mut saved.columns {
+            column.original = Some(column.name.clone());
+        }
+        saved.foreign_keys[0].original = Some(saved.foreign_keys[0].name.clone());
+        saved.indexes[0].name = String::from("Orders_customer_id_idx");
+        saved.indexes[0].original = Some(saved.indexes[0].name.clone());
+        saved
+    }
+
+    #[test]
+    fn creates_tables() {
+        assert_eq!(
+            design().script(None).unwrap(),
+            [
+                "CREATE TABLE public.\"Orders\" (\n  \
+                 id bigint GENERATED ALWAYS AS IDENTITY NOT NULL,\n  \
+                 customer_id bigint,\n  \
+                 PRIMARY KEY (id),\n  \
+                 CONSTRAINT orders_customer FOREIGN KEY (customer_id) \
+                 REFERENCES public.customers ON DELETE CASCADE\n);",
+                "CREATE INDEX ON public.\"Orders\" USING btree (customer_id);",
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_tables_need_no_statements() {
+        let saved = saved(&design());
+        assert_eq!(saved.script(Some(&saved)).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn alters_only_what_changed() {
+        let original = saved(&design());
+        let mut design = original.clone();
+        design.columns[1].name = String::from("client_id");
+        design.columns[1].not_null = true;
+        design.foreign_keys[0].name = String::from("orders_client");
+        design.indexes[0].spec.unique = true;
+        design.columns.remove(0);
+        design.add_column();
+        design.columns[1].name = String::from("note");
+
+        assert_eq!(
+            design.script(Some(&original)).unwrap(),
+            [
+                "ALTER TABLE public.\"Orders\" DROP CONSTRAINT \"Orders_pkey\";",
+                "DROP INDEX public.\"Orders_customer_id_idx\";",
+                "ALTER TABLE public.\"Orders\" DROP COLUMN id;",
+                "ALTER TABLE public.\"Orders\" RENAME COLUMN customer_id TO client_id;",
+                "ALTER TABLE public.\"Orders\" ALTER COLUMN client_id SET NOT NULL;",
+                "ALTER TABLE public.\"Orders\" ADD COLUMN note text;",
+                "CREATE UNIQUE INDEX \"Orders_customer_id_idx\" \
+                 ON public.\"Orders\" USING btree (customer_id);",
+                "ALTER TABLE public.\"Orders\" RENAME CONSTRAINT orders_customer TO orders_client;",
+            ]
+        );
+        assert_eq!(design.dropped_columns(&original), ["id"]);
+    }
+
+    #[test]
+    fn rejects_incomplete_designs() {
+        let mut design = design();
+        design.columns[1].name = String::from("id");
+        assert!(design.script(None).is_err());
+        design.columns[1].name = String::from("customer_id");
+        design.columns[0].default_value = String::from("1");
+        assert!(design.script(None).is_err());
+    }
+}
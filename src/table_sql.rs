@@ -0,0 +1,234 @@
+use crate::{
+    query_functions::pg_schema::TableColumn,
+    sql::{qualified_name, quote_identifier},
+};
+
+const SELECT_LIMIT: usize = 100;
+
+/// Statements offered in a table's context menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableTemplate {
+    SelectColumns,
+    SelectLimit,
+    Count,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    Drop,
+}
+
+impl TableTemplate {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TableTemplate::SelectColumns => "SELECT columns",
+            TableTemplate::SelectLimit => "SELECT … LIMIT 100",
+            TableTemplate::Count => "COUNT(*)",
+            TableTemplate::Insert => "INSERT template",
+            TableTemplate::Update => "UPDATE template",
+            TableTemplate::Delete => "DELETE template",
+            TableTemplate::Truncate => "Truncate…",
+            TableTemplate::Drop => "Drop…",
+        }
+    }
+
+    /// Reads run straight away, like clicking the table does.
+    pub fn runs(&self) -> bool {
+        matches!(
+            self,
+            TableTemplate::SelectColumns | TableTemplate::SelectLimit | TableTemplate::Count
+        )
+    }
+
+    /// Truncate and drop only run once the user confirms them.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, TableTemplate::Truncate | TableTemplate::Drop)
+    }
+}
+
+/// Whether a value can be written to the column, rather than being generated.
+pub fn is_writable(column: &TableColumn) -> bool {
+    column.generated != "s" && column.identity != "a"
+}
+
+/// `:name` for columns that make a valid named parameter, `$n` otherwise.
+fn placeholder(column: &str, position: &mut usize) -> String {
+    let named = column
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && column
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if named {
+        format!(":{column}")
+    } else {
+        *position += 1;
+        format!("${position}")
+    }
+}
+
+/// Builds a statement for a table, view or foreign table. Updates and deletes
+/// match rows on `key`, or on every writable column when there is no key.
+pub fn table_template(
+    template: TableTemplate,
+    kind: &str,
+    schema: &str,
+    table: &str,
+    columns: &[TableColumn],
+    key: &[String],
+) -> String {
+    let name = qualified_name(schema, table);
+    let quoted = |column: &TableColumn| quote_identifier(&column.name);
+    let writable: Vec<&TableColumn> = columns.iter().filter(|c| is_writable(c)).collect();
+    let key_columns: Vec<&TableColumn> = if key.is_empty() {
+        writable.clone()
+    } else {
+        columns.iter().filter(|c| key.contains(&c.name)).collect()
+    };
+    let mut position = 0;
+    // Without a key an update matches on the columns it sets, so the old
+    // values need parameters of their own.
+    let condition = |position: &mut usize, prefix: &str| {
+        key_columns
+            .iter()
+            .map(|column| {
+                let parameter = placeholder(&format!("{prefix}{}", column.name), position);
+                format!("{} = {parameter}", quoted(column))
+            })
+            .collect::<Vec<_>>()
+            .join("\n  AND ")
+    };
+
+    match template {
+        TableTemplate::SelectColumns => {
+            let list: Vec<String> = columns.iter().map(quoted).collect();
+            format!("SELECT\n  {}\nFROM\n  {name};", list.join(",\n  "))
+        }
+        TableTemplate::SelectLimit => {
+            format!("SELECT\n  *\nFROM\n  {name}\nLIMIT\n  {SELECT_LIMIT};")
+        }
+        TableTemplate::Count => format!("SELECT\n  count(*)\nFROM\n  {name};"),
+        TableTemplate::Insert => {
+            let list: Vec<String> = writable.iter().map(|c| quoted(c)).collect();
+            let values: Vec<String> = writable
+                .iter()
+                .map(|c| placeholder(&c.name, &mut position))
+                .collect();
+            format!(
+                "INSERT INTO {name} (\n  {}\n)\nVALUES (\n  {}\n);",
+                list.join(",\n  "),
+                values.join(",\n  ")
+            )
+        }
+        TableTemplate::Update => {
+            let assignments: Vec<String> = writable
+                .iter()
+                .filter(|c| !key.contains(&c.name))
+                .map(|c| format!("{} = {}", quoted(c), placeholder(&c.name, &mut position)))
+                .collect();
+            format!(
+                "UPDATE {name}\nSET\n  {}\nWHERE\n  {};",
+                assignments.join(",\n  "),
+                condition(&mut position, if key.is_empty() { "old_" } else { "" })
+            )
+        }
+        TableTemplate::Delete => {
+            format!(
+                "DELETE FROM {name}\nWHERE\n  {};",
+                condition(&mut position, "")
+            )
+        }
+        TableTemplate::Truncate => format!("TRUNCATE TABLE {name};"),
+        TableTemplate::Drop => match kind {
+            "view" => format!("DROP VIEW {name};"),
+            "foreign table" => format!("DROP FOREIGN TABLE {name};"),
+            _ => format!("DROP TABLE {name};"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, identity: &str, generated: &str) -> TableColumn {
+        TableColumn {
+            name: name.to_string(),
+            data_type: String::from("integer"),
+            not_null: false,
+            default_value: None,
+            identity: identity.to_string(),
+            generated: generated.to_string(),
+            comment: None,
+        }
+    }
+
+    fn columns() -> Vec<TableColumn> {
+        vec![
+            column("id", "a", ""),
+            column("unit price", "", ""),
+            column("qty", "", ""),
+            column("total", "", "s"),
+        ]
+    }
+
+    #[test]
+    fn quotes_names() {
+        assert_eq!(
+            table_template(TableTemplate::Count, "table", "Sales", "select", &[], &[]),
+            "SELECT\n  count(*)\nFROM\n  \"Sales\".\"select\";"
+        );
+        assert_eq!(
+            table_template(TableTemplate::Drop, "view", "public", "v", &[], &[]),
+            "DROP VIEW public.v;"
+        );
+    }
+
+    #[test]
+    fn inserts_leave_out_generated_columns() {
+        assert_eq!(
+            table_template(
+                TableTemplate::Insert,
+                "table",
+                "public",
+                "t",
+                &columns(),
+                &[]
+            ),
+            "INSERT INTO public.t (\n  \"unit price\",\n  qty\n)\nVALUES (\n  $1,\n  :qty\n);"
+        );
+    }
+
+    #[test]
+    fn updates_and_deletes_match_on_the_key() {
+        let key = [String::from("id")];
+        assert_eq!(
+            table_template(
+                TableTemplate::Update,
+                "table",
+                "public",
+                "t",
+                &columns(),
+                &key
+            ),
+            "UPDATE public.t\nSET\n  \"unit price\" = $1,\n  qty = :qty\nWHERE\n  id = :id;"
+        );
+        assert_eq!(
+            table_template(
+                TableTemplate::Delete,
+                "table",
+                "public",
+                "t",
+                &columns(),
+                &key
+            ),
+            "DELETE FROM public.t\nWHERE\n  id = :id;"
+        );
+        // Without a key the old values get parameters of their own.
+        assert_eq!(
+            table_template(TableTemplate::Update, "view", "public", "v", &columns(), &[]),
+            "UPDATE public.v\nSET\n  \"unit price\" = $1,\n  qty = :qty\nWHERE\n  \"unit price\" = $2\n  AND qty = :old_qty;"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::query_functions::pg_data::{IndexStats, TableEstimate, TableStats};
+
+/// Estimates of a connection's tables, keyed by schema and table name.
+pub type TableEstimates = HashMap<(String, String), TableEstimate>;
+
+/// A count shortened to three figures, e.g. `12.3k` or `4.5M`.
+pub fn approximate_count(count: i64) -> String {
+    let abs = count.unsigned_abs() as f64;
+    let (value, suffix) = if abs >= 1e9 {
+        (abs / 1e9, "G")
+    } else if abs >= 1e6 {
+        (abs / 1e6, "M")
+    } else if abs >= 1e3 {
+        (abs / 1e3, "k")
+    } else {
+        return count.to_string();
+    };
+    let sign = if count < 0 { "-" } else { "" };
+    if value >= 100.0 {
+        format!("{sign}{value:.0}{suffix}")
+    } else {
+        format!("{sign}{value:.1}{suffix}")
+    }
+}
+
+/// How long ago a vacuum or analyze ran, e.g. `3 hours ago`.
+pub fn time_ago(then: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - then).num_seconds();
+    if seconds < 60 {
+        return String::from("just now");
+    }
+    let (count, unit) = match seconds {
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+    if count == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{count} {unit}s ago")
+    }
+}
+
+/// Dead tuples as a share of all tuples, the figure autovacuum compares with
+/// its scale factor.
+pub fn dead_tuple_percent(live: i64, dead: i64) -> Option<f64> {
+    let total = live + dead;
+    (total > 0).then(|| dead as f64 * 100.0 / total as f64)
+}
+
+/// The table shown in the details panel.
+#[derive(Debug)]
+pub struct TableInfo {
+    pub key: String,
+    pub schema: String,
+    pub table: String,
+    pub stats: Option<Result<(TableStats, Vec<IndexStats>), String>>,
+    pub loading: bool,
+}
+
+impl TableInfo {
+    pub fn new(key: &str, schema: &str, table: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            schema: schema.to_string(),
+            table: table.to_string(),
+            stats: None,
+            loading: false,
+        }
+    }
+
+    pub fn is(&self, key: &str, schema: &str, table: &str) -> bool {
+        self.key == key && self.schema == schema && self.table == table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn shortens_counts() {
+        assert_eq!(approximate_count(0), "0");
+        assert_eq!(approximate_count(999), "999");
+        assert_eq!(approximate_count(1000), "1.0k");
+        assert_eq!(approximate_count(12_345), "12.3k");
+        assert_eq!(approximate_count(456_789), "457k");
+        assert_eq!(approximate_count(4_500_000), "4.5M");
+        assert_eq!(approximate_count(2_000_000_000), "2.0G");
+    }
+
+    #[test]
+    fn describes_elapsed_time() {
+        let now = Utc::now();
+        assert_eq!(time_ago(now - Duration::seconds(5), now), "just now");
+        assert_eq!(time_ago(now - Duration::minutes(1), now), "1 minute ago");
+        assert_eq!(time_ago(now - Duration::minutes(150), now), "2 hours ago");
+        assert_eq!(time_ago(now - Duration::days(40), now), "40 days ago");
+    }
+
+    #[test]
+    fn computes_dead_tuple_share() {
+        assert_eq!(dead_tuple_percent(0, 0), None);
+        assert_eq!(dead_tuple_percent(75, 25), Some(25.0));
+        assert_eq!(dead_tuple_percent(0, 10), Some(100.0));
+    }
+}
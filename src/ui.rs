@@ -1,8 +1,18 @@
 pub mod connections_panel;
+pub mod credentials_panel;
+pub mod data_compare_panel;
+pub mod data_editor_panel;
 pub mod databases_panel;
 pub mod editor_panel;
+pub mod er_diagram_panel;
+pub mod library_panel;
+pub mod object_search_panel;
 pub mod pagination_panel;
 pub mod query_metrics_panel;
+pub mod query_params_panel;
 pub mod results_table_panel;
 pub mod running_queries_panel;
+pub mod schema_compare_panel;
+pub mod table_designer_panel;
+pub mod table_info_panel;
 pub mod tables_panel;
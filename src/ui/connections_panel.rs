@@ -1,9 +1,107 @@
-use crate::{app::SavedConnection, Rosemary};
-use egui::Ui;
+use crate::{
+    app::{new_connection_id, CredentialPrompt, SavedConnection},
+    connection_file::{export_connections, import_connections},
+    connection_string::{parse_connection_string, parse_key_values, to_uri, ImportedConnection},
+    libpq::{connection_from_database_url, connection_from_pg_env, load_services},
+    ssh_tunnel::VERIFY_FULL_TUNNEL_WARNING,
+    Rosemary,
+};
+use egui::{Response, Ui};
+
+const SSL_MODES: [&str; 6] = [
+    "disable",
+    "allow",
+    "prefer",
+    "require",
+    "verify-ca",
+    "verify-full",
+];
+
+fn file_row(ui: &mut Ui, label: &str, path: &mut String) -> Response {
+    ui.label(label);
+    let response = ui
+        .horizontal(|ui| {
+            let response = ui.text_edit_singleline(path);
+            if ui.button("…").clicked() {
+                if let Some(file) = rfd::FileDialog::new().pick_file() {
+                    *path = file.display().to_string();
+                }
+            }
+            response
+        })
+        .inner;
+    ui.end_row();
+    response
+}
+
+/// Builds a new connection from the form. The password is left out so it can
+/// be stored in the vault.
+fn connection_from_form(app: &Rosemary) -> Result<SavedConnection, String> {
+    let mut params = parse_key_values(&app.db_params)?;
+    if app.db_use_ssh_tunnel {
+        app.db_ssh_tunnel.validate()?;
+    }
+    for (key, value) in [
+        ("sslmode", &app.db_ssl_mode),
+        ("sslrootcert", &app.db_ssl_root_cert),
+        ("sslcert", &app.db_ssl_cert),
+        ("sslkey", &app.db_ssl_key),
+    ] {
+        if !value.trim().is_empty() {
+            params.insert(key.to_string(), value.trim().to_string());
+        }
+    }
+    Ok(SavedConnection {
+        id: new_connection_id(),
+        connection_name: app.connection_name.clone(),
+        db_host: app.db_host.clone(),
+        db_port: app.db_port.clone(),
+        db_user: app.db_user.clone(),
+        db_password: String::new(),
+        db_name: app.db_name.clone(),
+        prompt_for_password: app.prompt_for_password,
+        params,
+        ssh_tunnel: app.db_use_ssh_tunnel.then(|| app.db_ssh_tunnel.clone()),
+        group: app.connection_group.trim().to_string(),
+        colour: app.connection_colour,
+        notes: app.connection_notes.clone(),
+        read_only: app.db_read_only,
+        production: app.db_production,
+        session: app.db_session.clone(),
+    })
+}
 
 pub fn show_connections_panel(ui: &mut Ui, app: &mut Rosemary) -> bool {
     let mut connect_to_db = false;
 
+    if app.is_connecting() {
+        ui.horizontal(|ui| show_connect_progress(ui, app));
+        ui.separator();
+    } else if !app.connect_error.is_empty() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!("Failed to connect:\n{}", app.connect_error),
+        );
+        ui.separator();
+    }
+
+    ui.label("Connection string:");
+    ui.horizontal(|ui| {
+        let input = ui
+            .text_edit_singleline(&mut app.connection_string)
+            .on_hover_text("A postgresql:// URI or key=value pairs");
+        let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Parse").clicked() || submitted {
+            match parse_connection_string(&app.connection_string) {
+                Ok(imported) => {
+                    app.import_connection(imported);
+                    app.connection_string.clear();
+                }
+                Err(e) => app.connection_form_error = e,
+            }
+        }
+    });
+
     ui.label("Connection name:");
     ui.text_edit_singleline(&mut app.connection_name);
     ui.label("Database Host:");
@@ -13,45 +111,533 @@ pub fn show_connections_panel(ui: &mut Ui, app: &mut Rosemary) -> bool {
     ui.label("Username:");
     ui.text_edit_singleline(&mut app.db_user);
     ui.label("Password:");
-    ui.horizontal(|ui| ui.add(egui::TextEdit::singleline(&mut app.db_password).password(true)));
+    ui.add_enabled_ui(!app.prompt_for_password, |ui| {
+        ui.horizontal(|ui| ui.add(egui::TextEdit::singleline(&mut app.db_password).password(true)));
+    });
+    ui.checkbox(
+        &mut app.prompt_for_password,
+        "Don't save the password, ask when connecting",
+    );
     ui.label("Database Name:");
     ui.text_edit_singleline(&mut app.db_name);
+    ui.label("Parameters:");
+    ui.text_edit_singleline(&mut app.db_params)
+        .on_hover_text("Other libpq parameters, e.g. connect_timeout=10");
+    ui.label("Group:");
+    ui.text_edit_singleline(&mut app.connection_group)
+        .on_hover_text("e.g. prod, staging or dev");
+    ui.horizontal(|ui| {
+        let mut use_colour = app.connection_colour.is_some();
+        if ui.checkbox(&mut use_colour, "Colour").changed() {
+            app.connection_colour = use_colour.then_some([200, 60, 60]);
+        }
+        if let Some(colour) = app.connection_colour.as_mut() {
+            ui.color_edit_button_srgb(colour);
+        }
+    })
+    .response
+    .on_hover_text("Tints the window while connected");
+    ui.checkbox(&mut app.db_read_only, "Read-only")
+        .on_hover_text("Sessions start with default_transaction_read_only = on");
+    ui.checkbox(&mut app.db_production, "Production")
+        .on_hover_text("Ask before running statements that change data or the schema");
+    ui.label("Notes:");
+    ui.add(egui::TextEdit::multiline(&mut app.connection_notes).desired_rows(2));
+
+    ui.collapsing("SSH tunnel", |ui| {
+        ui.checkbox(&mut app.db_use_ssh_tunnel, "Connect through an SSH tunnel");
+        ui.add_enabled_ui(app.db_use_ssh_tunnel, |ui| {
+            egui::Grid::new("connection_ssh")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    let tunnel = &mut app.db_ssh_tunnel;
+                    ui.label("SSH host:");
+                    ui.text_edit_singleline(&mut tunnel.host)
+                        .on_hover_text("The host key must already be in known_hosts");
+                    ui.end_row();
+                    ui.label("SSH port:");
+                    ui.add(egui::TextEdit::singleline(&mut tunnel.port).hint_text("22"));
+                    ui.end_row();
+                    ui.label("SSH user:");
+                    ui.text_edit_singleline(&mut tunnel.user);
+                    ui.end_row();
+                    file_row(ui, "Private key:", &mut tunnel.identity_file)
+                        .on_hover_text("Leave empty to use the SSH agent or your default keys");
+                    file_row(ui, "Known hosts:", &mut tunnel.known_hosts_file)
+                        .on_hover_text("Leave empty to use ~/.ssh/known_hosts");
+                });
+        });
+    });
 
-    if ui.button("Save").clicked() {
-        let connection = SavedConnection {
-            connection_name: app.connection_name.clone(),
-            db_host: app.db_host.clone(),
-            db_port: app.db_port.clone(),
-            db_user: app.db_user.clone(),
-            db_password: app.db_password.clone(),
-            db_name: app.db_name.clone(),
+    ui.collapsing("Session", |ui| {
+        egui::Grid::new("connection_session")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let session = &mut app.db_session;
+                for (label, value, hint) in [
+                    (
+                        "Application name:",
+                        &mut session.application_name,
+                        "Rosemary",
+                    ),
+                    ("Search path:", &mut session.search_path, "e.g. app, public"),
+                    ("Role:", &mut session.role, "SET ROLE on connect"),
+                    (
+                        "Statement timeout:",
+                        &mut session.statement_timeout,
+                        "e.g. 30s",
+                    ),
+                    ("Lock timeout:", &mut session.lock_timeout, "e.g. 5s"),
+                    (
+                        "Idle in transaction timeout:",
+                        &mut session.idle_in_transaction_session_timeout,
+                        "e.g. 10min",
+                    ),
+                    ("Time zone:", &mut session.time_zone, "e.g. UTC"),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::TextEdit::singleline(value).hint_text(hint));
+                    ui.end_row();
+                }
+            });
+        ui.label("Extra statements:");
+        ui.add(
+            egui::TextEdit::multiline(&mut app.db_session.extra_statements)
+                .code_editor()
+                .desired_rows(2)
+                .hint_text("SET work_mem = '64MB';"),
+        )
+        .on_hover_text("Run on every new session after the settings above");
+    });
+
+    ui.collapsing("TLS", |ui| {
+        egui::Grid::new("connection_tls")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("SSL mode:");
+                let selected = if app.db_ssl_mode.is_empty() {
+                    "prefer (default)"
+                } else {
+                    app.db_ssl_mode.as_str()
+                };
+                egui::ComboBox::from_id_salt("ssl_mode")
+                    .selected_text(selected.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut app.db_ssl_mode,
+                            String::new(),
+                            "prefer (default)",
+                        );
+                        for mode in SSL_MODES {
+                            ui.selectable_value(&mut app.db_ssl_mode, mode.to_string(), mode);
+                        }
+                    });
+                ui.end_row();
+                file_row(ui, "Root certificate:", &mut app.db_ssl_root_cert)
+                    .on_hover_text("CA certificate used by verify-ca and verify-full");
+                file_row(ui, "Client certificate:", &mut app.db_ssl_cert);
+                file_row(ui, "Client key:", &mut app.db_ssl_key)
+                    .on_hover_text("PEM encoded PKCS#8 private key");
+            });
+        if app.db_use_ssh_tunnel && app.db_ssl_mode == "verify-full" {
+            ui.colored_label(ui.visuals().warn_fg_color, VERIFY_FULL_TUNNEL_WARNING);
+        }
+    });
+
+    if !app.connection_form_error.is_empty() {
+        ui.colored_label(ui.visuals().error_fg_color, &app.connection_form_error);
+    }
+    for warning in &app.connection_import_warnings {
+        ui.colored_label(ui.visuals().warn_fg_color, warning);
+    }
+
+    ui.horizontal(|ui| {
+        let save_label = if app.editing_connection.is_some() {
+            "Update"
+        } else {
+            "Save"
         };
-        app.connection_list.push(connection);
+        if ui.button(save_label).clicked() {
+            match connection_from_form(app) {
+                Ok(connection) => {
+                    app.connection_form_error.clear();
+                    app.save_connection(connection);
+                }
+                Err(e) => app.connection_form_error = e,
+            }
+        }
+        if app.editing_connection.is_some() && ui.button("Cancel edit").clicked() {
+            app.fill_connection_form(SavedConnection::default());
+        }
+
+        let testing = app.connection_test_task.is_some();
+        if ui
+            .add_enabled(!testing, egui::Button::new("Test"))
+            .clicked()
+        {
+            match connection_from_form(app) {
+                Ok(connection) => {
+                    app.connection_form_error.clear();
+                    let editing = app
+                        .editing_connection
+                        .as_ref()
+                        .and_then(|id| app.connection_list.iter().find(|conn| &conn.id == id));
+                    let password = match editing {
+                        Some(saved) if app.db_password.is_empty() => {
+                            app.saved_password(saved).unwrap_or_default()
+                        }
+                        _ => app.db_password.clone(),
+                    };
+                    app.start_connection_test(connection, password);
+                }
+                Err(e) => app.connection_form_error = e,
+            }
+        }
+        if testing {
+            ui.spinner();
+        }
+    });
+    match &app.connection_test_result {
+        Some(Ok(description)) => {
+            ui.colored_label(egui::Color32::from_rgb(80, 160, 80), description);
+        }
+        Some(Err(e)) => {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        None => {}
+    }
+
+    ui.horizontal(|ui| {
+        if app.vault.is_unlocked() {
+            ui.label("🔓 Vault unlocked");
+            if ui.button("Lock").clicked() {
+                app.vault.lock();
+            }
+        } else if app.vault.exists() {
+            ui.label("🔒 Vault locked");
+            if ui.button("Unlock").clicked() {
+                app.credential_prompt = Some(CredentialPrompt::UnlockVault);
+            }
+        }
+    });
+    if app.pending_secret.is_some() {
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "A password is waiting to be saved in the vault",
+            );
+            if ui.button("Save").clicked() {
+                app.prompt_for_vault("Saved passwords are kept in an encrypted vault.");
+            }
+        });
+    }
+
+    ui.collapsing("Import", |ui| {
+        if app.should_load_pg_services {
+            app.should_load_pg_services = false;
+            app.pg_services = load_services();
+        }
+
+        ui.horizontal(|ui| {
+            let pg_env = connection_from_pg_env();
+            if ui
+                .add_enabled(pg_env.is_some(), egui::Button::new("From PG* variables"))
+                .on_disabled_hover_text("PGHOST, PGPORT, PGUSER and PGDATABASE are not set")
+                .clicked()
+            {
+                if let Some(connection) = pg_env {
+                    app.import_connection(connection.into());
+                }
+            }
+
+            let database_url = connection_from_database_url();
+            if ui
+                .add_enabled(
+                    database_url.is_some(),
+                    egui::Button::new("From DATABASE_URL"),
+                )
+                .on_disabled_hover_text("DATABASE_URL is not set")
+                .clicked()
+            {
+                if let Some(imported) = database_url {
+                    app.import_connection(imported);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("pg_service.conf ( {} )", app.pg_services.len()));
+            if ui.button("⟳").on_hover_text("Reload").clicked() {
+                app.should_load_pg_services = true;
+            }
+        });
+        let mut service_to_import = None;
+        for service in &app.pg_services {
+            let description = [
+                service.params.get("host"),
+                service.params.get("port"),
+                service.params.get("dbname"),
+            ]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" / ");
+            if ui
+                .button(&service.name)
+                .on_hover_text(description)
+                .clicked()
+            {
+                service_to_import = Some(service.to_connection());
+            }
+        }
+        if let Some(imported) = service_to_import {
+            app.import_connection(imported);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Export…").clicked() {
+            if let Some(path) = connection_file_dialog()
+                .set_file_name("connections.json")
+                .save_file()
+            {
+                if let Err(e) = export_connections(&path, &app.connection_list) {
+                    app.connection_form_error = e;
+                }
+            }
+        }
+        if ui.button("Import…").clicked() {
+            if let Some(path) = connection_file_dialog().pick_file() {
+                match import_connections(&path, &app.connection_list) {
+                    Ok(imported) => {
+                        app.connection_import_warnings.clear();
+                        for ImportedConnection {
+                            connection,
+                            warnings,
+                        } in imported
+                        {
+                            app.connection_import_warnings.extend(
+                                warnings
+                                    .iter()
+                                    .map(|w| format!("{}: {w}", connection.connection_name)),
+                            );
+                            app.connection_list.push(connection);
+                        }
+                    }
+                    Err(e) => app.connection_form_error = e,
+                }
+            }
+        }
+    })
+    .response
+    .on_hover_text("Passwords are not exported");
+
+    // Groups are shown in the order they first appear in the list.
+    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (idx, connection) in app.connection_list.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|(group, _)| *group == connection.group)
+        {
+            Some((_, members)) => members.push(idx),
+            None => groups.push((&connection.group, vec![idx])),
+        }
     }
+    groups.sort_by_key(|(group, _)| !group.is_empty());
 
-    let mut idx_to_remove: usize = 0;
-    let mut delete_connection = false;
+    let mut action = None;
 
     egui::ScrollArea::vertical()
         .id_salt("connection_list")
         .show(ui, |ui| {
-            for (idx, connection) in app.connection_list.iter().enumerate() {
-                ui.horizontal(|ui| {
-                    if ui.button(&connection.connection_name).clicked() {
-                        app.connect_to_idx = idx;
-                        connect_to_db = true;
+            for (group, members) in &groups {
+                let mut show_rows = |ui: &mut Ui| {
+                    for &idx in members {
+                        if let Some(row_action) = show_connection_row(ui, app, idx) {
+                            action = Some(row_action);
+                        }
                     }
-                    if ui.button("ｘ").clicked() {
-                        idx_to_remove = idx;
-                        delete_connection = true;
-                    }
-                });
+                };
+                if group.is_empty() {
+                    show_rows(ui);
+                } else {
+                    ui.push_id(group, |ui| {
+                        egui::CollapsingHeader::new(format!("{} ( {} )", group, members.len()))
+                            .default_open(true)
+                            .show(ui, show_rows);
+                    });
+                }
             }
         });
 
-    if delete_connection {
-        app.connection_list.remove(idx_to_remove);
+    match action {
+        Some(ConnectionAction::Connect(idx)) => {
+            app.connect_to_id = app.connection_list[idx].id.clone();
+            app.selected_db.clear();
+            connect_to_db = true;
+        }
+        Some(ConnectionAction::Edit(idx)) => app.edit_connection(idx),
+        Some(ConnectionAction::Duplicate(idx)) => app.duplicate_connection(idx),
+        Some(ConnectionAction::Delete(idx)) => {
+            app.pending_connection_delete = Some(app.connection_list[idx].id.clone())
+        }
+        Some(ConnectionAction::Move { from, before }) => app.move_connection(from, before),
+        None => {}
     }
 
     connect_to_db
 }
+
+enum ConnectionAction {
+    Connect(usize),
+    Edit(usize),
+    Duplicate(usize),
+    Delete(usize),
+    Move { from: usize, before: usize },
+}
+
+fn connection_file_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .add_filter("JSON", &["json"])
+        .add_filter("TOML", &["toml"])
+}
+
+fn show_connection_row(ui: &mut Ui, app: &Rosemary, idx: usize) -> Option<ConnectionAction> {
+    let connection = &app.connection_list[idx];
+    let mut action = None;
+
+    let row = ui.horizontal(|ui| {
+        ui.dnd_drag_source(
+            egui::Id::new(("connection_drag", &connection.id)),
+            idx,
+            |ui| {
+                ui.label("☰");
+            },
+        )
+        .response
+        .on_hover_text("Drag to reorder");
+
+        if let Some([r, g, b]) = connection.colour {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(8.0, 16.0), egui::Sense::hover());
+            ui.painter()
+                .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+        }
+
+        let mut hover = format!("{}:{}", connection.db_host, connection.db_port);
+        if !connection.notes.is_empty() {
+            hover += &format!("\n\n{}", connection.notes);
+        }
+        let button = ui.button(&connection.connection_name).on_hover_text(hover);
+        let open = app
+            .live_connections
+            .iter()
+            .filter(|live| live.connection_id == connection.id)
+            .count();
+        if open > 0 {
+            ui.colored_label(egui::Color32::from_rgb(80, 160, 80), "●")
+                .on_hover_text(format!("{open} open"));
+        }
+        if connection.production {
+            ui.colored_label(ui.visuals().error_fg_color, "PROD");
+        }
+        if connection.read_only {
+            ui.weak("read-only");
+        }
+        if button.clicked() {
+            action = Some(ConnectionAction::Connect(idx));
+        }
+        button.context_menu(|ui| {
+            if ui.button("Edit").clicked() {
+                action = Some(ConnectionAction::Edit(idx));
+                ui.close_menu();
+            }
+            if ui.button("Duplicate").clicked() {
+                action = Some(ConnectionAction::Duplicate(idx));
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Copy as URI").clicked() {
+                ui.ctx().copy_text(to_uri(connection, None));
+                ui.close_menu();
+            }
+            let password = app.saved_password(connection);
+            if ui
+                .add_enabled(
+                    password.is_some(),
+                    egui::Button::new("Copy as URI with password"),
+                )
+                .on_disabled_hover_text("No saved password, or the vault is locked")
+                .clicked()
+            {
+                ui.ctx().copy_text(to_uri(connection, password.as_deref()));
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Delete").clicked() {
+                action = Some(ConnectionAction::Delete(idx));
+                ui.close_menu();
+            }
+        });
+        if ui.button("ｘ").on_hover_text("Delete").clicked() {
+            action = Some(ConnectionAction::Delete(idx));
+        }
+    });
+
+    // Dropping a connection on a row moves it in front of that row.
+    let response = row.response;
+    if response.dnd_hover_payload::<usize>().is_some() {
+        let stroke = ui.visuals().selection.stroke;
+        ui.painter()
+            .hline(response.rect.x_range(), response.rect.top(), stroke);
+    }
+    if let Some(from) = response.dnd_release_payload::<usize>() {
+        action = Some(ConnectionAction::Move {
+            from: *from,
+            before: idx,
+        });
+    }
+
+    action
+}
+
+pub fn show_connect_progress(ui: &mut Ui, app: &mut Rosemary) {
+    let name = &app.connecting_to;
+    let elapsed = app
+        .connect_started
+        .map_or(0, |started| started.elapsed().as_secs());
+    ui.spinner();
+    ui.label(format!("Connecting to {name}… {elapsed}s"));
+    if ui.button("Cancel").clicked() {
+        app.cancel_connect();
+    }
+}
+
+pub fn show_connection_status(ui: &mut Ui, app: &Rosemary) {
+    let Some(live) = app.active_connection() else {
+        return;
+    };
+    let name = live.label();
+
+    match &live.ssl_status {
+        Some(status) if status.ssl == Some(true) => {
+            let details: Vec<String> = [
+                status.version.clone(),
+                status.cipher.clone(),
+                status.bits.map(|bits| format!("{bits} bits")),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            ui.label(format!("🔒 {name}"))
+                .on_hover_text(format!("Encrypted: {}", details.join(", ")));
+        }
+        Some(_) => {
+            ui.label(format!("🔓 {name}"))
+                .on_hover_text("Not encrypted");
+        }
+        None => {
+            ui.label(name);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::app::{CredentialPrompt, Rosemary};
+use egui::Ui;
+
+pub fn show_credentials_panel(ui: &mut Ui, app: &mut Rosemary) -> bool {
+    let Some(prompt) = app.credential_prompt else {
+        return false;
+    };
+    let mut submit = false;
+
+    if !app.credential_reason.is_empty() {
+        ui.label(&app.credential_reason);
+    }
+
+    let label = match prompt {
+        CredentialPrompt::CreateVault => "New master password:",
+        CredentialPrompt::UnlockVault => "Master password:",
+        CredentialPrompt::ConnectionPassword => "Password:",
+    };
+    ui.label(label);
+    let input = ui.add(egui::TextEdit::singleline(&mut app.credential_input).password(true));
+    if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+        submit = true;
+    }
+
+    if prompt == CredentialPrompt::CreateVault {
+        ui.label("Confirm master password:");
+        let confirm =
+            ui.add(egui::TextEdit::singleline(&mut app.credential_confirm).password(true));
+        if confirm.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            submit = true;
+        }
+        ui.weak("The master password can't be recovered if it's lost.");
+    }
+
+    if !app.credential_error.is_empty() {
+        ui.colored_label(ui.visuals().error_fg_color, &app.credential_error);
+    }
+
+    let button = match prompt {
+        CredentialPrompt::CreateVault => "Create vault",
+        CredentialPrompt::UnlockVault => "Unlock",
+        CredentialPrompt::ConnectionPassword => "Connect",
+    };
+    if ui.button(button).clicked() {
+        submit = true;
+    }
+
+    submit
+}
@@ -0,0 +1,282 @@
+use crate::{
+    data_diff::{DataCompare, RowDiff, MAX_DIFFERENCES},
+    sql::qualified_name,
+    ui::schema_compare_panel::connection_picker,
+};
+use egui::{Color32, RichText, Ui};
+use egui_extras::{Column as eguiColumn, TableBuilder};
+
+pub enum DataCompareAction {
+    Compare,
+    Cancel,
+    OpenInEditor(String),
+}
+
+fn table_picker(
+    ui: &mut Ui,
+    id: &str,
+    table: &mut Option<(String, String)>,
+    tables: &[(String, String)],
+) {
+    let selected = table
+        .as_ref()
+        .map_or(String::from("Pick a table"), |(schema, name)| {
+            qualified_name(schema, name)
+        });
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected)
+        .height(400.0)
+        .show_ui(ui, |ui| {
+            for (schema, name) in tables {
+                let value = Some((schema.clone(), name.clone()));
+                ui.selectable_value(table, value, qualified_name(schema, name));
+            }
+        });
+}
+
+fn cell(ui: &mut Ui, value: &Option<String>, colour: Option<Color32>) {
+    let text = match value {
+        Some(value) => RichText::new(value),
+        None => RichText::new("NULL").weak(),
+    };
+    ui.label(match colour {
+        Some(colour) => text.color(colour),
+        None => text,
+    });
+}
+
+fn show_rows(ui: &mut Ui, compare: &DataCompare) {
+    let Some(diff) = &compare.result else {
+        return;
+    };
+    let rows: Vec<&RowDiff> = diff
+        .rows
+        .iter()
+        .filter(|row| match row {
+            RowDiff::Missing(_) => compare.show_missing,
+            RowDiff::Extra(_) => compare.show_extra,
+            RowDiff::Changed { .. } => compare.show_changed,
+        })
+        .collect();
+    let missing = Color32::from_rgb(110, 190, 110);
+    let extra = ui.visuals().error_fg_color;
+    let changed = ui.visuals().warn_fg_color;
+
+    let text_height = 22.0;
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(eguiColumn::exact(24.0));
+        for _ in &diff.columns {
+            table = table.column(eguiColumn::initial(140.0).at_least(40.0).clip(true));
+        }
+        table
+            .header(text_height, |mut header| {
+                header.col(|_| {});
+                for (idx, name) in diff.columns.iter().enumerate() {
+                    header.col(|ui| {
+                        if diff.key.contains(&idx) {
+                            ui.strong(format!("🔑 {name}"));
+                        } else {
+                            ui.strong(name);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(text_height, rows.len(), |mut row| match rows[row.index()] {
+                    RowDiff::Missing(values) => {
+                        row.col(|ui| {
+                            ui.colored_label(missing, "+")
+                                .on_hover_text("Missing from the target");
+                        });
+                        for value in values {
+                            row.col(|ui| cell(ui, value, Some(missing)));
+                        }
+                    }
+                    RowDiff::Extra(values) => {
+                        row.col(|ui| {
+                            ui.colored_label(extra, "−")
+                                .on_hover_text("Only in the target");
+                        });
+                        for value in values {
+                            row.col(|ui| cell(ui, value, Some(extra)));
+                        }
+                    }
+                    RowDiff::Changed {
+                        source,
+                        target,
+                        columns,
+                    } => {
+                        row.col(|ui| {
+                            ui.colored_label(changed, "≠")
+                                .on_hover_text("Different values");
+                        });
+                        for idx in 0..source.len() {
+                            row.col(|ui| {
+                                if !columns.contains(&idx) {
+                                    cell(ui, &source[idx], None);
+                                    return;
+                                }
+                                let show = |value: &Option<String>| {
+                                    value.as_deref().unwrap_or("NULL").to_string()
+                                };
+                                ui.colored_label(
+                                    changed,
+                                    format!("{} → {}", show(&target[idx]), show(&source[idx])),
+                                )
+                                .on_hover_text(format!(
+                                    "Target: {}\nSource: {}",
+                                    show(&target[idx]),
+                                    show(&source[idx])
+                                ));
+                            });
+                        }
+                    }
+                });
+            });
+    });
+}
+
+pub fn show_data_compare_panel(
+    ui: &mut Ui,
+    compare: &mut DataCompare,
+    connections: &[(String, String)],
+    source_tables: &[(String, String)],
+    target_tables: &[(String, String)],
+) -> Option<DataCompareAction> {
+    let mut action = None;
+    let running = compare.is_running();
+
+    ui.add_enabled_ui(!running, |ui| {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut compare.use_queries, false, "Tables");
+            ui.radio_value(&mut compare.use_queries, true, "Queries");
+        });
+        ui.columns(2, |columns| {
+            let sides = [
+                (
+                    "Source",
+                    &mut compare.source_key,
+                    &mut compare.source_table,
+                    &mut compare.source_sql,
+                    source_tables,
+                ),
+                (
+                    "Target",
+                    &mut compare.target_key,
+                    &mut compare.target_table,
+                    &mut compare.target_sql,
+                    target_tables,
+                ),
+            ];
+            let use_queries = compare.use_queries;
+            for (ui, (label, key, table, sql, tables)) in columns.iter_mut().zip(sides) {
+                ui.horizontal(|ui| {
+                    ui.strong(label);
+                    connection_picker(ui, &format!("data_compare_{label}"), key, connections);
+                    if !use_queries {
+                        table_picker(ui, &format!("data_compare_table_{label}"), table, tables);
+                    }
+                });
+                if use_queries {
+                    ui.add(
+                        egui::TextEdit::multiline(sql)
+                            .code_editor()
+                            .desired_rows(4)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("SELECT …"),
+                    );
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Key columns:");
+            let hint = if compare.use_queries {
+                "id, other_id"
+            } else {
+                "The source table's primary key"
+            };
+            ui.add(egui::TextEdit::singleline(&mut compare.key_columns).hint_text(hint));
+        });
+    });
+
+    ui.horizontal(|ui| {
+        if running {
+            if ui.button("Cancel").clicked() {
+                action = Some(DataCompareAction::Cancel);
+            }
+            ui.spinner();
+            ui.label(format!("Compared {} rows", compare.progress));
+        } else if ui.button("Compare").clicked() {
+            action = Some(DataCompareAction::Compare);
+        }
+        if let Some(diff) = &compare.result {
+            ui.separator();
+            ui.checkbox(
+                &mut compare.show_missing,
+                format!("{} missing", diff.missing),
+            );
+            ui.checkbox(&mut compare.show_extra, format!("{} extra", diff.extra));
+            ui.checkbox(
+                &mut compare.show_changed,
+                format!("{} changed", diff.changed),
+            );
+            ui.weak(format!("{} the same", diff.same));
+        }
+    });
+    if let Some(e) = &compare.error {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+    let Some(diff) = &compare.result else {
+        return action;
+    };
+    if !diff.is_complete() {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("Only the first {MAX_DIFFERENCES} differences are listed and synced."),
+        );
+    }
+    ui.separator();
+
+    if !diff.is_empty() {
+        egui::TopBottomPanel::bottom("data_compare_script")
+            .resizable(true)
+            .default_height(140.0)
+            .show_inside(ui, |ui| {
+                let Some(table) = &compare.sync_table else {
+                    ui.weak("A sync script can only be written when the target is a table.");
+                    return;
+                };
+                let mut script = compare.script.as_str();
+                ui.horizontal(|ui| {
+                    ui.strong(format!("Sync script for {table}"));
+                    if ui.button("Open in editor").clicked() {
+                        action = Some(DataCompareAction::OpenInEditor(script.to_string()));
+                    }
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(script.to_string());
+                    }
+                });
+                egui::ScrollArea::both()
+                    .id_salt("data_compare_script_scroll")
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut script)
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+            });
+    }
+
+    if diff.is_empty() {
+        ui.label("The rows are the same.");
+    } else {
+        show_rows(ui, compare);
+    }
+
+    action
+}
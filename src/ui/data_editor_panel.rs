@@ -0,0 +1,251 @@
+use crate::data_editor::{DataEditor, NewCell};
+use egui::Ui;
+use egui_extras::{Column as eguiColumn, TableBuilder};
+
+pub enum DataEditorAction {
+    Reload,
+    Save,
+    PreviousPage,
+    NextPage,
+}
+
+/// A text field for a cell. Returns the new text when it was edited.
+fn cell_edit(
+    ui: &mut Ui,
+    value: Option<&str>,
+    hint: &str,
+    editable: bool,
+    modified: bool,
+) -> (egui::Response, Option<String>) {
+    let mut text = value.unwrap_or_default().to_string();
+    let mut edit = egui::TextEdit::singleline(&mut text)
+        .hint_text(if value.is_none() { hint } else { "" })
+        .desired_width(f32::INFINITY)
+        .interactive(editable);
+    if modified {
+        edit = edit.text_color(ui.visuals().warn_fg_color);
+    }
+    let response = ui.add(edit);
+    let changed = response.changed();
+    (response, changed.then_some(text))
+}
+
+pub fn show_data_editor_panel(ui: &mut Ui, editor: &mut DataEditor) -> Option<DataEditorAction> {
+    let mut action = None;
+    let busy = editor.loading || editor.saving;
+
+    ui.horizontal(|ui| {
+        let dirty = editor.is_dirty();
+        ui.add_enabled_ui(!busy && !dirty, |ui| {
+            if ui
+                .add_enabled(editor.offset > 0, egui::Button::new("⏴"))
+                .clicked()
+            {
+                action = Some(DataEditorAction::PreviousPage);
+            }
+            if !editor.rows.is_empty() {
+                ui.label(format!(
+                    "Rows {}–{}",
+                    editor.offset + 1,
+                    editor.offset + editor.rows.len()
+                ));
+            }
+            if ui
+                .add_enabled(editor.has_more, egui::Button::new("⏵"))
+                .clicked()
+            {
+                action = Some(DataEditorAction::NextPage);
+            }
+        })
+        .response
+        .on_disabled_hover_text("Save or discard the changes first");
+        ui.add_enabled_ui(!busy, |ui| {
+            if ui.button("⟳").on_hover_text("Reload").clicked() {
+                action = Some(DataEditorAction::Reload);
+            }
+        });
+        ui.separator();
+        ui.add_enabled_ui(!busy && editor.read_only.is_none(), |ui| {
+            if ui.button("Add row").clicked() {
+                editor.add_row();
+            }
+            if ui.add_enabled(dirty, egui::Button::new("Save")).clicked() {
+                if editor.production.is_some() {
+                    editor.confirm_save = true;
+                } else {
+                    action = Some(DataEditorAction::Save);
+                }
+            }
+            if ui
+                .add_enabled(dirty, egui::Button::new("Discard"))
+                .clicked()
+            {
+                editor.discard();
+            }
+        });
+        if busy {
+            ui.spinner();
+        }
+    });
+
+    if let Some(reason) = &editor.read_only {
+        ui.colored_label(ui.visuals().warn_fg_color, reason);
+    }
+    if let Some(e) = &editor.error {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    } else if !editor.status.is_empty() {
+        ui.weak(&editor.status);
+    }
+    if editor.confirm_save {
+        if let Some(name) = &editor.production {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ These changes are saved to the production connection \"{name}\"."),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Save anyway").clicked() {
+                action = Some(DataEditorAction::Save);
+            }
+            if ui.button("Cancel").clicked() {
+                editor.confirm_save = false;
+            }
+        });
+    }
+    ui.separator();
+
+    let text_height = 22.0;
+    let loaded = editor.rows.len();
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(eguiColumn::exact(24.0));
+        for _ in &editor.columns {
+            table = table.column(eguiColumn::initial(140.0).at_least(40.0).clip(true));
+        }
+
+        table
+            .header(text_height, |mut header| {
+                header.col(|_| {});
+                for column in &editor.columns {
+                    header.col(|ui| {
+                        ui.strong(&column.name);
+                        ui.weak(&column.data_type);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(text_height, loaded + editor.inserted.len(), |mut row| {
+                    let idx = row.index();
+                    if idx < loaded {
+                        let deleted = editor.deleted.contains(&idx);
+                        row.col(|ui| {
+                            let (label, hover) = if deleted {
+                                ("↺", "Keep this row")
+                            } else {
+                                ("🗑", "Delete this row")
+                            };
+                            if ui
+                                .add_enabled(
+                                    editor.read_only.is_none(),
+                                    egui::Button::new(label).small(),
+                                )
+                                .on_hover_text(hover)
+                                .clicked()
+                            {
+                                if deleted {
+                                    editor.deleted.remove(&idx);
+                                } else {
+                                    editor.deleted.insert(idx);
+                                }
+                            }
+                        });
+                        for column in 0..editor.columns.len() {
+                            row.col(|ui| {
+                                let editable = !deleted && editor.is_editable(column);
+                                let modified = editor.edits.contains_key(&(idx, column));
+                                let value = editor.value(idx, column).map(String::from);
+                                let (response, edited) =
+                                    cell_edit(ui, value.as_deref(), "NULL", editable, modified);
+                                if let Some(text) = edited {
+                                    editor.set_value(idx, column, Some(text));
+                                }
+                                if editable {
+                                    response.context_menu(|ui| {
+                                        if ui.button("Set NULL").clicked() {
+                                            editor.set_value(idx, column, None);
+                                            ui.close_menu();
+                                        }
+                                        if ui
+                                            .add_enabled(modified, egui::Button::new("Revert"))
+                                            .clicked()
+                                        {
+                                            editor.edits.remove(&(idx, column));
+                                            ui.close_menu();
+                                        }
+                                    });
+                                }
+                            });
+                        }
+                    } else {
+                        let new_idx = idx - loaded;
+                        row.col(|ui| {
+                            if ui
+                                .small_button("ｘ")
+                                .on_hover_text("Don't add this row")
+                                .clicked()
+                            {
+                                editor.inserted.remove(new_idx);
+                            }
+                        });
+                        for column in 0..editor.columns.len() {
+                            row.col(|ui| {
+                                let Some(cell) = editor
+                                    .inserted
+                                    .get(new_idx)
+                                    .and_then(|cells| cells.get(column))
+                                    .cloned()
+                                else {
+                                    return;
+                                };
+                                if !editor.is_editable(column) {
+                                    ui.weak("generated");
+                                    return;
+                                }
+                                let (value, hint) = match &cell {
+                                    NewCell::Default => (None, "DEFAULT"),
+                                    NewCell::Value(value) => (value.as_deref(), "NULL"),
+                                };
+                                let (response, edited) = cell_edit(ui, value, hint, true, true);
+                                let cells = &mut editor.inserted[new_idx];
+                                if let Some(text) = edited {
+                                    cells[column] = NewCell::Value(Some(text));
+                                }
+                                response.context_menu(|ui| {
+                                    if ui.button("Set NULL").clicked() {
+                                        cells[column] = NewCell::Value(None);
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Use DEFAULT").clicked() {
+                                        cells[column] = NewCell::Default;
+                                        ui.close_menu();
+                                    }
+                                });
+                            });
+                        }
+                    }
+                });
+            });
+    });
+
+    if loaded == 0 && editor.inserted.is_empty() && !editor.loading {
+        ui.weak(if editor.offset == 0 {
+            "The table is empty."
+        } else {
+            "No more rows."
+        });
+    }
+    action
+}
@@ -1,5 +1,188 @@
-use crate::{app::Rosemary, query_functions::pg_query_handlers::format_sql};
-use egui::{Layout, TextEdit, TextStyle, Ui};
+use crate::{
+    app::Rosemary,
+    find_replace::{highlight_matches, match_ranges},
+    library::find_snippet,
+    query_functions::{pg_query_handlers::format_sql, pg_session::TransactionStatus},
+};
+use egui::{
+    text::{CCursor, CCursorRange},
+    Layout, TextEdit, TextStyle, Ui,
+};
+
+pub fn editor_id() -> egui::Id {
+    egui::Id::new("rosemary_code_editor")
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(idx, _)| idx)
+}
+
+fn set_editor_cursor(ctx: &egui::Context, char_index: usize) {
+    if let Some(mut state) = TextEdit::load_state(ctx, editor_id()) {
+        state
+            .cursor
+            .set_char_range(Some(CCursorRange::one(CCursor::new(char_index))));
+        TextEdit::store_state(ctx, editor_id(), state);
+    }
+}
+
+/// Inserts text at the editor's cursor, or appends it if the editor has no cursor yet.
+pub fn insert_into_editor(ctx: &egui::Context, code: &mut String, text: &str) {
+    let cursor = TextEdit::load_state(ctx, editor_id())
+        .and_then(|state| state.cursor.char_range())
+        .map(|range| range.primary.index);
+
+    match cursor {
+        Some(char_index) => {
+            code.insert_str(byte_index(code, char_index), text);
+            set_editor_cursor(ctx, char_index + text.chars().count());
+        }
+        None => code.push_str(text),
+    }
+}
+
+/// Expands `trigger*` before the cursor into the matching library snippet when Tab is pressed.
+fn expand_snippet(ui: &Ui, app: &mut Rosemary) {
+    let ctx = ui.ctx();
+    if !ctx.memory(|m| m.has_focus(editor_id())) {
+        return;
+    }
+    if !ctx.input(|i| i.key_pressed(egui::Key::Tab) && i.modifiers.is_none()) {
+        return;
+    }
+    let Some(cursor) = TextEdit::load_state(ctx, editor_id())
+        .and_then(|state| state.cursor.char_range())
+        .filter(|range| range.primary == range.secondary)
+        .map(|range| range.primary.index)
+    else {
+        return;
+    };
+
+    let end = byte_index(&app.code, cursor);
+    let Some(before) = app.code[..end].strip_suffix('*') else {
+        return;
+    };
+    let start = before
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(idx, c)| idx + c.len_utf8());
+    let trigger = &before[start..];
+    if trigger.is_empty() {
+        return;
+    }
+
+    if let Some(snippet) = find_snippet(&app.library_entries, trigger) {
+        let body = snippet.body.clone();
+        ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab));
+        let new_cursor = app.code[..start].chars().count() + body.chars().count();
+        app.code.replace_range(start..end, &body);
+        set_editor_cursor(ctx, new_cursor);
+    }
+}
+
+fn handle_find_shortcuts(ui: &Ui, app: &mut Rosemary) {
+    let find_replace = &mut app.find_replace;
+    if ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+        find_replace.open = true;
+        find_replace.show_replace = false;
+        ui.memory_mut(|m| m.request_focus(find_id()));
+    }
+    if ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::H)) {
+        find_replace.open = true;
+        find_replace.show_replace = true;
+        ui.memory_mut(|m| m.request_focus(find_id()));
+    }
+    if find_replace.open && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+        find_replace.open = false;
+    }
+}
+
+fn find_id() -> egui::Id {
+    egui::Id::new("rosemary_find_query")
+}
+
+fn show_find_bar(ui: &mut Ui, app: &mut Rosemary) {
+    let matches = app.find_replace.find_matches(&app.code);
+    let find_replace = &mut app.find_replace;
+    if find_replace.current >= matches.len() {
+        find_replace.current = 0;
+    }
+
+    ui.horizontal(|ui| {
+        let query = ui.add(
+            egui::TextEdit::singleline(&mut find_replace.query)
+                .id(find_id())
+                .hint_text("Find")
+                .desired_width(180.0),
+        );
+        if query.changed() {
+            find_replace.current = 0;
+            find_replace.scroll_to_current = true;
+        }
+        if query.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            let forward = !ui.input(|i| i.modifiers.shift);
+            find_replace.step(matches.len(), forward);
+            query.request_focus();
+        }
+
+        let case = ui
+            .toggle_value(&mut find_replace.case_sensitive, "Aa")
+            .on_hover_text("Match case");
+        let word = ui
+            .toggle_value(&mut find_replace.whole_word, "W")
+            .on_hover_text("Whole word");
+        let regex = ui
+            .toggle_value(&mut find_replace.use_regex, ".*")
+            .on_hover_text("Regular expression");
+        if case.changed() || word.changed() || regex.changed() {
+            find_replace.current = 0;
+            find_replace.scroll_to_current = true;
+        }
+
+        match find_replace.regex() {
+            Some(Err(_)) => {
+                ui.colored_label(ui.visuals().error_fg_color, "Invalid regex");
+            }
+            Some(Ok(_)) if matches.is_empty() => {
+                ui.label("No results");
+            }
+            Some(Ok(_)) => {
+                ui.label(format!("{} of {}", find_replace.current + 1, matches.len()));
+            }
+            None => {}
+        }
+
+        if ui.button("⬆").on_hover_text("Previous match").clicked() {
+            find_replace.step(matches.len(), false);
+        }
+        if ui.button("⬇").on_hover_text("Next match").clicked() {
+            find_replace.step(matches.len(), true);
+        }
+        ui.toggle_value(&mut find_replace.show_replace, "Replace");
+        if ui.button("ｘ").clicked() {
+            find_replace.open = false;
+        }
+    });
+
+    if find_replace.show_replace {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut find_replace.replacement)
+                    .hint_text("Replace")
+                    .desired_width(180.0),
+            );
+            if ui.button("Replace").clicked() {
+                find_replace.replace_current(&mut app.code);
+            }
+            if ui.button("Replace all").clicked() {
+                find_replace.replace_all(&mut app.code);
+            }
+        });
+    }
+}
 
 pub fn show_editor_panel(
     ui: &mut Ui,
@@ -9,14 +192,36 @@ pub fn show_editor_panel(
 ) {
     let theme = egui_extras::syntax_highlighting::CodeTheme::from_memory(ui.ctx(), ui.style());
 
+    handle_find_shortcuts(ui, app);
+    if app.find_replace.open {
+        show_find_bar(ui, app);
+    }
+
+    expand_snippet(ui, app);
+
+    let match_regex = app
+        .find_replace
+        .open
+        .then(|| app.find_replace.regex()?.as_ref().ok().cloned())
+        .flatten();
+    let current_match = app.find_replace.current;
     let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
         let mut layout_job = egui_extras::syntax_highlighting::highlight(
             ui.ctx(),
             ui.style(),
             &theme,
             string,
-            "sql".into(),
+            "sql",
         );
+        if let Some(regex) = &match_regex {
+            highlight_matches(
+                &mut layout_job,
+                &match_ranges(regex, string),
+                current_match,
+                ui.visuals().selection.bg_fill.gamma_multiply(0.5),
+                ui.visuals().warn_fg_color.gamma_multiply(0.6),
+            );
+        }
         layout_job.wrap.max_width = wrap_width;
         ui.fonts(|f| f.layout_job(layout_job))
     };
@@ -27,20 +232,98 @@ pub fn show_editor_panel(
     } else {
         available_height * 0.95
     };
+
+    if let Some(path) = &app.editor_file {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let label = if app.is_editor_dirty() {
+            format!("{file_name} ●")
+        } else {
+            file_name
+        };
+        ui.label(label).on_hover_text(path.display().to_string());
+    }
+
     egui::ScrollArea::vertical()
         .id_salt("code_editor")
         .max_height(max_height)
         .show(ui, |ui| {
-            ui.add(
-                TextEdit::multiline(&mut app.code)
-                    .font(TextStyle::Monospace)
-                    .desired_rows(60)
-                    .code_editor()
-                    .desired_width(f32::INFINITY)
-                    .layouter(&mut layouter),
-            );
+            let output = TextEdit::multiline(&mut app.code)
+                .id(editor_id())
+                .font(TextStyle::Monospace)
+                .desired_rows(60)
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter)
+                .show(ui);
+
+            if app.find_replace.scroll_to_current {
+                app.find_replace.scroll_to_current = false;
+                let matches = app.find_replace.find_matches(&app.code);
+                if let Some(m) = matches.get(app.find_replace.current) {
+                    let char_index = app.code[..m.start].chars().count();
+                    let rect = output
+                        .galley
+                        .pos_from_ccursor(CCursor::new(char_index))
+                        .translate(output.galley_pos.to_vec2());
+                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                }
+            }
         });
 
+    ui.horizontal(|ui| {
+        let selected = app
+            .active_connection()
+            .map_or_else(|| String::from("Not connected"), |live| live.label());
+        let mut switch_to = None;
+        egui::ComboBox::from_id_salt("editor_connection")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for live in &app.live_connections {
+                    let active = live.key == app.active_connection_key;
+                    if ui.selectable_label(active, live.label()).clicked() {
+                        switch_to = Some(live.key.clone());
+                    }
+                }
+            })
+            .response
+            .on_hover_text("The connection this editor runs queries on");
+        if let Some(key) = switch_to {
+            app.set_active_connection(&key);
+        }
+
+        ui.checkbox(&mut app.auto_commit, "Auto-commit");
+
+        let transaction_open = app.transaction_status.is_open() && !app.ending_transaction;
+        if ui
+            .add_enabled(transaction_open, egui::Button::new("Commit"))
+            .clicked()
+        {
+            app.finish_transaction(ui.ctx(), true);
+        }
+        if ui
+            .add_enabled(transaction_open, egui::Button::new("Rollback"))
+            .clicked()
+        {
+            app.finish_transaction(ui.ctx(), false);
+        }
+
+        let status_colour = match app.transaction_status {
+            TransactionStatus::Idle => ui.visuals().weak_text_color(),
+            TransactionStatus::InTransaction => ui.visuals().warn_fg_color,
+            TransactionStatus::Failed => ui.visuals().error_fg_color,
+        };
+        ui.colored_label(
+            status_colour,
+            format!("● {}", app.transaction_status.label()),
+        );
+        if let Some(e) = &app.transaction_error {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+    });
+
     ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
         if ui.add(egui::Button::new("Execute")).clicked() {
             if ui.ctx().input(|i| i.modifiers.shift) {
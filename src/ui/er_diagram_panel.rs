@@ -0,0 +1,183 @@
+use crate::er_diagram::{ErDiagram, FONT_SIZE, HEADER_HEIGHT, MARKER_WIDTH, PADDING, ROW_HEIGHT};
+use egui::{epaint::CubicBezierShape, Align2, FontId, Sense, Stroke, Ui};
+
+pub enum DiagramAction {
+    Reload,
+    /// Table positions changed and should be saved.
+    LayoutChanged,
+    ExportSvg,
+    ExportPng,
+}
+
+/// Checkboxes for the tables shown in the diagram.
+fn table_list(ui: &mut Ui, diagram: &mut ErDiagram) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        if ui.small_button("All").clicked() {
+            diagram.hidden.clear();
+            changed = true;
+        }
+        if ui.small_button("None").clicked() {
+            diagram.hidden = diagram.tables.iter().map(|t| t.name.clone()).collect();
+            changed = true;
+        }
+    });
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for table in &diagram.tables {
+            let mut shown = !diagram.hidden.contains(&table.name);
+            if ui.checkbox(&mut shown, &table.name).changed() {
+                if shown {
+                    diagram.hidden.remove(&table.name);
+                } else {
+                    diagram.hidden.insert(table.name.clone());
+                }
+                changed = true;
+            }
+        }
+    });
+    changed
+}
+
+fn draw_diagram(ui: &mut Ui, diagram: &mut ErDiagram) -> bool {
+    let mut moved = false;
+    let visuals = ui.visuals().clone();
+    let font = FontId::monospace(FONT_SIZE);
+    let line = Stroke::new(1.5, visuals.weak_text_color());
+
+    for edge in diagram.edges() {
+        ui.painter().add(CubicBezierShape::from_points_stroke(
+            edge.bezier(),
+            false,
+            egui::Color32::TRANSPARENT,
+            line,
+        ));
+        ui.painter().circle_filled(edge.to, 3.0, line.color);
+    }
+
+    let names: Vec<String> = diagram.visible().map(|table| table.name.clone()).collect();
+    for name in names {
+        let Some(table) = diagram.tables.iter().find(|table| table.name == name) else {
+            continue;
+        };
+        let rect = diagram.node_rect(table);
+        let response = ui.interact(rect, ui.id().with(("er_table", &name)), Sense::drag());
+
+        let painter = ui.painter();
+        let border = visuals.widgets.noninteractive.bg_stroke;
+        painter.rect_filled(rect, 4.0, visuals.window_fill);
+        let header = rect.with_max_y(rect.top() + HEADER_HEIGHT);
+        painter.rect_filled(
+            header,
+            egui::CornerRadius {
+                nw: 4,
+                ne: 4,
+                sw: 0,
+                se: 0,
+            },
+            visuals.faint_bg_color,
+        );
+        painter.rect_stroke(rect, 4.0, border, egui::StrokeKind::Inside);
+        painter.text(
+            header.left_center() + egui::vec2(PADDING, 0.0),
+            Align2::LEFT_CENTER,
+            &table.name,
+            FontId::monospace(FONT_SIZE + 1.0),
+            visuals.strong_text_color(),
+        );
+        for (idx, column) in table.columns.iter().enumerate() {
+            let y = header.bottom() + (idx as f32 + 0.5) * ROW_HEIGHT;
+            let x = rect.left() + PADDING;
+            painter.text(
+                egui::pos2(x, y),
+                Align2::LEFT_CENTER,
+                column.marker(),
+                font.clone(),
+                visuals.warn_fg_color,
+            );
+            painter.text(
+                egui::pos2(x + MARKER_WIDTH, y),
+                Align2::LEFT_CENTER,
+                &column.name,
+                font.clone(),
+                visuals.text_color(),
+            );
+            painter.text(
+                egui::pos2(rect.right() - PADDING, y),
+                Align2::RIGHT_CENTER,
+                &column.data_type,
+                font.clone(),
+                visuals.weak_text_color(),
+            );
+        }
+
+        if response.dragged() {
+            if let Some(position) = diagram.positions.get_mut(&name) {
+                *position += response.drag_delta();
+            }
+        }
+        if response.drag_stopped() {
+            moved = true;
+        }
+        response.on_hover_cursor(egui::CursorIcon::Grab);
+    }
+    moved
+}
+
+pub fn show_er_diagram_panel(ui: &mut Ui, diagram: &mut ErDiagram) -> Option<DiagramAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        if ui.button("Auto layout").clicked() {
+            diagram.auto_layout();
+            action = Some(DiagramAction::LayoutChanged);
+        }
+        if ui.button("Fit").clicked() {
+            diagram.fit();
+        }
+        if ui.button("⟳").on_hover_text("Reload").clicked() {
+            action = Some(DiagramAction::Reload);
+        }
+        ui.separator();
+        if ui.button("Export SVG…").clicked() {
+            action = Some(DiagramAction::ExportSvg);
+        }
+        if ui
+            .button("Export PNG…")
+            .on_hover_text("Saves the part of the diagram in view")
+            .clicked()
+        {
+            action = Some(DiagramAction::ExportPng);
+        }
+        if let Some(e) = &diagram.error {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        } else if !diagram.status.is_empty() {
+            ui.weak(&diagram.status);
+        }
+    });
+    ui.separator();
+
+    egui::SidePanel::left("er_diagram_tables")
+        .resizable(true)
+        .default_width(180.0)
+        .show_inside(ui, |ui| {
+            if table_list(ui, diagram) {
+                diagram.place_new_tables();
+            }
+        });
+
+    egui::Frame::canvas(ui.style()).show(ui, |ui| {
+        diagram.canvas = ui.available_rect_before_wrap();
+        let mut scene_rect = diagram.scene_rect;
+        let moved = egui::Scene::new()
+            .zoom_range(0.1..=2.0)
+            .max_inner_size([100_000.0, 100_000.0])
+            .show(ui, &mut scene_rect, |ui| draw_diagram(ui, diagram))
+            .inner;
+        diagram.scene_rect = scene_rect;
+        if moved {
+            action = Some(DiagramAction::LayoutChanged);
+        }
+    });
+
+    action
+}
@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, io, path::PathBuf};
+
+use crate::{
+    app::Rosemary,
+    library::{load_library, save_entry, LibraryEntry},
+    ui::editor_panel::insert_into_editor,
+};
+use egui::Ui;
+
+pub fn reload_library(app: &mut Rosemary) {
+    app.should_reload_library = false;
+    match load_library(&PathBuf::from(&app.library_dir)) {
+        Ok(entries) => {
+            app.library_entries = entries;
+            app.library_error.clear();
+        }
+        Err(e) => app.library_error = format!("Failed to load library: {e}"),
+    }
+}
+
+pub fn show_library_panel(ui: &mut Ui, app: &mut Rosemary) {
+    if app.should_reload_library {
+        reload_library(app);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Directory:");
+        if ui
+            .add(egui::TextEdit::singleline(&mut app.library_dir).desired_width(160.0))
+            .lost_focus()
+        {
+            app.should_reload_library = true;
+        }
+        if ui.button("⟳").on_hover_text("Reload").clicked() {
+            app.should_reload_library = true;
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        ui.add(egui::TextEdit::singleline(&mut app.library_filter));
+        if ui.button("ｘ").clicked() {
+            app.library_filter.clear();
+        }
+    });
+
+    if !app.library_error.is_empty() {
+        ui.colored_label(ui.visuals().error_fg_color, &app.library_error);
+    }
+
+    ui.collapsing("Save editor contents", |ui| {
+        let mut changed = false;
+        egui::Grid::new("library_draft")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Name:");
+                changed |= ui
+                    .text_edit_singleline(&mut app.library_draft.name)
+                    .changed();
+                ui.end_row();
+                ui.label("Folder:");
+                changed |= ui
+                    .text_edit_singleline(&mut app.library_draft.folder)
+                    .changed();
+                ui.end_row();
+                ui.label("Tags:");
+                changed |= ui
+                    .text_edit_singleline(&mut app.library_draft.tags)
+                    .on_hover_text("Comma separated")
+                    .changed();
+                ui.end_row();
+                ui.label("Description:");
+                changed |= ui
+                    .text_edit_singleline(&mut app.library_draft.description)
+                    .changed();
+                ui.end_row();
+                ui.label("Trigger:");
+                changed |= ui
+                    .text_edit_singleline(&mut app.library_draft.trigger)
+                    .on_hover_text("Type the trigger followed by * and press Tab in the editor")
+                    .changed();
+                ui.end_row();
+            });
+        // The confirmation was for the file the draft pointed at before.
+        if changed && app.library_confirm_overwrite {
+            app.library_confirm_overwrite = false;
+            app.library_error.clear();
+        }
+        let mut save = None;
+        ui.horizontal(|ui| {
+            if app.library_confirm_overwrite {
+                if ui.button("Overwrite").clicked() {
+                    save = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    app.library_confirm_overwrite = false;
+                    app.library_error.clear();
+                }
+            } else if ui.button("Save").clicked() {
+                save = Some(false);
+            }
+        });
+        if let Some(overwrite) = save {
+            let entry = app.library_draft.to_entry(&app.code);
+            app.library_confirm_overwrite = false;
+            match save_entry(&PathBuf::from(&app.library_dir), &entry, overwrite) {
+                Ok(_) => {
+                    app.library_draft = Default::default();
+                    app.library_error.clear();
+                    app.should_reload_library = true;
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    app.library_confirm_overwrite = true;
+                    app.library_error = format!("{e}, overwrite it?");
+                }
+                Err(e) => app.library_error = format!("Failed to save query: {e}"),
+            }
+        }
+    });
+
+    ui.separator();
+
+    let mut folder_map: BTreeMap<&str, Vec<&LibraryEntry>> = BTreeMap::new();
+    for entry in &app.library_entries {
+        if entry.matches(&app.library_filter) {
+            folder_map.entry(&entry.folder).or_default().push(entry);
+        }
+    }
+
+    let mut to_insert = None;
+    let mut to_replace = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("library_list")
+        .show(ui, |ui| {
+            for (folder, entries) in &folder_map {
+                let mut show_entries = |ui: &mut Ui| {
+                    for entry in entries {
+                        let mut hover = entry.description.clone();
+                        if !entry.tags.is_empty() {
+                            hover += &format!("\nTags: {}", entry.tags.join(", "));
+                        }
+                        if !entry.trigger.is_empty() {
+                            hover += &format!("\nTrigger: {}*", entry.trigger);
+                        }
+
+                        let button = egui::Button::new(&entry.name);
+                        let response = ui.add_sized([ui.available_width(), 0.0], button);
+                        let response = if hover.trim().is_empty() {
+                            response
+                        } else {
+                            response.on_hover_text(hover.trim())
+                        };
+                        if response.clicked() {
+                            to_insert = Some(entry.body.clone());
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Insert at cursor").clicked() {
+                                to_insert = Some(entry.body.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button("Replace editor contents").clicked() {
+                                to_replace = Some(entry.body.clone());
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                };
+
+                if folder.is_empty() {
+                    show_entries(ui);
+                } else {
+                    ui.push_id(folder, |ui| {
+                        ui.collapsing(format!("{} ( {} )", folder, entries.len()), |ui| {
+                            show_entries(ui)
+                        });
+                    });
+                }
+            }
+        });
+
+    if let Some(body) = to_insert {
+        insert_into_editor(ui.ctx(), &mut app.code, &body);
+    }
+    if let Some(body) = to_replace {
+        app.code = body;
+    }
+}
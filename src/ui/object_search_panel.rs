@@ -0,0 +1,122 @@
+use crate::object_search::ObjectSearch;
+use egui::{text::LayoutJob, Key, Modifiers, TextFormat, Ui};
+
+pub enum ObjectSearchAction {
+    /// Shows the selected object in the table tree.
+    Reveal,
+    /// Inserts the selected object's qualified name into the editor.
+    Insert,
+    Close,
+}
+
+/// The name with its matched characters highlighted.
+fn highlighted_name(ui: &Ui, name: &str, positions: &[usize]) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let normal = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let matched = TextFormat::simple(font_id, ui.visuals().warn_fg_color);
+    let mut job = LayoutJob::default();
+    for (idx, c) in name.chars().enumerate() {
+        let format = if positions.contains(&idx) {
+            matched.clone()
+        } else {
+            normal.clone()
+        };
+        job.append(&c.to_string(), 0.0, format);
+    }
+    job
+}
+
+pub fn show_object_search_panel(
+    ui: &mut Ui,
+    search: &mut ObjectSearch,
+    connections: &[(String, String)],
+) -> Option<ObjectSearchAction> {
+    let mut action = None;
+    let mut moved = false;
+
+    // Handled before the text field sees them.
+    ui.input_mut(|i| {
+        if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+            search.selected = (search.selected + 1).min(search.results.len().saturating_sub(1));
+            moved = true;
+        }
+        if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+            search.selected = search.selected.saturating_sub(1);
+            moved = true;
+        }
+        if i.consume_key(Modifiers::COMMAND, Key::Enter) {
+            action = Some(ObjectSearchAction::Insert);
+        } else if i.consume_key(Modifiers::NONE, Key::Enter) {
+            action = Some(ObjectSearchAction::Reveal);
+        }
+        if i.consume_key(Modifiers::NONE, Key::Escape) {
+            action = Some(ObjectSearchAction::Close);
+        }
+    });
+
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut search.query)
+            .hint_text("Tables, views, columns, functions, indexes, sequences and types")
+            .desired_width(f32::INFINITY),
+    );
+    response.request_focus();
+    if response.changed() {
+        search.update_results();
+    }
+    if search.loading > 0 {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.weak("Loading objects…");
+        });
+    }
+    for e in &search.errors {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+
+    egui::ScrollArea::vertical()
+        .max_height(360.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for (row, (idx, positions)) in search.results.iter().enumerate() {
+                let (key, object) = &search.objects[*idx];
+                let selected = row == search.selected;
+                let response = ui
+                    .horizontal(|ui| {
+                        let name = highlighted_name(ui, &object.name, positions);
+                        let response = ui.selectable_label(selected, name);
+                        ui.weak(&object.kind);
+                        let location = match &object.table {
+                            Some(table) => format!("{}.{table}", object.schema),
+                            None => object.schema.clone(),
+                        };
+                        ui.weak(location);
+                        if let Some(detail) = &object.detail {
+                            ui.weak(detail);
+                        }
+                        if connections.len() > 1 {
+                            if let Some((_, label)) =
+                                connections.iter().find(|(live_key, _)| live_key == key)
+                            {
+                                ui.weak(format!("· {label}"));
+                            }
+                        }
+                        response
+                    })
+                    .inner;
+                if selected && moved {
+                    response.scroll_to_me(None);
+                }
+                if response.clicked() {
+                    search.selected = row;
+                    action = Some(ObjectSearchAction::Reveal);
+                }
+            }
+            if search.results.is_empty() && !search.query.trim().is_empty() {
+                ui.weak("Nothing found");
+            }
+        });
+    ui.separator();
+    ui.weak("Enter shows it in the tree, Ctrl+Enter inserts its name into the editor");
+
+    action
+}
@@ -0,0 +1,44 @@
+use crate::{app::ParameterPrompt, query_functions::pg_params::ParamType};
+use egui::{Layout, Ui};
+
+/// Returns `Some(true)` to execute, `Some(false)` to cancel.
+pub fn show_query_params_panel(ui: &mut Ui, prompt: &mut ParameterPrompt) -> Option<bool> {
+    let mut resolution = None;
+
+    egui::Grid::new("query_params_grid")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            for (idx, param) in prompt.params.iter_mut().enumerate() {
+                ui.strong(&param.name);
+                egui::ComboBox::from_id_salt(("param_type", idx))
+                    .selected_text(param.param_type.label())
+                    .show_ui(ui, |ui| {
+                        for param_type in ParamType::ALL {
+                            ui.selectable_value(
+                                &mut param.param_type,
+                                param_type,
+                                param_type.label(),
+                            );
+                        }
+                    });
+                ui.add_enabled(!param.is_null, egui::TextEdit::singleline(&mut param.value));
+                ui.checkbox(&mut param.is_null, "NULL");
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        if ui.button("Execute").clicked()
+            || ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.is_none())
+        {
+            resolution = Some(true);
+        }
+        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            resolution = Some(false);
+        }
+    });
+
+    resolution
+}
@@ -0,0 +1,262 @@
+use crate::schema_diff::{DiffEntry, DiffKind, DiffStatus, SchemaCompare};
+use egui::{Color32, Ui};
+
+pub enum SchemaCompareAction {
+    Compare,
+    OpenInEditor(String),
+}
+
+fn status_colour(ui: &Ui, status: DiffStatus) -> Color32 {
+    match status {
+        DiffStatus::Missing => Color32::from_rgb(110, 190, 110),
+        DiffStatus::Extra => ui.visuals().error_fg_color,
+        DiffStatus::Changed => ui.visuals().warn_fg_color,
+        DiffStatus::Same => ui.visuals().weak_text_color(),
+    }
+}
+
+/// A picker for one side of a compare, listing the open connections by label.
+pub fn connection_picker(
+    ui: &mut Ui,
+    id: &str,
+    key: &mut String,
+    connections: &[(String, String)],
+) {
+    let selected = connections
+        .iter()
+        .find(|(live_key, _)| live_key == key)
+        .map_or("Pick a connection", |(_, label)| label.as_str());
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (live_key, label) in connections {
+                ui.selectable_value(key, live_key.clone(), label);
+            }
+        });
+}
+
+fn entry_row(ui: &mut Ui, compare: &mut SchemaCompare, idx: usize) {
+    let entry = &mut compare.entries[idx];
+    let status = entry.status();
+    ui.horizontal(|ui| {
+        ui.add_enabled(
+            status != DiffStatus::Same,
+            egui::Checkbox::without_text(&mut entry.included),
+        )
+        .on_hover_text("Include in the migration script");
+        let text = egui::RichText::new(format!("{} {}", status.symbol(), entry.label()))
+            .color(status_colour(ui, status));
+        if ui
+            .selectable_label(compare.selected == Some(idx), text)
+            .clicked()
+        {
+            compare.selected = Some(idx);
+        }
+    });
+}
+
+fn is_shown(compare: &SchemaCompare, entry: &DiffEntry) -> bool {
+    compare.show_identical || entry.status() != DiffStatus::Same
+}
+
+fn show_tree(ui: &mut Ui, compare: &mut SchemaCompare) {
+    let kinds = [
+        DiffKind::Schema,
+        DiffKind::Type,
+        DiffKind::Sequence,
+        DiffKind::Function,
+        DiffKind::Table,
+        DiffKind::View,
+        DiffKind::Grant,
+    ];
+    for kind in kinds {
+        let rows: Vec<usize> = (0..compare.entries.len())
+            .filter(|&idx| {
+                let entry = &compare.entries[idx];
+                if entry.kind != kind {
+                    return false;
+                }
+                // Tables are listed when one of their columns, constraints or
+                // indexes differs.
+                is_shown(compare, entry)
+                    || (kind == DiffKind::Table
+                        && compare.entries.iter().any(|part| {
+                            part.kind.is_table_part()
+                                && part.schema == entry.schema
+                                && part.table == entry.name
+                                && part.status() != DiffStatus::Same
+                        }))
+            })
+            .collect();
+        let differences = compare
+            .entries
+            .iter()
+            .filter(|entry| entry.kind == kind && entry.status() != DiffStatus::Same)
+            .count();
+        egui::CollapsingHeader::new(format!("{} ( {differences} )", kind.label()))
+            .id_salt(kind.label())
+            .default_open(differences > 0)
+            .show(ui, |ui| {
+                for idx in rows {
+                    if kind != DiffKind::Table {
+                        entry_row(ui, compare, idx);
+                        continue;
+                    }
+                    let (schema, table) = (
+                        compare.entries[idx].schema.clone(),
+                        compare.entries[idx].name.clone(),
+                    );
+                    let parts: Vec<usize> = (0..compare.entries.len())
+                        .filter(|&part| {
+                            let entry = &compare.entries[part];
+                            entry.kind.is_table_part()
+                                && entry.schema == schema
+                                && entry.table == table
+                                && is_shown(compare, entry)
+                        })
+                        .collect();
+                    let id = ui.make_persistent_id(("compare_table", &schema, &table));
+                    egui::collapsing_header::CollapsingState::load_with_default_open(
+                        ui.ctx(),
+                        id,
+                        false,
+                    )
+                    .show_header(ui, |ui| entry_row(ui, compare, idx))
+                    .body(|ui| {
+                        for part in parts {
+                            ui.horizontal(|ui| {
+                                ui.weak(compare.entries[part].kind.label());
+                                entry_row(ui, compare, part);
+                            });
+                        }
+                    });
+                }
+            });
+    }
+}
+
+fn definition_column(ui: &mut Ui, title: &str, definition: Option<&str>) {
+    ui.strong(title);
+    match definition {
+        Some(definition) => {
+            ui.add(
+                egui::TextEdit::multiline(&mut &*definition)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        }
+        None => {
+            ui.weak("Doesn't exist");
+        }
+    }
+}
+
+pub fn show_schema_compare_panel(
+    ui: &mut Ui,
+    compare: &mut SchemaCompare,
+    connections: &[(String, String)],
+) -> Option<SchemaCompareAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        ui.label("Source:");
+        connection_picker(ui, "compare_source", &mut compare.source_key, connections);
+        if ui.button("⇄").on_hover_text("Swap").clicked() {
+            std::mem::swap(&mut compare.source_key, &mut compare.target_key);
+        }
+        ui.label("Target:");
+        connection_picker(ui, "compare_target", &mut compare.target_key, connections);
+        let ready = !compare.loading
+            && !compare.source_key.is_empty()
+            && !compare.target_key.is_empty()
+            && compare.source_key != compare.target_key;
+        if ui
+            .add_enabled(ready, egui::Button::new("Compare"))
+            .clicked()
+        {
+            action = Some(SchemaCompareAction::Compare);
+        }
+        if compare.loading {
+            ui.spinner();
+        }
+        ui.checkbox(&mut compare.show_identical, "Show identical");
+    });
+    if let Some(e) = &compare.error {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+    if compare.snapshots.is_none() {
+        ui.weak("Pick two connections and compare them. The script changes the target to match the source.");
+        return action;
+    }
+    ui.separator();
+
+    let differences = compare
+        .entries
+        .iter()
+        .filter(|entry| entry.status() != DiffStatus::Same)
+        .count();
+    if differences == 0 {
+        ui.label("The schemas are the same.");
+    }
+
+    egui::TopBottomPanel::bottom("schema_compare_script")
+        .resizable(true)
+        .default_height(160.0)
+        .show_inside(ui, |ui| {
+            let mut script = compare.script_text();
+            ui.horizontal(|ui| {
+                ui.strong("Migration script");
+                let enabled = !script.is_empty();
+                if ui
+                    .add_enabled(enabled, egui::Button::new("Open in editor"))
+                    .clicked()
+                {
+                    action = Some(SchemaCompareAction::OpenInEditor(script.to_string()));
+                }
+                if ui.add_enabled(enabled, egui::Button::new("Copy")).clicked() {
+                    ui.ctx().copy_text(script.to_string());
+                }
+            });
+            egui::ScrollArea::both()
+                .id_salt("schema_compare_script_scroll")
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut script)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        });
+
+    egui::SidePanel::left("schema_compare_tree")
+        .resizable(true)
+        .default_width(320.0)
+        .show_inside(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("schema_compare_tree_scroll")
+                .show(ui, |ui| show_tree(ui, compare));
+        });
+
+    egui::ScrollArea::vertical()
+        .id_salt("schema_compare_definitions")
+        .show(ui, |ui| {
+            let Some(entry) = compare.selected.and_then(|idx| compare.entries.get(idx)) else {
+                ui.weak("Select an object to see both definitions.");
+                return;
+            };
+            ui.columns(2, |columns| {
+                definition_column(
+                    &mut columns[0],
+                    "Source",
+                    entry.source.as_ref().map(|o| o.definition.as_str()),
+                );
+                definition_column(
+                    &mut columns[1],
+                    "Target",
+                    entry.target.as_ref().map(|o| o.definition.as_str()),
+                );
+            });
+        });
+
+    action
+}
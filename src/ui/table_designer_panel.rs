@@ -0,0 +1,370 @@
+use crate::table_designer::{referential_action, Named, TableDesigner, REFERENTIAL_ACTIONS};
+use egui::Ui;
+
+pub enum DesignerAction {
+    Apply,
+    OpenInEditor(String),
+}
+
+/// A type field with a menu of the types in the database, filtered by what
+/// has been typed so far.
+fn type_picker(ui: &mut Ui, data_type: &mut String, types: &[String]) {
+    ui.add(egui::TextEdit::singleline(data_type).desired_width(140.0));
+    ui.menu_button("▾", |ui| {
+        let filter = data_type.trim().to_lowercase();
+        let show_all = types.contains(&filter);
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                for name in types
+                    .iter()
+                    .filter(|name| show_all || name.to_lowercase().contains(&filter))
+                {
+                    if ui.button(name).clicked() {
+                        *data_type = name.clone();
+                        ui.close_menu();
+                    }
+                }
+            });
+    });
+}
+
+fn action_picker(ui: &mut Ui, id: impl std::hash::Hash, code: &mut String) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(referential_action(code))
+        .show_ui(ui, |ui| {
+            for (action_code, label) in REFERENTIAL_ACTIONS {
+                ui.selectable_value(code, action_code.to_string(), label);
+            }
+        });
+}
+
+fn name_edit<T>(ui: &mut Ui, item: &mut Named<T>, hint: &str) {
+    ui.add(
+        egui::TextEdit::singleline(&mut item.name)
+            .hint_text(hint)
+            .desired_width(120.0),
+    );
+}
+
+/// Shows a list of constraints or indexes with an "Add" button, and removes
+/// the ones whose ✕ was clicked.
+fn named_section<T: Default>(
+    ui: &mut Ui,
+    title: &str,
+    headers: &[&str],
+    items: &mut Vec<Named<T>>,
+    mut row: impl FnMut(&mut Ui, usize, &mut Named<T>),
+) {
+    egui::CollapsingHeader::new(format!("{} ( {} )", title, items.len()))
+        .id_salt(title)
+        .default_open(!items.is_empty())
+        .show(ui, |ui| {
+            let mut remove = None;
+            if !items.is_empty() {
+                egui::Grid::new(title).striped(true).show(ui, |ui| {
+                    for header in headers {
+                        ui.strong(*header);
+                    }
+                    ui.end_row();
+                    for (idx, item) in items.iter_mut().enumerate() {
+                        row(ui, idx, item);
+                        if ui.small_button("ｘ").clicked() {
+                            remove = Some(idx);
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+            if let Some(idx) = remove {
+                items.remove(idx);
+            }
+            if ui.button("Add").clicked() {
+                items.push(Named::default());
+            }
+        });
+}
+
+pub fn show_table_designer_panel(
+    ui: &mut Ui,
+    designer: &mut TableDesigner,
+    types: &[String],
+    tables: &[String],
+) -> Option<DesignerAction> {
+    let mut action = None;
+    let creating = designer.original.is_none();
+    let design = &mut designer.design;
+
+    egui::Grid::new("designer_table")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Schema:");
+            ui.add_enabled(creating, egui::TextEdit::singleline(&mut design.schema));
+            ui.end_row();
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut design.name);
+            ui.end_row();
+        });
+
+    egui::ScrollArea::vertical()
+        .id_salt("designer_scroll")
+        .max_height(ui.available_height() - 220.0)
+        .show(ui, |ui| {
+            egui::CollapsingHeader::new(format!("Columns ( {} )", design.columns.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    let mut remove = None;
+                    egui::Grid::new("designer_columns")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for header in ["Name", "Type", "Default", "Not null", "PK", "Identity"]
+                            {
+                                ui.strong(header);
+                            }
+                            ui.end_row();
+                            for (idx, column) in design.columns.iter_mut().enumerate() {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut column.name)
+                                        .desired_width(120.0),
+                                );
+                                if let Some(expression) = &column.generated {
+                                    ui.label(&column.data_type);
+                                    ui.weak(format!("generated: {expression}"));
+                                } else {
+                                    ui.horizontal(|ui| {
+                                        type_picker(ui, &mut column.data_type, types)
+                                    });
+                                    ui.add_enabled(
+                                        column.identity.is_empty(),
+                                        egui::TextEdit::singleline(&mut column.default_value)
+                                            .desired_width(120.0),
+                                    );
+                                }
+                                ui.checkbox(&mut column.not_null, "");
+                                if ui.checkbox(&mut column.primary_key, "").changed()
+                                    && column.primary_key
+                                {
+                                    column.not_null = true;
+                                }
+                                ui.add_enabled_ui(column.generated.is_none(), |ui| {
+                                    let label = match column.identity.as_str() {
+                                        "a" => "always",
+                                        "d" => "by default",
+                                        _ => "none",
+                                    };
+                                    egui::ComboBox::from_id_salt(("identity", idx))
+                                        .selected_text(label)
+                                        .show_ui(ui, |ui| {
+                                            for (code, label) in
+                                                [("", "none"), ("a", "always"), ("d", "by default")]
+                                            {
+                                                ui.selectable_value(
+                                                    &mut column.identity,
+                                                    code.to_string(),
+                                                    label,
+                                                );
+                                            }
+                                        });
+                                });
+                                if ui.small_button("ｘ").clicked() {
+                                    remove = Some(idx);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    if let Some(idx) = remove {
+                        design.columns.remove(idx);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Add column").clicked() {
+                            design.add_column();
+                        }
+                        ui.label("Primary key name:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut design.primary_key_name)
+                                .hint_text("generated")
+                                .desired_width(160.0),
+                        );
+                    });
+                });
+
+            named_section(
+                ui,
+                "Unique constraints",
+                &["Name", "Columns"],
+                &mut design.uniques,
+                |ui, _, unique| {
+                    name_edit(ui, unique, "generated");
+                    ui.add(egui::TextEdit::singleline(&mut unique.spec.columns).hint_text("a, b"));
+                },
+            );
+            named_section(
+                ui,
+                "Check constraints",
+                &["Name", "Expression"],
+                &mut design.checks,
+                |ui, _, check| {
+                    name_edit(ui, check, "generated");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut check.spec.expression)
+                            .hint_text("price > 0")
+                            .desired_width(240.0),
+                    );
+                },
+            );
+            named_section(
+                ui,
+                "Foreign keys",
+                &[
+                    "Name",
+                    "Columns",
+                    "References",
+                    "Referenced columns",
+                    "On update",
+                    "On delete",
+                ],
+                &mut design.foreign_keys,
+                |ui, idx, foreign_key| {
+                    name_edit(ui, foreign_key, "generated");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut foreign_key.spec.columns)
+                            .hint_text("customer_id")
+                            .desired_width(120.0),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut foreign_key.spec.references)
+                                .hint_text("public.customers")
+                                .desired_width(140.0),
+                        );
+                        ui.menu_button("▾", |ui| {
+                            egui::ScrollArea::vertical()
+                                .max_height(240.0)
+                                .show(ui, |ui| {
+                                    for table in tables {
+                                        if ui.button(table).clicked() {
+                                            foreign_key.spec.references = table.clone();
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                        });
+                    });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut foreign_key.spec.foreign_columns)
+                            .hint_text("primary key")
+                            .desired_width(120.0),
+                    );
+                    action_picker(ui, ("on_update", idx), &mut foreign_key.spec.on_update);
+                    action_picker(ui, ("on_delete", idx), &mut foreign_key.spec.on_delete);
+                },
+            );
+            named_section(
+                ui,
+                "Indexes",
+                &["Name", "Unique", "Method", "Keys", "Where"],
+                &mut design.indexes,
+                |ui, _, index| {
+                    name_edit(ui, index, "generated");
+                    ui.checkbox(&mut index.spec.unique, "");
+                    ui.add(egui::TextEdit::singleline(&mut index.spec.method).desired_width(60.0));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut index.spec.keys)
+                            .hint_text("lower(email), created_at DESC")
+                            .desired_width(200.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut index.spec.predicate)
+                            .hint_text("optional")
+                            .desired_width(120.0),
+                    );
+                },
+            );
+        });
+
+    ui.separator();
+    let script = designer.script();
+    match &script {
+        Ok(statements) if statements.is_empty() => {
+            ui.weak("No changes.");
+        }
+        Ok(statements) => {
+            let text = statements.join("\n");
+            egui::ScrollArea::both()
+                .id_salt("designer_script")
+                .max_height(140.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut text.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        }
+        Err(e) => {
+            ui.colored_label(ui.visuals().warn_fg_color, e);
+        }
+    }
+    if let Some(original) = &designer.original {
+        let dropped = designer.design.dropped_columns(original);
+        if !dropped.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ Dropping {} deletes its data.", dropped.join(", ")),
+            );
+        }
+    }
+    if let Some(reason) = &designer.read_only {
+        ui.colored_label(ui.visuals().warn_fg_color, reason);
+    }
+    if let Some(e) = &designer.error {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    } else if !designer.status.is_empty() {
+        ui.weak(&designer.status);
+    }
+
+    let statements = script.unwrap_or_default();
+    if designer.confirm_apply {
+        if let Some(name) = &designer.production {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ This changes the schema of the production connection \"{name}\"."),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Apply anyway").clicked() {
+                action = Some(DesignerAction::Apply);
+            }
+            if ui.button("Cancel").clicked() {
+                designer.confirm_apply = false;
+            }
+        });
+    } else {
+        ui.horizontal(|ui| {
+            let can_apply =
+                !statements.is_empty() && designer.read_only.is_none() && !designer.applying;
+            if ui
+                .add_enabled(can_apply, egui::Button::new("Apply"))
+                .on_hover_text("Runs the script in one transaction")
+                .clicked()
+            {
+                if designer.production.is_some() {
+                    designer.confirm_apply = true;
+                } else {
+                    action = Some(DesignerAction::Apply);
+                }
+            }
+            if ui
+                .add_enabled(!statements.is_empty(), egui::Button::new("Open in editor"))
+                .clicked()
+            {
+                action = Some(DesignerAction::OpenInEditor(statements.join("\n")));
+            }
+            if designer.applying {
+                ui.spinner();
+            }
+        });
+    }
+
+    action
+}
@@ -0,0 +1,222 @@
+use crate::{
+    query_functions::pg_data::{IndexStats, TableStats},
+    sql::qualified_name,
+    table_stats::{approximate_count, dead_tuple_percent, time_ago, TableInfo},
+};
+use chrono::{DateTime, Local, Utc};
+use egui::Ui;
+use egui_extras::{Column as eguiColumn, TableBuilder};
+
+pub enum TableInfoAction {
+    Reload,
+}
+
+fn count(value: Option<i64>) -> String {
+    value.map_or(String::from("–"), |value| value.to_string())
+}
+
+fn timestamp(ui: &mut Ui, value: Option<DateTime<Utc>>, now: DateTime<Utc>) {
+    match value {
+        Some(value) => {
+            ui.label(time_ago(value, now)).on_hover_text(
+                value
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S %Z")
+                    .to_string(),
+            );
+        }
+        None => {
+            ui.weak("never");
+        }
+    }
+}
+
+fn show_stats(ui: &mut Ui, stats: &TableStats) {
+    let now = Utc::now();
+    ui.strong("Size");
+    egui::Grid::new("table_info_sizes")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (label, value) in [
+                ("Total", &stats.total_size),
+                ("Table", &stats.table_size),
+                ("Indexes", &stats.index_size),
+                ("TOAST", &stats.toast_size),
+            ] {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(8.0);
+    ui.strong("Rows");
+    egui::Grid::new("table_info_rows")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Estimated");
+            match stats.estimated_rows {
+                Some(rows) => ui
+                    .label(approximate_count(rows))
+                    .on_hover_text(format!("{rows} rows, from pg_class.reltuples")),
+                None => ui
+                    .weak("unknown")
+                    .on_hover_text("The table hasn't been vacuumed or analyzed yet"),
+            };
+            ui.end_row();
+            ui.label("Live tuples");
+            ui.label(count(stats.live_tuples));
+            ui.end_row();
+            ui.label("Dead tuples");
+            let dead = count(stats.dead_tuples);
+            match stats
+                .live_tuples
+                .zip(stats.dead_tuples)
+                .and_then(|(live, dead)| dead_tuple_percent(live, dead))
+            {
+                Some(percent) => ui.label(format!("{dead} ({percent:.1}%)")),
+                None => ui.label(dead),
+            };
+            ui.end_row();
+            ui.label("Changed since analyze");
+            ui.label(count(stats.modified_since_analyze));
+            ui.end_row();
+        });
+
+    ui.add_space(8.0);
+    ui.strong("Maintenance");
+    egui::Grid::new("table_info_maintenance")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (label, value) in [
+                ("Last vacuum", stats.last_vacuum),
+                ("Last autovacuum", stats.last_autovacuum),
+                ("Last analyze", stats.last_analyze),
+                ("Last autoanalyze", stats.last_autoanalyze),
+            ] {
+                ui.label(label);
+                timestamp(ui, value, now);
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(8.0);
+    ui.strong("Scans");
+    egui::Grid::new("table_info_scans")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Sequential scans");
+            ui.label(count(stats.seq_scans));
+            ui.end_row();
+            ui.label("Rows read by them");
+            ui.label(count(stats.seq_tuples_read));
+            ui.end_row();
+            ui.label("Index scans");
+            ui.label(count(stats.index_scans));
+            ui.end_row();
+            ui.label("Rows fetched by them");
+            ui.label(count(stats.index_tuples_fetched));
+            ui.end_row();
+        });
+}
+
+fn show_indexes(ui: &mut Ui, indexes: &[IndexStats]) {
+    ui.strong("Indexes");
+    if indexes.is_empty() {
+        ui.weak("None");
+        return;
+    }
+    let text_height = 20.0;
+    TableBuilder::new(ui)
+        .id_salt("table_info_indexes")
+        .striped(true)
+        .resizable(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(eguiColumn::initial(140.0).at_least(60.0).clip(true))
+        .columns(eguiColumn::auto(), 4)
+        .header(text_height, |mut header| {
+            for title in ["Name", "Size", "Scans", "Read", "Fetched"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(text_height, indexes.len(), |mut row| {
+                let index = &indexes[row.index()];
+                row.col(|ui| {
+                    let name = if index.is_primary {
+                        format!("🔑 {}", index.name)
+                    } else {
+                        index.name.clone()
+                    };
+                    let response = ui.label(name);
+                    if index.is_unique && !index.is_primary {
+                        response.on_hover_text("Unique");
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(&index.size);
+                });
+                row.col(|ui| {
+                    if index.scans == Some(0) && !index.is_unique {
+                        ui.colored_label(ui.visuals().warn_fg_color, "0")
+                            .on_hover_text("Never used since the statistics were reset");
+                    } else {
+                        ui.label(count(index.scans));
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(count(index.tuples_read));
+                });
+                row.col(|ui| {
+                    ui.label(count(index.tuples_fetched));
+                });
+            });
+        });
+}
+
+pub fn show_table_info_panel(
+    ui: &mut Ui,
+    info: Option<&TableInfo>,
+    connection: Option<&str>,
+) -> Option<TableInfoAction> {
+    let mut action = None;
+    let Some(info) = info else {
+        ui.weak("Click a table in the table list to see its details.");
+        return action;
+    };
+
+    ui.horizontal(|ui| {
+        ui.heading(qualified_name(&info.schema, &info.table));
+        if info.loading {
+            ui.spinner();
+        } else if ui.button("⟳").on_hover_text("Reload").clicked() {
+            action = Some(TableInfoAction::Reload);
+        }
+    });
+    if let Some(connection) = connection {
+        ui.weak(connection);
+    }
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .id_salt("table_info_scroll")
+        .show(ui, |ui| match &info.stats {
+            Some(Ok((stats, indexes))) => {
+                show_stats(ui, stats);
+                ui.add_space(8.0);
+                show_indexes(ui, indexes);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {}
+        });
+
+    action
+}
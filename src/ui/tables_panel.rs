@@ -1,10 +1,18 @@
 use std::collections::BTreeMap;
 
 use crate::{
-    app::Rosemary,
-    query_functions::{pg_data::PublicTable, pg_query_handlers::format_sql},
+    app::{LiveConnection, ObjectRef, Rosemary},
+    object_search::TreeReveal,
+    query_functions::{
+        pg_data::{PublicTable, TableEstimate},
+        pg_query_handlers::format_sql,
+        pg_schema::{SchemaObject, TableColumn, TableDetails},
+    },
+    sql::qualified_name,
+    table_sql::TableTemplate,
+    table_stats::approximate_count,
 };
-use egui::Ui;
+use egui::{collapsing_header::CollapsingState, Ui};
 
 //TODO: Improve this arg logic
 pub fn show_tables_panel(
@@ -21,73 +29,968 @@ pub fn show_tables_panel(
         if ui.button("ｘ").clicked() {
             app.table_filter.clear();
         }
+        ui.checkbox(&mut app.show_table_estimates, "Sizes")
+            .on_hover_text("Estimated row counts and sizes, from the planner's statistics");
     });
-    let mut schema_table_map = BTreeMap::new();
-    for table in &app.tables {
-        match &table.table_schema {
-            Some(schema) => {
-                schema_table_map
-                    .entry(schema)
-                    .or_insert(Vec::new())
-                    .push(table);
-            }
-            None => {}
-        }
-    }
+    let mut action = None;
 
     egui::ScrollArea::vertical()
         .id_salt("public_table_list")
         .show(ui, |ui| {
-            for (schema, tables) in &schema_table_map {
-                ui.push_id(schema, |ui| {
-                    let filtered_tables: Vec<&&PublicTable> = if app.table_filter.trim().is_empty()
+            for live in &app.live_connections {
+                let active = live.key == app.active_connection_key;
+                let title = if active {
+                    egui::RichText::new(format!("▶ {}", live.label())).strong()
+                } else {
+                    egui::RichText::new(live.label())
+                };
+                let revealing = app
+                    .tree_reveal
+                    .as_ref()
+                    .is_some_and(|reveal| reveal.key == live.key);
+                let header = egui::CollapsingHeader::new(title)
+                    .id_salt(&live.key)
+                    .default_open(true)
+                    .open(revealing.then_some(true))
+                    .show(ui, |ui| {
+                        if let Some(table_action) = show_connection_tables(ui, app, live) {
+                            action = Some(table_action);
+                        }
+                    });
+                header.header_response.context_menu(|ui| {
+                    if ui
+                        .add_enabled(!active, egui::Button::new("Use in editor"))
+                        .clicked()
                     {
-                        tables.iter().collect()
-                    } else {
-                        tables
-                            .iter()
-                            .filter(|table| {
-                                table
-                                    .table_name
-                                    .as_deref()
-                                    .unwrap_or("NULL")
-                                    .to_lowercase()
-                                    .contains(&app.table_filter)
-                            })
-                            .collect()
-                    };
-                    ui.collapsing(format!("{} ( {} )", schema, filtered_tables.len()), |ui| {
-                        for table in filtered_tables {
-                            let table_name = table.table_name.as_deref().unwrap_or("NULL");
-                            let table_schema = table.table_schema.as_deref().unwrap_or("NULL");
-
-                            let table_type = table.table_type.as_deref().unwrap_or("NULL");
-                            let button_label = format!("{table_name} [{table_type}]");
-                            let button = egui::Button::new(button_label);
-
-                            if ui.add_sized([ui.available_width(), 0.0], button).clicked() {
-                                if app.table_queries_are_additive {
-                                    let code = app.code.clone()
-                                        + &format!("SELECT * FROM {table_schema}.{table_name};");
-                                    app.code = format_sql(&code);
-                                } else {
-                                    app.code = format_sql(&format!(
-                                        "SELECT * FROM {table_schema}.{table_name};"
-                                    ));
+                        action = Some(TreeAction::Activate(live.key.clone()));
+                        ui.close_menu();
+                    }
+                    if ui.button("Databases…").clicked() {
+                        action = Some(TreeAction::Databases(live.key.clone()));
+                        ui.close_menu();
+                    }
+                    if ui.button("Refresh").clicked() {
+                        action = Some(TreeAction::Refresh(live.key.clone()));
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Disconnect").clicked() {
+                        action = Some(TreeAction::Disconnect(live.key.clone()));
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
+
+    // Give up on revealing an object the tree doesn't list, once what it is
+    // listed in has loaded.
+    if let Some(reveal) = &app.tree_reveal {
+        let missing = app
+            .live_connections
+            .iter()
+            .find(|live| live.key == reveal.key)
+            .is_none_or(|live| {
+                reveal_state(live, reveal, &app.table_filter) == RevealState::Missing
+            });
+        if missing {
+            app.tree_reveal = None;
+        }
+    }
+
+    match action {
+        Some(TreeAction::Activate(key)) => app.set_active_connection(&key),
+        Some(TreeAction::Revealed) => app.tree_reveal = None,
+        Some(TreeAction::Databases(key)) => {
+            app.set_active_connection(&key);
+            // The switch waits on the open transaction being ended, so don't
+            // list the databases of the connection that is still active.
+            if app.active_connection_key == key {
+                app.open_databases();
+            }
+        }
+        Some(TreeAction::Refresh(key)) => app.refresh_tables(&key),
+        Some(TreeAction::LoadDetails { key, schema, table }) => {
+            app.load_table_details(ui.ctx(), &key, &schema, &table)
+        }
+        Some(TreeAction::LoadObjects { key, schema }) => {
+            app.load_schema_objects(ui.ctx(), &key, &schema)
+        }
+        Some(TreeAction::LoadExtensions(key)) => app.load_extensions(ui.ctx(), &key),
+        Some(TreeAction::Definition { key, kind, object }) => {
+            app.show_definition(ui.ctx(), &key, &kind, object)
+        }
+        Some(TreeAction::CopyDefinition { key, kind, object }) => {
+            app.copy_definition(ui.ctx(), &key, &kind, object)
+        }
+        Some(TreeAction::Run { key, sql }) => {
+            app.set_active_connection(&key);
+            if app.active_connection_key == key {
+                app.submit_query(ui.ctx(), sql, 0);
+            }
+        }
+        Some(TreeAction::Disconnect(key)) => app.request_disconnect(&key),
+        Some(TreeAction::Query { key, schema, table }) => {
+            app.select_table(ui.ctx(), &key, &schema, &table);
+            let sql = format!("SELECT * FROM {};", qualified_name(&schema, &table));
+            if app.table_queries_are_additive {
+                app.code = format_sql(&(app.code.clone() + &sql));
+            } else {
+                app.code = format_sql(&sql);
+            }
+
+            // Queries from a table run on that table's connection. If switching
+            // has to wait for an open transaction, only the query is added.
+            app.set_active_connection(&key);
+            if app.active_connection_key == key {
+                if shift_pressed {
+                    if !app.split_results_table {
+                        app.split_results_table = true;
+                    }
+                    *should_execute_secondary = true;
+                } else {
+                    *should_execute = true;
+                }
+            }
+        }
+        Some(TreeAction::Template {
+            key,
+            schema,
+            table,
+            kind,
+            template,
+        }) => app.open_table_template(ui.ctx(), &key, &schema, &table, &kind, template),
+        Some(TreeAction::OpenDataEditor { key, schema, table }) => {
+            app.open_data_editor(ui.ctx(), &key, &schema, &table)
+        }
+        Some(TreeAction::TableSize { key, schema, table }) => {
+            app.load_table_size(ui.ctx(), &key, &schema, &table)
+        }
+        Some(TreeAction::TableInfo { key, schema, table }) => {
+            app.show_table_info = true;
+            app.select_table(ui.ctx(), &key, &schema, &table);
+        }
+        Some(TreeAction::LoadEstimates(key)) => app.load_table_estimates(ui.ctx(), &key),
+        Some(TreeAction::DesignTable { key, schema, table }) => {
+            app.open_table_designer(ui.ctx(), &key, &schema, table.as_deref())
+        }
+        Some(TreeAction::ErDiagram { key, schema, table }) => {
+            app.open_er_diagram(ui.ctx(), &key, &schema, table.as_deref())
+        }
+        Some(TreeAction::CompareData { key, schema, table }) => {
+            app.open_data_compare(&key, Some((schema, table)))
+        }
+        None => {}
+    }
+}
+
+enum TreeAction {
+    Activate(String),
+    Databases(String),
+    Refresh(String),
+    Disconnect(String),
+    Query {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    LoadDetails {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    LoadObjects {
+        key: String,
+        schema: String,
+    },
+    LoadExtensions(String),
+    Definition {
+        key: String,
+        kind: String,
+        object: ObjectRef,
+    },
+    CopyDefinition {
+        key: String,
+        kind: String,
+        object: ObjectRef,
+    },
+    /// Runs a statement on the connection, e.g. to refresh a materialized view.
+    Run {
+        key: String,
+        sql: String,
+    },
+    Template {
+        key: String,
+        schema: String,
+        table: String,
+        kind: String,
+        template: TableTemplate,
+    },
+    OpenDataEditor {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    TableSize {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    /// Shows the table in the details panel.
+    TableInfo {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    LoadEstimates(String),
+    /// Opens the table designer, on a new table when `table` is `None`.
+    DesignTable {
+        key: String,
+        schema: String,
+        table: Option<String>,
+    },
+    /// Opens the ER diagram of a schema, showing only `table` and the tables
+    /// related to it when given.
+    ErDiagram {
+        key: String,
+        schema: String,
+        table: Option<String>,
+    },
+    CompareData {
+        key: String,
+        schema: String,
+        table: String,
+    },
+    /// The object picked in the object search is in view.
+    Revealed,
+}
+
+const READ_TEMPLATES: [TableTemplate; 3] = [
+    TableTemplate::SelectColumns,
+    TableTemplate::SelectLimit,
+    TableTemplate::Count,
+];
+
+const WRITE_TEMPLATES: [TableTemplate; 3] = [
+    TableTemplate::Insert,
+    TableTemplate::Update,
+    TableTemplate::Delete,
+];
+
+/// Table types from `information_schema.tables`, grouped the way the tree shows them.
+const TABLE_CATEGORIES: [(&str, &[&str]); 3] = [
+    ("Tables", &["BASE TABLE", "LOCAL TEMPORARY"]),
+    ("Views", &["VIEW"]),
+    ("Foreign tables", &["FOREIGN"]),
+];
+
+/// Object kinds from `get_schema_objects`, grouped the way the tree shows them.
+const OBJECT_CATEGORIES: [(&str, &[&str]); 5] = [
+    ("Materialized views", &["materialized view"]),
+    ("Functions", &["function", "aggregate"]),
+    ("Procedures", &["procedure"]),
+    ("Sequences", &["sequence"]),
+    ("Types", &["enum", "domain", "composite", "range"]),
+];
+
+fn show_connection_tables(
+    ui: &mut Ui,
+    app: &Rosemary,
+    live: &LiveConnection,
+) -> Option<TreeAction> {
+    let mut action = None;
+    let matches_filter = |name: &str| {
+        app.table_filter.trim().is_empty() || name.to_lowercase().contains(&app.table_filter)
+    };
+    let reveal = app
+        .tree_reveal
+        .as_ref()
+        .filter(|reveal| reveal.key == live.key);
+    let estimates = match &live.table_estimates {
+        Some(Ok(estimates)) if app.show_table_estimates => Some(estimates),
+        None if app.show_table_estimates => {
+            action = Some(TreeAction::LoadEstimates(live.key.clone()));
+            None
+        }
+        Some(Err(e)) if app.show_table_estimates => {
+            ui.colored_label(ui.visuals().error_fg_color, format!("Sizes: {e}"));
+            None
+        }
+        _ => None,
+    };
+
+    let mut schema_table_map: BTreeMap<&str, Vec<&PublicTable>> = live
+        .schemas
+        .iter()
+        .map(|schema| (schema.as_str(), Vec::new()))
+        .collect();
+    for table in &live.tables {
+        if let Some(schema) = &table.table_schema {
+            schema_table_map.entry(schema).or_default().push(table);
+        }
+    }
+
+    for (schema, tables) in &schema_table_map {
+        ui.push_id(schema, |ui| {
+            let reveal = reveal.filter(|reveal| reveal.object.schema == *schema);
+            let filtered_tables: Vec<&PublicTable> = tables
+                .iter()
+                .copied()
+                .filter(|table| matches_filter(table.table_name.as_deref().unwrap_or("NULL")))
+                .collect();
+            let response =
+                egui::CollapsingHeader::new(format!("{} ( {} )", schema, filtered_tables.len()))
+                    .open(reveal.map(|_| true))
+                    .show(ui, |ui| {
+                        for (title, table_types) in TABLE_CATEGORIES {
+                            let category: Vec<&PublicTable> = filtered_tables
+                                .iter()
+                                .copied()
+                                .filter(|table| {
+                                    table_types.contains(&table.table_type.as_deref().unwrap_or(""))
+                                })
+                                .collect();
+                            if category.is_empty() {
+                                continue;
+                            }
+                            let reveal = reveal.filter(|reveal| {
+                                in_table_row(reveal)
+                                    && category.iter().any(|table| {
+                                        table.table_name.as_deref() == Some(reveal.relation())
+                                    })
+                            });
+                            egui::CollapsingHeader::new(format!(
+                                "{} ( {} )",
+                                title,
+                                category.len()
+                            ))
+                            .default_open(title == "Tables")
+                            .open(reveal.map(|_| true))
+                            .show(ui, |ui| {
+                                for table in category {
+                                    let reveal = reveal.filter(|reveal| {
+                                        table.table_name.as_deref() == Some(reveal.relation())
+                                    });
+                                    let estimate = estimates.and_then(|estimates| {
+                                        estimates.get(&(
+                                            schema.to_string(),
+                                            table.table_name.clone().unwrap_or_default(),
+                                        ))
+                                    });
+                                    if let Some(row_action) =
+                                        show_table_row(ui, live, table, estimate, reveal)
+                                    {
+                                        action = Some(row_action);
+                                    }
                                 }
+                            });
+                        }
 
-                                if shift_pressed {
-                                    if !app.split_results_table {
-                                        app.split_results_table = true;
+                        match live.schema_objects.get(*schema) {
+                            Some(Ok(objects)) => {
+                                for (title, kinds) in OBJECT_CATEGORIES {
+                                    let category: Vec<&SchemaObject> = objects
+                                        .iter()
+                                        .filter(|object| {
+                                            kinds.contains(&object.kind.as_str())
+                                                && matches_filter(&object.name)
+                                        })
+                                        .collect();
+                                    if category.is_empty() {
+                                        continue;
                                     }
-                                    *should_execute_secondary = true;
-                                } else {
-                                    *should_execute = true;
+                                    let revealed = |object: &SchemaObject| {
+                                        reveal.is_some_and(|reveal| {
+                                            !in_table_row(reveal)
+                                                && object.kind == reveal.object.kind
+                                                && object.name == reveal.relation()
+                                        })
+                                    };
+                                    egui::CollapsingHeader::new(format!(
+                                        "{} ( {} )",
+                                        title,
+                                        category.len()
+                                    ))
+                                    .open(category.iter().any(|o| revealed(o)).then_some(true))
+                                    .show(ui, |ui| {
+                                        for object in category {
+                                            if let Some(row_action) = show_object_row(
+                                                ui,
+                                                live,
+                                                schema,
+                                                object,
+                                                revealed(object),
+                                            ) {
+                                                action = Some(row_action);
+                                            }
+                                        }
+                                    });
                                 }
                             }
+                            Some(Err(e)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, e);
+                            }
+                            None => {
+                                ui.spinner();
+                                action = Some(TreeAction::LoadObjects {
+                                    key: live.key.clone(),
+                                    schema: schema.to_string(),
+                                });
+                            }
                         }
                     });
+            response.header_response.context_menu(|ui| {
+                if ui.button("New table…").clicked() {
+                    action = Some(TreeAction::DesignTable {
+                        key: live.key.clone(),
+                        schema: schema.to_string(),
+                        table: None,
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("ER diagram").clicked() {
+                    action = Some(TreeAction::ErDiagram {
+                        key: live.key.clone(),
+                        schema: schema.to_string(),
+                        table: None,
+                    });
+                    ui.close_menu();
+                }
+            });
+        });
+    }
+
+    ui.collapsing("Extensions", |ui| match &live.extensions {
+        Some(Ok(extensions)) => {
+            for extension in extensions {
+                if let Some(row_action) = show_object_row(ui, live, "", extension, false) {
+                    action = Some(row_action);
+                }
+            }
+        }
+        Some(Err(e)) => {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        None => {
+            ui.spinner();
+            action = Some(TreeAction::LoadExtensions(live.key.clone()));
+        }
+    });
+
+    action
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RevealState {
+    Listed,
+    /// The tables, schema objects or table details it would be in are loading.
+    Loading,
+    Missing,
+}
+
+/// Whether the tree lists an object being revealed, going by the loaded
+/// catalog rather than by what has been drawn.
+fn reveal_state(live: &LiveConnection, reveal: &TreeReveal, filter: &str) -> RevealState {
+    let object = &reveal.object;
+    let relation = reveal.relation();
+    if !filter.trim().is_empty() && !relation.to_lowercase().contains(filter) {
+        return RevealState::Missing;
+    }
+
+    if !in_table_row(reveal) {
+        return match live.schema_objects.get(&object.schema) {
+            Some(Ok(objects)) => {
+                let listed = OBJECT_CATEGORIES
+                    .iter()
+                    .any(|(_, kinds)| kinds.contains(&object.kind.as_str()))
+                    && objects
+                        .iter()
+                        .any(|listed| listed.kind == object.kind && listed.name == relation);
+                if listed {
+                    RevealState::Listed
+                } else {
+                    RevealState::Missing
+                }
+            }
+            Some(Err(_)) => RevealState::Missing,
+            None => RevealState::Loading,
+        };
+    }
+
+    if live.should_fetch_table_list {
+        return RevealState::Loading;
+    }
+    let listed = live.tables.iter().any(|table| {
+        table.table_schema.as_deref() == Some(object.schema.as_str())
+            && table.table_name.as_deref() == Some(relation)
+            && TABLE_CATEGORIES
+                .iter()
+                .any(|(_, types)| types.contains(&table.table_type.as_deref().unwrap_or("")))
+    });
+    if !listed {
+        return RevealState::Missing;
+    }
+    if object.table.is_none() {
+        return RevealState::Listed;
+    }
+    match live
+        .table_details
+        .get(&(object.schema.clone(), relation.to_string()))
+    {
+        Some(Ok(details)) => {
+            let listed = match object.kind.as_str() {
+                "column" => details
+                    .columns
+                    .iter()
+                    .any(|column| column.name == object.name),
+                "index" => details
+                    .indexes
+                    .iter()
+                    .any(|index| index.name == object.name),
+                _ => false,
+            };
+            if listed {
+                RevealState::Listed
+            } else {
+                RevealState::Missing
+            }
+        }
+        Some(Err(_)) => RevealState::Missing,
+        None => RevealState::Loading,
+    }
+}
+
+/// Whether an object is shown under a table row: a table, view, column or index.
+fn in_table_row(reveal: &TreeReveal) -> bool {
+    matches!(
+        reveal.object.kind.as_str(),
+        "table" | "view" | "foreign table" | "column" | "index"
+    )
+}
+
+fn show_table_row(
+    ui: &mut Ui,
+    live: &LiveConnection,
+    table: &PublicTable,
+    estimate: Option<&TableEstimate>,
+    reveal: Option<&TreeReveal>,
+) -> Option<TreeAction> {
+    let mut action = None;
+    let table_name = table.table_name.as_deref().unwrap_or("NULL");
+    let table_schema = table.table_schema.as_deref().unwrap_or("NULL");
+    let table_type = table.table_type.as_deref().unwrap_or("NULL");
+    let button = egui::Button::new(table_name);
+
+    let name = (table_schema.to_string(), table_name.to_string());
+    let id = ui.make_persistent_id(table_name);
+    let reveal_part = reveal.filter(|reveal| reveal.object.table.is_some());
+    let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, false);
+    if reveal_part.is_some() {
+        state.set_open(true);
+    }
+    state
+        .show_header(ui, |ui| {
+            let response = ui
+                .with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    match live.table_sizes.get(&name) {
+                        Some(Ok(size)) => {
+                            ui.weak(format!("{} rows, {}", size.row_count, size.total_size));
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                                .on_hover_text(e);
+                        }
+                        None => {
+                            if let Some(estimate) = estimate {
+                                let rows = estimate
+                                    .estimated_rows
+                                    .map_or(String::from("?"), approximate_count);
+                                ui.weak(format!("~{rows} rows, {}", estimate.total_size))
+                                    .on_hover_text("Estimated from the planner's statistics");
+                            }
+                        }
+                    }
+                    ui.add_sized([ui.available_width(), 0.0], button)
+                })
+                .inner;
+            if reveal.is_some() && reveal_part.is_none() {
+                response.scroll_to_me(Some(egui::Align::Center));
+                action = Some(TreeAction::Revealed);
+            }
+            if response.clicked() {
+                action = Some(TreeAction::Query {
+                    key: live.key.clone(),
+                    schema: table_schema.to_string(),
+                    table: table_name.to_string(),
+                });
+            }
+            let kind = match table_type {
+                "VIEW" => "view",
+                "FOREIGN" => "foreign table",
+                _ => "table",
+            };
+            response.context_menu(|ui| {
+                let template_action = |template| TreeAction::Template {
+                    key: live.key.clone(),
+                    schema: table_schema.to_string(),
+                    table: table_name.to_string(),
+                    kind: kind.to_string(),
+                    template,
+                };
+                for template in READ_TEMPLATES.into_iter().chain(WRITE_TEMPLATES) {
+                    if template == TableTemplate::Insert {
+                        ui.separator();
+                    }
+                    if ui.button(template.label()).clicked() {
+                        action = Some(template_action(template));
+                        ui.close_menu();
+                    }
+                }
+                ui.separator();
+                if kind == "table" && ui.button("Open data editor").clicked() {
+                    action = Some(TreeAction::OpenDataEditor {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: table_name.to_string(),
+                    });
+                    ui.close_menu();
+                }
+                if kind == "table" && ui.button("Design table…").clicked() {
+                    action = Some(TreeAction::DesignTable {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: Some(table_name.to_string()),
+                    });
+                    ui.close_menu();
+                }
+                if kind == "table" && ui.button("Show in ER diagram").clicked() {
+                    action = Some(TreeAction::ErDiagram {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: Some(table_name.to_string()),
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("Compare data…").clicked() {
+                    action = Some(TreeAction::CompareData {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: table_name.to_string(),
+                    });
+                    ui.close_menu();
+                }
+                if kind == "table" && ui.button("Details").clicked() {
+                    action = Some(TreeAction::TableInfo {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: table_name.to_string(),
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("Row count and size").clicked() {
+                    action = Some(TreeAction::TableSize {
+                        key: live.key.clone(),
+                        schema: table_schema.to_string(),
+                        table: table_name.to_string(),
+                    });
+                    ui.close_menu();
+                }
+                ui.separator();
+                let object = ObjectRef::Relation(table_schema.to_string(), table_name.to_string());
+                definition_menu(ui, &live.key, kind, object, &mut action);
+                ui.separator();
+                if kind == "table" && ui.button(TableTemplate::Truncate.label()).clicked() {
+                    action = Some(template_action(TableTemplate::Truncate));
+                    ui.close_menu();
+                }
+                if ui.button(TableTemplate::Drop.label()).clicked() {
+                    action = Some(template_action(TableTemplate::Drop));
+                    ui.close_menu();
+                }
+            });
+        })
+        .body(|ui| match live.table_details.get(&name) {
+            Some(Ok(details)) => {
+                if let Some(details_action) = show_table_details(ui, live, details, reveal_part) {
+                    action = Some(details_action);
+                }
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {
+                ui.spinner();
+                action = Some(TreeAction::LoadDetails {
+                    key: live.key.clone(),
+                    schema: name.0.clone(),
+                    table: name.1.clone(),
                 });
             }
         });
+
+    action
+}
+
+fn show_object_row(
+    ui: &mut Ui,
+    live: &LiveConnection,
+    schema: &str,
+    object: &SchemaObject,
+    reveal: bool,
+) -> Option<TreeAction> {
+    let mut action = None;
+    let kind = object.kind.as_str();
+    let definition = |object: &SchemaObject| TreeAction::Definition {
+        key: live.key.clone(),
+        kind: object.kind.clone(),
+        object: ObjectRef::Oid(object.oid, object.name.clone()),
+    };
+
+    let response = ui
+        .horizontal(|ui| {
+            let response = ui.selectable_label(false, &object.name);
+            if matches!(kind, "enum" | "domain" | "composite" | "range") {
+                ui.weak(kind);
+            }
+            if let Some(detail) = &object.detail {
+                ui.weak(detail);
+            }
+            response
+        })
+        .inner;
+    let response = match &object.detail {
+        Some(detail) => response.on_hover_text(format!("{} {}", object.name, detail)),
+        None => response,
+    };
+
+    if reveal {
+        response.scroll_to_me(Some(egui::Align::Center));
+        action = Some(TreeAction::Revealed);
+    }
+    if response.clicked() {
+        action = Some(definition(object));
+    }
+    response.context_menu(|ui| {
+        let object_ref = ObjectRef::Oid(object.oid, object.name.clone());
+        definition_menu(ui, &live.key, kind, object_ref, &mut action);
+        if kind == "materialized view" {
+            let name = qualified_name(schema, &object.name);
+            if ui.button("Refresh").clicked() {
+                action = Some(TreeAction::Run {
+                    key: live.key.clone(),
+                    sql: format!("REFRESH MATERIALIZED VIEW {name}"),
+                });
+                ui.close_menu();
+            }
+            if ui
+                .button("Refresh concurrently")
+                .on_hover_text("Needs a unique index, and doesn't block readers")
+                .clicked()
+            {
+                action = Some(TreeAction::Run {
+                    key: live.key.clone(),
+                    sql: format!("REFRESH MATERIALIZED VIEW CONCURRENTLY {name}"),
+                });
+                ui.close_menu();
+            }
+        }
+    });
+
+    action
+}
+
+/// The "Show DDL" and "Copy CREATE statement" entries of a context menu.
+fn definition_menu(
+    ui: &mut Ui,
+    key: &str,
+    kind: &str,
+    object: ObjectRef,
+    action: &mut Option<TreeAction>,
+) {
+    if ui.button("Show DDL").clicked() {
+        *action = Some(TreeAction::Definition {
+            key: key.to_string(),
+            kind: kind.to_string(),
+            object: object.clone(),
+        });
+        ui.close_menu();
+    }
+    if ui.button("Copy CREATE statement").clicked() {
+        *action = Some(TreeAction::CopyDefinition {
+            key: key.to_string(),
+            kind: kind.to_string(),
+            object,
+        });
+        ui.close_menu();
+    }
+}
+
+fn column_hover(column: &TableColumn) -> String {
+    let mut lines = vec![format!("{} {}", column.name, column.data_type)];
+    match column.identity.as_str() {
+        "a" => lines.push(String::from("GENERATED ALWAYS AS IDENTITY")),
+        "d" => lines.push(String::from("GENERATED BY DEFAULT AS IDENTITY")),
+        _ => {}
+    }
+    if let Some(default) = &column.default_value {
+        if column.generated == "s" {
+            lines.push(format!("GENERATED ALWAYS AS ({default}) STORED"));
+        } else {
+            lines.push(format!("DEFAULT {default}"));
+        }
+    }
+    if let Some(comment) = &column.comment {
+        lines.push(String::new());
+        lines.push(comment.clone());
+    }
+    lines.join("\n")
+}
+
+/// Lists the columns, indexes, constraints, triggers, policies and partitions
+/// of an expanded table.
+fn show_table_details(
+    ui: &mut Ui,
+    live: &LiveConnection,
+    details: &TableDetails,
+    reveal: Option<&TreeReveal>,
+) -> Option<TreeAction> {
+    let mut action = None;
+    let revealed = |kind: &str, name: &str| {
+        reveal.is_some_and(|reveal| reveal.object.kind == kind && reveal.object.name == name)
+    };
+    let reveal_in = |kind: &str| reveal.is_some_and(|reveal| reveal.object.kind == kind);
+
+    egui::CollapsingHeader::new(format!("Columns ( {} )", details.columns.len()))
+        .open(reveal_in("column").then_some(true))
+        .show(ui, |ui| {
+            for column in &details.columns {
+                let response = ui
+                    .horizontal(|ui| {
+                        ui.label(&column.name);
+                        ui.weak(&column.data_type);
+                        if column.not_null {
+                            ui.weak("not null");
+                        }
+                    })
+                    .response
+                    .on_hover_text(column_hover(column));
+                if revealed("column", &column.name) {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                    action = Some(TreeAction::Revealed);
+                }
+            }
+        });
+
+    if !details.indexes.is_empty() {
+        egui::CollapsingHeader::new(format!("Indexes ( {} )", details.indexes.len()))
+            .open(reveal_in("index").then_some(true))
+            .show(ui, |ui| {
+                for index in &details.indexes {
+                    let response = ui
+                        .horizontal(|ui| {
+                            ui.label(&index.name);
+                            if index.is_primary {
+                                ui.weak("primary");
+                            } else if index.is_unique {
+                                ui.weak("unique");
+                            }
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text(&index.definition);
+                    if revealed("index", &index.name) {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                        action = Some(TreeAction::Revealed);
+                    }
+                    response.context_menu(|ui| {
+                        let object = ObjectRef::Oid(index.oid, index.name.clone());
+                        definition_menu(ui, &live.key, "index", object, &mut action);
+                    });
+                }
+            });
+    }
+
+    if !details.constraints.is_empty() {
+        ui.collapsing(
+            format!("Constraints ( {} )", details.constraints.len()),
+            |ui| {
+                for constraint in &details.constraints {
+                    ui.horizontal(|ui| {
+                        ui.label(&constraint.name);
+                        ui.weak(constraint.kind_label());
+                    })
+                    .response
+                    .on_hover_text(&constraint.definition);
+                }
+            },
+        );
+    }
+
+    if !details.triggers.is_empty() {
+        ui.collapsing(format!("Triggers ( {} )", details.triggers.len()), |ui| {
+            for trigger in &details.triggers {
+                ui.horizontal(|ui| {
+                    ui.label(&trigger.name);
+                    if trigger.enabled == "D" {
+                        ui.weak("disabled");
+                    }
+                })
+                .response
+                .interact(egui::Sense::click())
+                .on_hover_text(&trigger.definition)
+                .context_menu(|ui| {
+                    let object = ObjectRef::Oid(trigger.oid, trigger.name.clone());
+                    definition_menu(ui, &live.key, "trigger", object, &mut action);
+                });
+            }
+        });
+    }
+
+    if !details.policies.is_empty() || details.row_security {
+        let title = if details.row_security {
+            format!("Policies ( {} ), RLS enabled", details.policies.len())
+        } else {
+            format!("Policies ( {} ), RLS disabled", details.policies.len())
+        };
+        ui.collapsing(title, |ui| {
+            for policy in &details.policies {
+                let mut hover = format!(
+                    "{} FOR {} TO {}",
+                    policy.permissive,
+                    policy.command,
+                    policy.roles.join(", ")
+                );
+                if let Some(using) = &policy.using_expression {
+                    hover += &format!("\nUSING ({using})");
+                }
+                if let Some(check) = &policy.check_expression {
+                    hover += &format!("\nWITH CHECK ({check})");
+                }
+                ui.horizontal(|ui| {
+                    ui.label(&policy.name);
+                    ui.weak(&policy.command);
+                })
+                .response
+                .on_hover_text(hover);
+            }
+        });
+    }
+
+    if let Some(partition_key) = &details.partition_key {
+        ui.collapsing(
+            format!("Partitions ( {} )", details.partitions.len()),
+            |ui| {
+                ui.weak(format!("PARTITION BY {partition_key}"));
+                for partition in &details.partitions {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.{}", partition.schema, partition.name));
+                        if let Some(bound) = &partition.bound {
+                            ui.weak(bound);
+                        }
+                    });
+                }
+            },
+        );
+    }
+
+    action
 }
@@ -0,0 +1,223 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+/// Saved connection passwords, encrypted at rest with a key derived from a
+/// master password. Secrets are keyed by the connection id.
+pub struct CredentialVault {
+    path: PathBuf,
+    key: Option<Key>,
+    salt: Vec<u8>,
+    secrets: HashMap<String, String>,
+}
+
+impl Default for CredentialVault {
+    fn default() -> Self {
+        Self::new(
+            eframe::storage_dir("rosemary")
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("credentials.vault"),
+        )
+    }
+}
+
+fn derive_key(master_password: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(master_password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive vault key: {e}"))?;
+    Ok(key)
+}
+
+impl CredentialVault {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            key: None,
+            salt: Vec::new(),
+            secrets: HashMap::new(),
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.key.is_some()
+    }
+
+    pub fn create(&mut self, master_password: &str) -> Result<(), String> {
+        if master_password.is_empty() {
+            return Err(String::from("The master password can't be empty"));
+        }
+        let mut salt = vec![0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        self.key = Some(derive_key(master_password, &salt)?);
+        self.salt = salt;
+        self.secrets.clear();
+        self.save()
+    }
+
+    pub fn unlock(&mut self, master_password: &str) -> Result<(), String> {
+        let contents =
+            fs::read(&self.path).map_err(|e| format!("Failed to read the vault: {e}"))?;
+        let file: VaultFile = serde_json::from_slice(&contents)
+            .map_err(|e| format!("The vault file is corrupt: {e}"))?;
+        if file.version != VAULT_VERSION {
+            return Err(format!("Unsupported vault version {}", file.version));
+        }
+        if file.nonce.len() != 12 {
+            return Err(String::from("The vault file is corrupt: bad nonce"));
+        }
+
+        let key = derive_key(master_password, &file.salt)?;
+        let plaintext = ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(&file.nonce), file.ciphertext.as_slice())
+            .map_err(|_| String::from("Wrong master password"))?;
+
+        self.secrets = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("The vault contents are corrupt: {e}"))?;
+        self.salt = file.salt;
+        self.key = Some(key);
+        Ok(())
+    }
+
+    pub fn lock(&mut self) {
+        self.key = None;
+        self.secrets.clear();
+    }
+
+    pub fn get(&self, connection_id: &str) -> Option<&str> {
+        self.secrets.get(connection_id).map(String::as_str)
+    }
+
+    pub fn set(&mut self, connection_id: &str, password: &str) -> Result<(), String> {
+        self.secrets
+            .insert(connection_id.to_string(), password.to_string());
+        self.save()
+    }
+
+    pub fn remove(&mut self, connection_id: &str) -> Result<(), String> {
+        if self.secrets.remove(connection_id).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(key) = &self.key else {
+            return Err(String::from("The vault is locked"));
+        };
+
+        let plaintext = serde_json::to_vec(&self.secrets)
+            .map_err(|e| format!("Failed to serialize the vault: {e}"))?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = ChaCha20Poly1305::new(key)
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|e| format!("Failed to encrypt the vault: {e}"))?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            salt: self.salt.clone(),
+            nonce: nonce.to_vec(),
+            ciphertext,
+        };
+        let contents =
+            serde_json::to_vec(&file).map_err(|e| format!("Failed to serialize the vault: {e}"))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to save the vault: {e}"))?;
+        }
+        // Write to a temporary file first so a crash can't leave a half written vault.
+        let tmp_path = self.path.with_extension("vault.tmp");
+        fs::write(&tmp_path, contents).map_err(|e| format!("Failed to save the vault: {e}"))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600));
+        }
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to save the vault: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_vault(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rosemary-vault-{name}.vault"));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn unlocks_what_it_created() {
+        let path = temp_vault("round-trip");
+        let mut vault = CredentialVault::new(path.clone());
+        assert!(!vault.exists());
+        vault.create("master").unwrap();
+        vault.set("a", "secret").unwrap();
+        vault.lock();
+        assert!(!vault.is_unlocked());
+        assert_eq!(vault.get("a"), None);
+
+        vault.unlock("master").unwrap();
+        assert_eq!(vault.get("a"), Some("secret"));
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_master_password_leaves_the_file_alone() {
+        let path = temp_vault("wrong-password");
+        let mut vault = CredentialVault::new(path.clone());
+        vault.create("master").unwrap();
+        vault.set("a", "secret").unwrap();
+        let before = fs::read(&path).unwrap();
+
+        let mut other = CredentialVault::new(path.clone());
+        assert_eq!(other.unlock("wrong").unwrap_err(), "Wrong master password");
+        assert!(!other.is_unlocked());
+        assert!(other.set("b", "x").is_err());
+        assert_eq!(fs::read(&path).unwrap(), before);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn changes_persist_across_reopening() {
+        let path = temp_vault("persist");
+        let mut vault = CredentialVault::new(path.clone());
+        vault.create("master").unwrap();
+        vault.set("a", "one").unwrap();
+        vault.set("b", "two").unwrap();
+        vault.remove("a").unwrap();
+
+        let mut reopened = CredentialVault::new(path.clone());
+        reopened.unlock("master").unwrap();
+        assert_eq!(reopened.get("a"), None);
+        assert_eq!(reopened.get("b"), Some("two"));
+        reopened.set("b", "three").unwrap();
+
+        let mut again = CredentialVault::new(path.clone());
+        again.unlock("master").unwrap();
+        assert_eq!(again.get("b"), Some("three"));
+        fs::remove_file(&path).unwrap();
+    }
+}
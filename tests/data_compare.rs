@@ -0,0 +1,139 @@
+//! Creates two databases on the server `DATABASE_URL` points at, dropping
+//! them first if they exist, and compares their data. Run against a scratch
+//! server with `cargo test --test data_compare -- --ignored`.
+
+use std::{env, str::FromStr};
+
+use rosemary::data_diff::{compare_data, DataCompareSpec, DataSource, CHUNK_SIZE};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    Pool, Postgres,
+};
+
+async fn create_database(name: &str, setup: &str) -> Pool<Postgres> {
+    let url = env::var("DATABASE_URL").expect("DATABASE_URL is needed for this test");
+    let server = PgPoolOptions::new().connect(&url).await.unwrap();
+    for sql in [
+        format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)"),
+        format!("CREATE DATABASE {name}"),
+    ] {
+        sqlx::raw_sql(&sql).execute(&server).await.unwrap();
+    }
+    let options = PgConnectOptions::from_str(&url).unwrap().database(name);
+    let db = PgPoolOptions::new().connect_with(options).await.unwrap();
+    sqlx::raw_sql(setup).execute(&db).await.unwrap();
+    db
+}
+
+fn table(name: &str) -> DataSource {
+    DataSource::Table {
+        schema: String::from("public"),
+        table: name.to_string(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a scratch Postgres server"]
+async fn compares_and_syncs_tables_across_databases() {
+    // Enough rows for several chunks, with differences on both sides of the
+    // chunk boundaries.
+    let rows = CHUNK_SIZE * 2 + 500;
+    let source = create_database(
+        "rosemary_test_compare_source",
+        &format!(
+            "CREATE TABLE items (
+               tenant int,
+               id int,
+               name text,
+               price numeric,
+               tags text[],
+               PRIMARY KEY (tenant, id)
+             );
+             INSERT INTO items
+             SELECT i % 3, i, 'item ' || i, i * 1.5, ARRAY['a', 'it''s']
+             FROM generate_series(1, {rows}) i;"
+        ),
+    )
+    .await;
+    let target = create_database(
+        "rosemary_test_compare_target",
+        &format!(
+            "CREATE TABLE items (
+               tenant int,
+               id int,
+               name text,
+               price numeric,
+               tags text[],
+               PRIMARY KEY (tenant, id)
+             );
+             INSERT INTO items
+             SELECT i % 3, i, 'item ' || i, i * 1.5, ARRAY['a', 'it''s']
+             FROM generate_series(1, {rows}) i;
+             DELETE FROM items WHERE id IN (1, 1000, 1001, 2500);
+             UPDATE items SET name = NULL WHERE id = 7;
+             UPDATE items SET price = 0, tags = '{{}}' WHERE id IN (999, 2001);
+             INSERT INTO items VALUES (0, 5000, 'extra', 1, NULL), (2, -1, 'it''s', NULL, NULL);"
+        ),
+    )
+    .await;
+
+    let spec = DataCompareSpec {
+        source: table("items"),
+        target: table("items"),
+        key_columns: Vec::new(),
+    };
+    let diff = compare_data(&source, &target, &spec, |_| {}).await.unwrap();
+    assert_eq!(diff.key, [0, 1]);
+    assert_eq!(
+        (diff.missing, diff.extra, diff.changed, diff.same),
+        (4, 2, 3, rows - 7)
+    );
+
+    let script = diff.sync_script("public.items");
+    sqlx::raw_sql(&script.join("\n"))
+        .execute(&target)
+        .await
+        .unwrap();
+    let diff = compare_data(&source, &target, &spec, |_| {}).await.unwrap();
+    assert!(diff.is_empty(), "{:?}", diff.rows);
+    assert_eq!(diff.same, rows);
+}
+
+#[tokio::test]
+#[ignore = "needs a scratch Postgres server"]
+async fn compares_queries_on_chosen_key_columns() {
+    let source = create_database(
+        "rosemary_test_compare_query_source",
+        "CREATE TABLE people (email text, name text);
+         INSERT INTO people VALUES ('a@example.com', 'Ann'), ('b@example.com', 'Bob');",
+    )
+    .await;
+    let target = create_database(
+        "rosemary_test_compare_query_target",
+        "CREATE TABLE contacts (mail text, full_name text, phone text);
+         INSERT INTO contacts VALUES ('a@example.com', 'Anne', '1'), ('c@example.com', 'Cy', '2');",
+    )
+    .await;
+
+    let spec = DataCompareSpec {
+        source: DataSource::Query(String::from("SELECT email, name FROM people;")),
+        target: DataSource::Query(String::from(
+            "SELECT mail AS email, full_name AS name, phone FROM contacts",
+        )),
+        key_columns: vec![String::from("email")],
+    };
+    let diff = compare_data(&source, &target, &spec, |_| {}).await.unwrap();
+    assert_eq!(diff.columns, ["email", "name"]);
+    assert_eq!(
+        (diff.missing, diff.extra, diff.changed, diff.same),
+        (1, 1, 1, 0)
+    );
+
+    let without_key = DataCompareSpec {
+        key_columns: Vec::new(),
+        ..spec
+    };
+    assert!(compare_data(&source, &target, &without_key, |_| {})
+        .await
+        .is_err());
+}
@@ -0,0 +1,77 @@
+//! Needs an sshd that accepts key authentication without a prompt. Run with
+//! `cargo test --test ssh_tunnel -- --ignored`, pointing these at it:
+//!
+//! - `ROSEMARY_TEST_SSH_HOST` (default `localhost`)
+//! - `ROSEMARY_TEST_SSH_PORT`, `ROSEMARY_TEST_SSH_USER`
+//! - `ROSEMARY_TEST_SSH_IDENTITY`, `ROSEMARY_TEST_SSH_KNOWN_HOSTS`
+
+use std::{
+    env,
+    io::{Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use rosemary::ssh_tunnel::{SshTunnel, SshTunnelConfig};
+
+fn config_from_env() -> SshTunnelConfig {
+    let var = |name: &str| env::var(name).unwrap_or_default();
+    SshTunnelConfig {
+        host: env::var("ROSEMARY_TEST_SSH_HOST").unwrap_or_else(|_| String::from("localhost")),
+        port: var("ROSEMARY_TEST_SSH_PORT"),
+        user: var("ROSEMARY_TEST_SSH_USER"),
+        identity_file: var("ROSEMARY_TEST_SSH_IDENTITY"),
+        known_hosts_file: var("ROSEMARY_TEST_SSH_KNOWN_HOSTS"),
+    }
+}
+
+#[test]
+#[ignore = "needs a local sshd"]
+fn forwards_connections_to_the_target() {
+    // Stands in for the database: echoes what the tunnel sends back.
+    let target = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let target_port = target.local_addr().unwrap().port();
+    let echo = thread::spawn(move || {
+        let (mut stream, _) = target.accept().unwrap();
+        let mut buffer = [0; 4];
+        stream.read_exact(&mut buffer).unwrap();
+        stream.write_all(&buffer).unwrap();
+    });
+
+    let tunnel = SshTunnel::open(
+        &config_from_env(),
+        &[(String::from("127.0.0.1"), target_port)],
+    )
+    .unwrap();
+    let local_port = tunnel.local_ports()[0];
+    assert_ne!(local_port, target_port);
+
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, local_port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream.write_all(b"ping").unwrap();
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).unwrap();
+    assert_eq!(&reply, b"ping");
+    echo.join().unwrap();
+
+    drop(tunnel);
+    // The local port is released once the tunnel is dropped.
+    assert!(TcpStream::connect((Ipv4Addr::LOCALHOST, local_port)).is_err());
+}
+
+#[test]
+fn rejects_hosts_and_users_read_as_options() {
+    let config = |host: &str, user: &str| SshTunnelConfig {
+        host: host.to_string(),
+        user: user.to_string(),
+        ..Default::default()
+    };
+    assert!(config("bastion.example.com", "deploy").validate().is_ok());
+    assert!(config("-oProxyCommand=sh -c id", "").validate().is_err());
+    assert!(config("bastion", " -oProxyCommand=id").validate().is_err());
+    assert!(config(" ", "deploy").validate().is_err());
+    assert!(SshTunnel::open(&config("-oProxyCommand=id", ""), &[]).is_err());
+}